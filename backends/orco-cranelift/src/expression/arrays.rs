@@ -0,0 +1,225 @@
+use super::*;
+
+impl crate::Object {
+    /// Build an address of an element, returns it with the type of the element
+    pub fn build_index_address(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        index: &orco::expression::Index,
+    ) -> (cl::Value, orco::Type) {
+        let pointer_type = self.object.target_config().pointer_type();
+        let container = self.substitute(
+            index
+                .container
+                .as_ref()
+                .expect("Index has to be resolved by type inference"),
+        );
+        let (address, len, element) = match &container {
+            orco::Type::Array(element, len) => {
+                let address = self.build_place(builder, &index.value);
+                let len = builder.ins().iconst(pointer_type, *len as i64);
+                (address, Some(len), element.as_ref())
+            }
+            // Slices are pairs of a pointer and a length, in memory
+            orco::Type::Slice(element, _) => {
+                let slice = self
+                    .build_expression(builder, &index.value)
+                    .expect("Slice has to have a value");
+                let flags = cl::MemFlags::trusted();
+                let address = builder.ins().load(pointer_type, flags, slice, 0);
+                let len =
+                    builder
+                        .ins()
+                        .load(pointer_type, flags, slice, self.target.pointer_size as i32);
+                (address, Some(len), element.as_ref())
+            }
+            orco::Type::Pointer(element, _) => {
+                let address = self
+                    .build_expression(builder, &index.value)
+                    .expect("Pointer has to have a value");
+                (address, None, element.as_ref())
+            }
+            _ => unreachable!(),
+        };
+
+        let value = self
+            .build_expression(builder, &index.index)
+            .expect("Index has to have a value");
+        let index_type = index
+            .index_type
+            .as_ref()
+            .expect("Index has to be resolved by type inference");
+        let value = self.extend_to_pointer(builder, value, index_type);
+        if let Some(len) = len.filter(|_| index.checked) {
+            // Negative indices wrap around and fail the check too
+            let out_of_bounds =
+                builder
+                    .ins()
+                    .icmp(cl::IntCC::UnsignedGreaterThanOrEqual, value, len);
+            builder
+                .ins()
+                .trapnz(out_of_bounds, cl::TrapCode::HeapOutOfBounds);
+        }
+        let offset = builder
+            .ins()
+            .imul_imm(value, self.layout(element).size as i64);
+        (builder.ins().iadd(address, offset), element.clone())
+    }
+
+    /// Read an element of an array, a slice or a value behind a pointer
+    pub fn build_index(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        index: &orco::expression::Index,
+    ) -> Option<cl::Value> {
+        let (address, r#type) = self.build_index_address(builder, index);
+        self.load(builder, &r#type, address)
+    }
+
+    /// Construct an array in a new stack slot, returns it's address.
+    /// Arrays of literals are copied from the constant pool
+    pub fn build_array(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        signature: &orco::types::FunctionSignature,
+        args: &[orco::Expression],
+    ) -> Option<cl::Value> {
+        let orco::Type::Array(element, _) = signature.return_type.as_ref() else {
+            unreachable!()
+        };
+        let layout = self.layout(&signature.return_type);
+        let element_size = self.layout(element).size;
+        let pointer_type = self.object.target_config().pointer_type();
+        let slot = self.stack_slot(builder, &signature.return_type);
+        let address = builder.ins().stack_addr(pointer_type, slot, 0);
+
+        let constant = args
+            .iter()
+            .map(|arg| match arg {
+                orco::Expression::Literal(literal) => literal_bytes(literal, element_size),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        if let Some(constant) = constant {
+            let constant = self.constant(builder, &constant.concat(), layout.alignment);
+            builder.emit_small_memory_copy(
+                self.object.target_config(),
+                address,
+                constant,
+                layout.size,
+                layout.alignment as _,
+                layout.alignment as _,
+                true,
+                cl::MemFlags::trusted(),
+            );
+            return Some(address);
+        }
+
+        for (index, arg) in args.iter().enumerate() {
+            if let Some(value) = self.build_expression(builder, arg) {
+                let offset = index as u64 * element_size;
+                let address = builder.ins().stack_addr(pointer_type, slot, offset as _);
+                self.store(builder, element, value, address);
+            }
+        }
+        Some(address)
+    }
+
+    /// Make a slice from a pointer and a length, returns the address of the pair
+    pub fn build_slice(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        signature: &orco::types::FunctionSignature,
+        args: &[orco::Expression],
+    ) -> Option<cl::Value> {
+        let pointer_type = self.object.target_config().pointer_type();
+        let pointer = self
+            .build_expression(builder, &args[0])
+            .expect("Pointer has to have a value");
+        let len = self
+            .build_expression(builder, &args[1])
+            .expect("Length has to have a value");
+        let len = self.extend_to_pointer(builder, len, &signature.parameters[1].1);
+
+        let layout = self.layout(&signature.return_type);
+        let slot = self.stack_slot(builder, &signature.return_type);
+        builder
+            .ins()
+            .stack_store(pointer, slot, layout.field_offsets[0] as i32);
+        builder
+            .ins()
+            .stack_store(len, slot, layout.field_offsets[1] as i32);
+        Some(builder.ins().stack_addr(pointer_type, slot, 0))
+    }
+
+    /// Read the length of a slice
+    pub fn build_length(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        signature: &orco::types::FunctionSignature,
+        args: &[orco::Expression],
+    ) -> Option<cl::Value> {
+        let pointer_type = self.object.target_config().pointer_type();
+        let slice = self
+            .build_expression(builder, &args[0])
+            .expect("Slice has to have a value");
+        let len = builder.ins().load(
+            pointer_type,
+            cl::MemFlags::trusted(),
+            slice,
+            self.target.pointer_size as i32,
+        );
+        let [param] = self.convert_type(&signature.return_type)[..] else {
+            return None;
+        };
+        Some(if param.value_type.bits() < pointer_type.bits() {
+            builder.ins().ireduce(param.value_type, len)
+        } else if param.value_type.bits() > pointer_type.bits() {
+            builder.ins().uextend(param.value_type, len)
+        } else {
+            len
+        })
+    }
+
+    /// Put bytes into the constant pool, returns their address
+    pub fn constant(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        bytes: &[u8],
+        alignment: u64,
+    ) -> cl::Value {
+        let (id, offset) = self.add_constant(bytes, alignment);
+        let global = self.object.declare_data_in_func(id, builder.func);
+        let pointer_type = self.object.target_config().pointer_type();
+        let address = builder.ins().global_value(pointer_type, global);
+        builder.ins().iadd_imm(address, offset as i64)
+    }
+
+    /// Append bytes to the constant pool, returns the pool and the offset of the bytes in it
+    pub fn add_constant(&mut self, bytes: &[u8], alignment: u64) -> (cl::DataId, usize) {
+        let (id, data) = self.constant_data.get_or_insert_with(|| {
+            (
+                self.object.declare_anonymous_data(false, false).unwrap(),
+                Vec::new(),
+            )
+        });
+        let offset = data.len().next_multiple_of(alignment as usize);
+        data.resize(offset, 0);
+        data.extend_from_slice(bytes);
+        (*id, offset)
+    }
+}
+
+/// In-memory representation of a literal, target is little-endian
+fn literal_bytes(literal: &orco::expression::Literal, size: u64) -> Option<Vec<u8>> {
+    use orco::expression::Literal;
+    let size = size as usize;
+    Some(match literal {
+        Literal::Integer(value, _) => value.to_le_bytes()[..size].to_vec(),
+        Literal::Float(value, _) if size == 4 => (*value as f32).to_le_bytes().to_vec(),
+        Literal::Float(value, _) if size == 8 => value.to_le_bytes().to_vec(),
+        Literal::Float(..) => return None,
+        Literal::Bool(value) => vec![*value as u8],
+        Literal::Null(_) => vec![0; size],
+    })
+}
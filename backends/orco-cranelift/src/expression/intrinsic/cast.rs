@@ -0,0 +1,95 @@
+use crate::cl;
+use cranelift::prelude::InstBuilder;
+use orco::type_inference::intrinsics::Cast;
+
+impl crate::Object {
+    /// Convert a value from one type to another, see [Cast]
+    pub fn build_cast(
+        &self,
+        builder: &mut cl::FunctionBuilder,
+        cast: Cast,
+        from: &orco::Type,
+        to: &orco::Type,
+        value: cl::Value,
+    ) -> cl::Value {
+        let [param] = self.convert_type(to)[..] else {
+            panic!("Can't cast to {}", to);
+        };
+        let to_type = param.value_type;
+        let from_type = builder.func.dfg.value_type(value);
+        let signed = matches!(from, orco::Type::Integer(_));
+
+        match cast {
+            Cast::Convert => {
+                unreachable!("Conversions have to be resolved by type inference")
+            }
+            _ if from_type == to_type && cast != Cast::ToBool => value,
+            Cast::SignExtend => builder.ins().sextend(to_type, value),
+            Cast::ZeroExtend | Cast::FromBool => builder.ins().uextend(to_type, value),
+            Cast::Truncate => builder.ins().ireduce(to_type, value),
+            Cast::IntToFloat => {
+                // Narrow integers can't be converted directly
+                let value = if from_type.bits() < 32 && signed {
+                    builder.ins().sextend(cl::types::I32, value)
+                } else if from_type.bits() < 32 {
+                    builder.ins().uextend(cl::types::I32, value)
+                } else {
+                    value
+                };
+                if signed {
+                    builder.ins().fcvt_from_sint(to_type, value)
+                } else {
+                    builder.ins().fcvt_from_uint(to_type, value)
+                }
+            }
+            Cast::FloatToInt | Cast::FloatToIntSaturating => {
+                let saturating = cast == Cast::FloatToIntSaturating;
+                let signed = matches!(to, orco::Type::Integer(_));
+                let wide_type = if to_type.bits() < 32 {
+                    cl::types::I32
+                } else {
+                    to_type
+                };
+                let ins = builder.ins();
+                let value = match (signed, saturating) {
+                    (true, true) => ins.fcvt_to_sint_sat(wide_type, value),
+                    (true, false) => ins.fcvt_to_sint(wide_type, value),
+                    (false, true) => ins.fcvt_to_uint_sat(wide_type, value),
+                    (false, false) => ins.fcvt_to_uint(wide_type, value),
+                };
+                if wide_type == to_type {
+                    return value;
+                }
+                // Clamp to the range of the narrow integer before truncating
+                let value = if saturating && signed {
+                    let max = (1i64 << (to_type.bits() - 1)) - 1;
+                    let min = builder.ins().iconst(wide_type, -max - 1);
+                    let max = builder.ins().iconst(wide_type, max);
+                    let value = builder.ins().smin(value, max);
+                    builder.ins().smax(value, min)
+                } else if saturating {
+                    let max = builder
+                        .ins()
+                        .iconst(wide_type, (1i64 << to_type.bits()) - 1);
+                    builder.ins().umin(value, max)
+                } else {
+                    value
+                };
+                builder.ins().ireduce(to_type, value)
+            }
+            Cast::FloatResize if from_type.bits() < to_type.bits() => {
+                builder.ins().fpromote(to_type, value)
+            }
+            Cast::FloatResize => builder.ins().fdemote(to_type, value),
+            Cast::Bitcast => builder.ins().bitcast(to_type, cl::MemFlags::new(), value),
+            Cast::ToBool if from_type.is_float() => {
+                let zero = match from_type {
+                    cl::types::F32 => builder.ins().f32const(0.0),
+                    _ => builder.ins().f64const(0.0),
+                };
+                builder.ins().fcmp(cl::FloatCC::NotEqual, value, zero)
+            }
+            Cast::ToBool => builder.ins().icmp_imm(cl::IntCC::NotEqual, value, 0),
+        }
+    }
+}
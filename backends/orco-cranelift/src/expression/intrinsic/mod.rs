@@ -1,3 +1,337 @@
 use crate::cl;
+use cranelift::prelude::InstBuilder;
+use cranelift_module::Module;
+use orco::type_inference::intrinsics::Intrinsic;
 
-impl crate::Object {}
+pub mod cast;
+
+impl crate::Object {
+    /// Build a call to an intrinsic function
+    pub fn build_intrinsic(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        intrinsic: Intrinsic,
+        signature: &orco::types::FunctionSignature,
+        args: &[orco::Expression],
+    ) -> Option<cl::Value> {
+        // Intrinsics inside of generic functions use type parameters
+        let signature = if self.type_arguments.is_empty() {
+            std::borrow::Cow::Borrowed(signature)
+        } else {
+            std::borrow::Cow::Owned(signature.substitute(&self.type_arguments))
+        };
+        let signature = signature.as_ref();
+        match intrinsic {
+            Intrinsic::Return => {
+                let values = args
+                    .iter()
+                    .filter_map(|arg| self.build_expression(builder, arg))
+                    .collect::<Vec<_>>();
+                if let Some(address) = self.struct_return {
+                    self.store(builder, &signature.parameters[0].1, values[0], address);
+                    builder.ins().return_(&[]);
+                } else {
+                    builder.ins().return_(&values);
+                }
+                switch_to_unreachable_block(builder);
+                None
+            }
+            Intrinsic::Branch => self.build_branch(builder, signature, args),
+            Intrinsic::Loop => self.build_loop(builder, signature, args),
+            Intrinsic::Break(depth) => {
+                let values = args
+                    .iter()
+                    .filter_map(|arg| self.build_expression(builder, arg))
+                    .collect::<Vec<_>>();
+                let (_, exit_block) = self.loops[self.loops.len() - 1 - depth];
+                builder.ins().jump(exit_block, &values);
+                switch_to_unreachable_block(builder);
+                None
+            }
+            Intrinsic::Continue(depth) => {
+                let (next_block, _) = self.loops[self.loops.len() - 1 - depth];
+                builder.ins().jump(next_block, &[]);
+                switch_to_unreachable_block(builder);
+                None
+            }
+            Intrinsic::Assign => {
+                let value = self.build_expression(builder, &args[1])?;
+                if let orco::Expression::Variable(variable) = &args[0] {
+                    if let Some(variable) = self.variable(builder, variable) {
+                        builder.def_var(variable, value);
+                        return Some(value);
+                    }
+                }
+                let address = self.build_place(builder, &args[0]);
+                self.store(builder, &signature.parameters[0].1, value, address);
+                Some(value)
+            }
+            Intrinsic::Construct => self.build_construct(builder, signature, args),
+            Intrinsic::Array => self.build_array(builder, signature, args),
+            Intrinsic::Slice => self.build_slice(builder, signature, args),
+            Intrinsic::Length => self.build_length(builder, signature, args),
+            Intrinsic::AddressOf => Some(self.build_place(builder, &args[0])),
+            Intrinsic::Load => {
+                let pointer = self
+                    .build_expression(builder, &args[0])
+                    .expect("Pointer has to have a value");
+                self.load(builder, &signature.return_type, pointer)
+            }
+            Intrinsic::Store => {
+                let pointer = self
+                    .build_expression(builder, &args[0])
+                    .expect("Pointer has to have a value");
+                if let Some(value) = self.build_expression(builder, &args[1]) {
+                    self.store(builder, &signature.parameters[1].1, value, pointer);
+                }
+                None
+            }
+            Intrinsic::Offset => {
+                let pointer = self
+                    .build_expression(builder, &args[0])
+                    .expect("Pointer has to have a value");
+                let offset = self
+                    .build_expression(builder, &args[1])
+                    .expect("Offset has to have a value");
+                let orco::Type::Pointer(pointee, _) = &signature.return_type.as_ref() else {
+                    unreachable!()
+                };
+                let size = self.layout(pointee).size;
+                let offset = self.extend_to_pointer(builder, offset, &signature.parameters[1].1);
+                let offset = builder.ins().imul_imm(offset, size as i64);
+                Some(builder.ins().iadd(pointer, offset))
+            }
+            Intrinsic::Cast(cast) | Intrinsic::ImplicitCast(cast) => {
+                let value = self
+                    .build_expression(builder, &args[0])
+                    .expect("Casted value has to have a value");
+                Some(self.build_cast(
+                    builder,
+                    cast,
+                    &signature.parameters[0].1,
+                    &signature.return_type,
+                    value,
+                ))
+            }
+            _ => {
+                let values = args
+                    .iter()
+                    .map(|arg| {
+                        self.build_expression(builder, arg)
+                            .expect("Operands of an operator have to have a value")
+                    })
+                    .collect::<Vec<_>>();
+                Some(build_operator(
+                    builder,
+                    intrinsic,
+                    &signature.parameters[0].1,
+                    &values,
+                ))
+            }
+        }
+    }
+
+    /// Sign- or zero-extend (or truncate) an integer to the pointer width
+    pub fn extend_to_pointer(
+        &self,
+        builder: &mut cl::FunctionBuilder,
+        value: cl::Value,
+        r#type: &orco::Type,
+    ) -> cl::Value {
+        let pointer_type = self.object.target_config().pointer_type();
+        let value_type = builder.func.dfg.value_type(value);
+        if value_type.bits() > pointer_type.bits() {
+            builder.ins().ireduce(pointer_type, value)
+        } else if value_type.bits() == pointer_type.bits() {
+            value
+        } else if let orco::Type::Integer(_) = r#type {
+            builder.ins().sextend(pointer_type, value)
+        } else {
+            builder.ins().uextend(pointer_type, value)
+        }
+    }
+
+    /// Build a branch, bodies are transparent functions
+    pub fn build_branch(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        signature: &orco::types::FunctionSignature,
+        args: &[orco::Expression],
+    ) -> Option<cl::Value> {
+        let condition = self
+            .build_expression(builder, &args[0])
+            .expect("Branch condition has to have a value");
+
+        let then_block = builder.create_block();
+        let else_block = builder.create_block();
+        let merge_block = builder.create_block();
+        let returns_value = !self.convert_type(&signature.return_type).is_empty();
+        for param in self.convert_type(&signature.return_type) {
+            builder.append_block_param(merge_block, param.value_type);
+        }
+
+        builder
+            .ins()
+            .brif(condition, then_block, &[], else_block, &[]);
+        for (block, body) in [(then_block, &args[1]), (else_block, &args[2])] {
+            builder.switch_to_block(block);
+            builder.seal_block(block);
+            let value = self.build_transparent_body(builder, body);
+            match value {
+                Some(value) if returns_value => builder.ins().jump(merge_block, &[value]),
+                _ => builder.ins().jump(merge_block, &[]),
+            };
+        }
+
+        builder.switch_to_block(merge_block);
+        builder.seal_block(merge_block);
+        builder.block_params(merge_block).first().copied()
+    }
+
+    /// Build a loop, bodies are transparent functions.
+    /// `continue` jumps to the `next` body, `break` to the exit block
+    pub fn build_loop(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        signature: &orco::types::FunctionSignature,
+        args: &[orco::Expression],
+    ) -> Option<cl::Value> {
+        let body_block = builder.create_block();
+        let next_block = builder.create_block();
+        let exit_block = builder.create_block();
+        for param in self.convert_type(&signature.return_type) {
+            builder.append_block_param(exit_block, param.value_type);
+        }
+
+        builder.ins().jump(body_block, &[]);
+        builder.switch_to_block(body_block);
+        self.loops.push((next_block, exit_block));
+        self.build_transparent_body(builder, &args[0]);
+        builder.ins().jump(next_block, &[]);
+
+        builder.switch_to_block(next_block);
+        builder.seal_block(next_block);
+        self.build_transparent_body(builder, &args[1]);
+        builder.ins().jump(body_block, &[]);
+        builder.seal_block(body_block);
+        self.loops.pop();
+
+        builder.switch_to_block(exit_block);
+        builder.seal_block(exit_block);
+        builder.block_params(exit_block).first().copied()
+    }
+
+    /// Build the body of a transparent function in place.
+    /// Bodies are function literals, [orco::verify::verify] rejects anything else
+    fn build_transparent_body(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        body: &orco::Expression,
+    ) -> Option<cl::Value> {
+        let orco::Expression::Function(function) = body else {
+            unreachable!("body is not a function literal, it should have been rejected by verify");
+        };
+        match &function.body {
+            orco::expression::function::FunctionBody::Block(body) => self.build_body(builder, body),
+            // Bodies take no arguments, so this is a call without them
+            orco::expression::function::FunctionBody::Intrinsic(intrinsic) => {
+                self.build_intrinsic(builder, *intrinsic, &function.signature, &[])
+            }
+        }
+    }
+}
+
+/// Code after a jump is unreachable, but still has to go somewhere
+fn switch_to_unreachable_block(builder: &mut cl::FunctionBuilder) {
+    let block = builder.create_block();
+    builder.switch_to_block(block);
+    builder.seal_block(block);
+}
+
+/// Build an arithmetic, bitwise or comparison operator
+fn build_operator(
+    builder: &mut cl::FunctionBuilder,
+    intrinsic: Intrinsic,
+    r#type: &orco::Type,
+    values: &[cl::Value],
+) -> cl::Value {
+    use cl::{FloatCC, IntCC};
+    let signed = matches!(r#type, orco::Type::Integer(_));
+    let float = matches!(r#type, orco::Type::Float(_));
+    let int_cc = |signed_cc, unsigned_cc| if signed { signed_cc } else { unsigned_cc };
+
+    let ins = builder.ins();
+    match intrinsic {
+        Intrinsic::Add if float => ins.fadd(values[0], values[1]),
+        Intrinsic::Add => ins.iadd(values[0], values[1]),
+        Intrinsic::Sub if float => ins.fsub(values[0], values[1]),
+        Intrinsic::Sub => ins.isub(values[0], values[1]),
+        Intrinsic::Mul if float => ins.fmul(values[0], values[1]),
+        Intrinsic::Mul => ins.imul(values[0], values[1]),
+        Intrinsic::Div if float => ins.fdiv(values[0], values[1]),
+        Intrinsic::Div if signed => ins.sdiv(values[0], values[1]),
+        Intrinsic::Div => ins.udiv(values[0], values[1]),
+        Intrinsic::Rem if signed => ins.srem(values[0], values[1]),
+        Intrinsic::Rem => ins.urem(values[0], values[1]),
+        Intrinsic::Neg if float => ins.fneg(values[0]),
+        Intrinsic::Neg => ins.ineg(values[0]),
+
+        Intrinsic::And => ins.band(values[0], values[1]),
+        Intrinsic::Or => ins.bor(values[0], values[1]),
+        Intrinsic::Xor => ins.bxor(values[0], values[1]),
+        Intrinsic::Not if *r#type == orco::Type::Bool => ins.bxor_imm(values[0], 1),
+        Intrinsic::Not => ins.bnot(values[0]),
+        Intrinsic::Shl => ins.ishl(values[0], values[1]),
+        Intrinsic::Shr if signed => ins.sshr(values[0], values[1]),
+        Intrinsic::Shr => ins.ushr(values[0], values[1]),
+
+        Intrinsic::Eq if float => ins.fcmp(FloatCC::Equal, values[0], values[1]),
+        Intrinsic::Ne if float => ins.fcmp(FloatCC::NotEqual, values[0], values[1]),
+        Intrinsic::Lt if float => ins.fcmp(FloatCC::LessThan, values[0], values[1]),
+        Intrinsic::Le if float => ins.fcmp(FloatCC::LessThanOrEqual, values[0], values[1]),
+        Intrinsic::Gt if float => ins.fcmp(FloatCC::GreaterThan, values[0], values[1]),
+        Intrinsic::Ge if float => ins.fcmp(FloatCC::GreaterThanOrEqual, values[0], values[1]),
+        Intrinsic::Eq => ins.icmp(IntCC::Equal, values[0], values[1]),
+        Intrinsic::Ne => ins.icmp(IntCC::NotEqual, values[0], values[1]),
+        Intrinsic::Lt => ins.icmp(
+            int_cc(IntCC::SignedLessThan, IntCC::UnsignedLessThan),
+            values[0],
+            values[1],
+        ),
+        Intrinsic::Le => ins.icmp(
+            int_cc(IntCC::SignedLessThanOrEqual, IntCC::UnsignedLessThanOrEqual),
+            values[0],
+            values[1],
+        ),
+        Intrinsic::Gt => ins.icmp(
+            int_cc(IntCC::SignedGreaterThan, IntCC::UnsignedGreaterThan),
+            values[0],
+            values[1],
+        ),
+        Intrinsic::Ge => ins.icmp(
+            int_cc(
+                IntCC::SignedGreaterThanOrEqual,
+                IntCC::UnsignedGreaterThanOrEqual,
+            ),
+            values[0],
+            values[1],
+        ),
+
+        Intrinsic::Return
+        | Intrinsic::Branch
+        | Intrinsic::Loop
+        | Intrinsic::Break(_)
+        | Intrinsic::Continue(_)
+        | Intrinsic::Assign
+        | Intrinsic::Construct
+        | Intrinsic::AddressOf
+        | Intrinsic::Load
+        | Intrinsic::Store
+        | Intrinsic::Offset
+        | Intrinsic::Array
+        | Intrinsic::Slice
+        | Intrinsic::Length
+        | Intrinsic::Cast(_)
+        | Intrinsic::ImplicitCast(_) => unreachable!(),
+    }
+}
@@ -1,24 +1,23 @@
 use super::*;
 
-impl Object {
+impl crate::Object {
     pub fn build_literal(
         &mut self,
         builder: &mut cl::FunctionBuilder,
-        lit: orco::expression::Literal,
+        literal: &orco::expression::Literal,
     ) -> Option<cl::Value> {
-        match lit {
-            orco::expression::Literal::Integer(lit) => self.build_integer_literal(builder, lit),
-        }
-    }
-
-    pub fn build_integer_literal(
-        &mut self,
-        builder: &mut cl::FunctionBuilder,
-        lit: &dyn orco::expression::literal::IntegerLiteral,
-    ) -> Option<cl::Value> {
-        Some(builder.ins().iconst(
-            self.convert_type(lit.r#type())[0].value_type,
-            lit.value() as i64,
-        ))
+        use orco::expression::Literal;
+        let [param] = self.convert_type(literal.r#type())[..] else {
+            return None;
+        };
+        Some(match literal {
+            Literal::Integer(value, _) => builder.ins().iconst(param.value_type, *value as i64),
+            Literal::Float(value, _) => match param.value_type {
+                cl::types::F32 => builder.ins().f32const(*value as f32),
+                _ => builder.ins().f64const(*value),
+            },
+            Literal::Bool(value) => builder.ins().iconst(param.value_type, *value as i64),
+            Literal::Null(_) => builder.ins().iconst(param.value_type, 0),
+        })
     }
 }
@@ -1,10 +1,13 @@
 use crate::cl;
 use cranelift::prelude::InstBuilder;
+use cranelift_module::Module;
 
 // pub mod block;
 // pub mod control_flow;
-// pub mod literal;
+pub mod literal;
 pub mod intrinsic;
+pub mod structs;
+pub mod arrays;
 
 impl crate::Object {
     pub fn build_expression(
@@ -14,37 +17,102 @@ impl crate::Object {
     ) -> Option<cl::Value> {
         use orco::Expression;
         match expression {
-            Expression::Literal(literal) => todo!(),
-            Expression::Variable(rw_lock) => todo!(),
-            Expression::Function(function) => todo!(),
+            Expression::Literal(literal) => self.build_literal(builder, literal),
+            Expression::Variable(variable) => {
+                if let Some(address) = self.variable_address(builder, variable) {
+                    let r#type = variable.read().unwrap().r#type.clone();
+                    return self.load(builder, &r#type, address);
+                }
+                let variable = self.variable(builder, variable)?;
+                Some(builder.use_var(variable))
+            }
+            Expression::Function(_) => {
+                unreachable!("function values are rejected by verify, bodies are built in place")
+            }
             Expression::Call(call) => {
                 let function = call.function.read().unwrap();
+                use orco::expression::function::FunctionBody;
                 use orco::types::CallingConvention;
-                match function.signature.calling_convention {
-                    CallingConvention::Transparent => {
-                        match function.body {
-                            orco::expression::function::FunctionBody::Block(vec) => todo!(),
-                            orco::expression::function::FunctionBody::Intrinsic(intrinsic) => {
-                                todo!()
+                match &function.body {
+                    FunctionBody::Intrinsic(intrinsic) => {
+                        self.build_intrinsic(builder, *intrinsic, &function.signature, &call.args)
+                    }
+                    FunctionBody::Block(body) => match function.signature.calling_convention {
+                        CallingConvention::Transparent => {
+                            for (parameter, arg) in function.parameters.iter().zip(&call.args) {
+                                if let Some(value) = self.build_expression(builder, arg) {
+                                    self.bind_parameter(builder, parameter, value);
+                                }
                             }
-                            //
+                            self.build_body(builder, body)
                         }
-                    }
-                    CallingConvention::Inline => todo!(),
-                    CallingConvention::Fastest => todo!(),
-                    CallingConvention::SystemV => todo!(),
-                    CallingConvention::Fastcall => todo!(),
+                        // Inline functions are inlined by `orco::passes::Inliner`,
+                        // the ones it couldn't inline are called like any other
+                        _ => {
+                            let name = function
+                                .name
+                                .as_ref()
+                                .expect("Only named functions can be called for now");
+                            let generic = !function.signature.type_parameters.is_empty();
+                            let (id, return_type) = if !generic {
+                                let id = *self
+                                    .functions
+                                    .get(name)
+                                    .expect("Function has to be declared before it is called!");
+                                (id, function.signature.return_type.as_ref().clone())
+                            } else {
+                                // Type arguments might refer to our own type parameters
+                                let type_arguments = call
+                                    .type_arguments
+                                    .iter()
+                                    .map(|r#type| self.substitute(r#type))
+                                    .collect::<Vec<_>>();
+                                let signature = function.signature.instantiate(&type_arguments);
+                                let id = self.instance(
+                                    name,
+                                    &signature,
+                                    call.function.clone(),
+                                    type_arguments,
+                                );
+                                (id, *signature.return_type)
+                            };
+                            // Aggregates are returned through a stack slot of the caller
+                            let struct_return = if self.is_aggregate(&return_type) {
+                                let slot = self.stack_slot(builder, &return_type);
+                                Some(builder.ins().stack_addr(
+                                    self.object.target_config().pointer_type(),
+                                    slot,
+                                    0,
+                                ))
+                            } else {
+                                None
+                            };
+                            let args = struct_return
+                                .into_iter()
+                                .chain(
+                                    call.args
+                                        .iter()
+                                        .filter_map(|arg| self.build_expression(builder, arg)),
+                                )
+                                .collect::<Vec<_>>();
+                            let func_ref = self.object.declare_func_in_func(id, builder.func);
+                            let inst = builder.ins().call(func_ref, &args);
+                            struct_return.or_else(|| builder.inst_results(inst).first().copied())
+                        }
+                    },
                 }
             }
-            Expression::Error => todo!(),
+            Expression::TraitCall(_) => {
+                panic!("Trait call encountered on backend phase, trait calls are resolved by type inference")
+            }
+            Expression::Field(field) => self.build_field(builder, field),
+            Expression::Index(index) => self.build_index(builder, index),
+            Expression::Comptime(_) => {
+                panic!("Comptime block encountered on backend phase, comptime blocks are evaluated by type inference")
+            }
+            Expression::Error(_) => {
+                panic!("Error expression encountered on backend phase, errors have to be reported before codegen")
+            }
         }
     }
-
-    pub fn inline_function(
-        &mut self,
-        builder: cl::FunctionBuilder,
-        function: orco::expression::Function,
-        args: Vec<orco::Expression>,
-    ) -> Option<cl::Value> {
-    }
 }
@@ -0,0 +1,148 @@
+use super::*;
+
+impl crate::Object {
+    /// Create a stack slot, big enough to hold a value of this type
+    pub fn stack_slot(
+        &self,
+        builder: &mut cl::FunctionBuilder,
+        r#type: &orco::Type,
+    ) -> cl::StackSlot {
+        let layout = self.layout(r#type);
+        builder.create_sized_stack_slot(cl::StackSlotData::new(
+            cl::StackSlotKind::ExplicitSlot,
+            layout.size as _,
+            layout.alignment.trailing_zeros() as _,
+        ))
+    }
+
+    /// Store a value of this type at an address. Aggregates are copied
+    pub fn store(
+        &self,
+        builder: &mut cl::FunctionBuilder,
+        r#type: &orco::Type,
+        value: cl::Value,
+        address: cl::Value,
+    ) {
+        if self.is_aggregate(r#type) {
+            let layout = self.layout(r#type);
+            builder.emit_small_memory_copy(
+                self.object.target_config(),
+                address,
+                value,
+                layout.size,
+                layout.alignment as _,
+                layout.alignment as _,
+                false,
+                cl::MemFlags::trusted(),
+            );
+        } else if !self.convert_type(r#type).is_empty() {
+            builder
+                .ins()
+                .store(cl::MemFlags::trusted(), value, address, 0);
+        }
+    }
+
+    /// Load a value of this type from an address. Aggregate values are their addresses
+    pub fn load(
+        &self,
+        builder: &mut cl::FunctionBuilder,
+        r#type: &orco::Type,
+        address: cl::Value,
+    ) -> Option<cl::Value> {
+        if self.is_aggregate(r#type) {
+            return Some(address);
+        }
+        let [param] = self.convert_type(r#type)[..] else {
+            return None;
+        };
+        Some(
+            builder
+                .ins()
+                .load(param.value_type, cl::MemFlags::trusted(), address, 0),
+        )
+    }
+
+    /// Build an address of a place: a variable in memory, a field, an element,
+    /// a loaded value or an aggregate-typed expression
+    pub fn build_place(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        expression: &orco::Expression,
+    ) -> cl::Value {
+        match expression {
+            orco::Expression::Variable(variable) => self
+                .variable_address(builder, variable)
+                .expect("Only variables, that live in memory have an address"),
+            orco::Expression::Field(field) => self.build_field_address(builder, field).0,
+            orco::Expression::Index(index) => self.build_index_address(builder, index).0,
+            // Address of a loaded value is the pointer it was loaded from
+            orco::Expression::Call(call)
+                if matches!(
+                    call.function.read().unwrap().body,
+                    orco::expression::function::FunctionBody::Intrinsic(
+                        orco::type_inference::intrinsics::Intrinsic::Load
+                    )
+                ) =>
+            {
+                self.build_expression(builder, &call.args[0])
+                    .expect("Pointer has to have a value")
+            }
+            expression => self
+                .build_expression(builder, expression)
+                .expect("Aggregate values have to be addresses"),
+        }
+    }
+
+    /// Build an address of a field, returns it with the type of the field
+    pub fn build_field_address(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        field: &orco::expression::FieldAccess,
+    ) -> (cl::Value, orco::Type) {
+        let (r#struct, index) = field
+            .field
+            .as_ref()
+            .expect("Field access has to be resolved by type inference");
+        let offset = self
+            .target
+            .layout(&orco::Type::Struct(r#struct.clone()))
+            .field_offsets[*index];
+        let r#type = r#struct.read().unwrap().fields[*index].1.clone();
+
+        let address = self.build_place(builder, &field.value);
+        (builder.ins().iadd_imm(address, offset as i64), r#type)
+    }
+
+    /// Read a field of a struct
+    pub fn build_field(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        field: &orco::expression::FieldAccess,
+    ) -> Option<cl::Value> {
+        let (address, r#type) = self.build_field_address(builder, field);
+        self.load(builder, &r#type, address)
+    }
+
+    /// Construct a struct in a new stack slot, returns it's address
+    pub fn build_construct(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        signature: &orco::types::FunctionSignature,
+        args: &[orco::Expression],
+    ) -> Option<cl::Value> {
+        let layout = self.layout(&signature.return_type);
+        let slot = self.stack_slot(builder, &signature.return_type);
+        let pointer_type = self.object.target_config().pointer_type();
+        for ((arg, (_, r#type)), offset) in args
+            .iter()
+            .zip(&signature.parameters)
+            .zip(layout.field_offsets)
+        {
+            if let Some(value) = self.build_expression(builder, arg) {
+                let address = builder.ins().stack_addr(pointer_type, slot, offset as _);
+                self.store(builder, r#type, value, address);
+            }
+        }
+        Some(builder.ins().stack_addr(pointer_type, slot, 0))
+    }
+}
@@ -1,4 +1,5 @@
 use crate::cl;
+use cranelift::prelude::InstBuilder;
 use cranelift_module::Module;
 use log::*;
 
@@ -19,15 +20,76 @@ impl crate::Object {
         self.functions.insert(name.to_owned(), id);
     }
 
-    /// Build the function
-    pub fn build_function(&mut self, name: &str, function: &orco::expression::Function) {
-        info!("Compiling function {:?}", name);
-        trace!("OrCo IR:\n{}", function);
-
+    /// Build the function, it is lowered and compiled by [Self::compile_functions]
+    pub fn build_function(
+        &mut self,
+        name: &str,
+        function: orco::ArcLock<orco::expression::Function>,
+    ) {
         let id = *self
             .functions
             .get(name)
             .expect("Function has to be declared before it is built!");
+        self.pending_functions.push(crate::PendingFunction {
+            id,
+            name: name.to_owned(),
+            type_arguments: Vec::new(),
+            function,
+        });
+    }
+
+    /// Declare an instance of a generic function, or get the one declared before.
+    /// The signature has to be instantiated already. Instances are built like
+    /// any other function, see [Self::build_function]
+    pub fn instance(
+        &mut self,
+        name: &str,
+        signature: &orco::types::FunctionSignature,
+        function: orco::ArcLock<orco::expression::Function>,
+        type_arguments: Vec<orco::Type>,
+    ) -> cl::FuncId {
+        let key = (name.to_owned(), type_arguments);
+        if let Some(id) = self.instances.get(&key) {
+            return *id;
+        }
+        let (_, type_arguments) = key.clone();
+        let name = format!(
+            "{}<{}>",
+            name,
+            type_arguments
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        trace!("Declaring instance {:?}", name);
+        let id = self
+            .object
+            .declare_function(
+                &name,
+                cl::Linkage::Local,
+                &self.convert_function_signature(signature),
+            )
+            .unwrap();
+        self.instances.insert(key, id);
+        self.pending_functions.push(crate::PendingFunction {
+            id,
+            name,
+            type_arguments,
+            function,
+        });
+        id
+    }
+
+    /// Lower the function to Cranelift IR
+    pub fn lower_function(
+        &mut self,
+        id: cl::FuncId,
+        name: &str,
+        function: &orco::expression::Function,
+    ) -> cl::codegen::Context {
+        info!("Compiling function {:?}", name);
+        trace!("OrCo IR:\n{}", function);
 
         let mut ctx = cl::codegen::Context::new();
         ctx.func = cl::codegen::ir::Function::with_name_signature(
@@ -48,10 +110,220 @@ impl crate::Object {
             builder.switch_to_block(block);
             builder.seal_block(block);
             builder.append_block_params_for_function_params(block);
+
+            self.variables.clear();
+            self.stack_slots.clear();
+            self.address_taken.clear();
+            if let orco::expression::function::FunctionBody::Block(body) = &function.body {
+                for expression in body {
+                    self.collect_address_taken(expression);
+                }
+            }
+            let mut params = builder.block_params(block).to_vec().into_iter();
+            self.struct_return = if self.is_aggregate(&function.signature.return_type) {
+                params.next()
+            } else {
+                None
+            };
+            for parameter in &function.parameters {
+                let values = params
+                    .by_ref()
+                    .take(self.convert_type(&parameter.read().unwrap().r#type).len())
+                    .collect::<Vec<_>>();
+                if let Some(&value) = values.first() {
+                    self.bind_parameter(&mut builder, parameter, value);
+                }
+            }
+
             self.build_function_body(&mut builder, function);
             builder.finalize();
         }
-        self.object.define_function(id, &mut ctx).unwrap();
+        ctx
+    }
+
+    /// Lower and compile functions from [crate::Object::pending_functions] on `threads` threads
+    /// and define them in the object. Functions are defined in the order they were declared,
+    /// so the object doesn't depend on the order they were lowered and compiled in
+    pub fn compile_functions(&mut self, threads: usize) {
+        for (id, ctx) in self.compile_pending(threads) {
+            let code = ctx.compiled_code().unwrap();
+            self.object
+                .define_function_bytes(
+                    id,
+                    &ctx.func,
+                    code.buffer.alignment as u64,
+                    code.code_buffer(),
+                    code.buffer.relocs(),
+                )
+                .unwrap();
+        }
+    }
+
+    /// Lower and compile functions from [crate::Object::pending_functions] on `threads` threads,
+    /// including the instances they use, without defining them. Returns them in the order
+    /// they were declared
+    pub fn compile_pending(&mut self, threads: usize) -> Vec<(cl::FuncId, cl::codegen::Context)> {
+        let mut pending = Vec::new();
+        while !self.pending_functions.is_empty() {
+            pending.extend(self.lower_pending(threads));
+        }
+        pending.sort_by_key(|(id, _)| *id);
+        let isa = self.object.isa();
+        let queue = std::sync::Mutex::new(pending.iter_mut());
+        let compile = || loop {
+            let Some((id, ctx)) = queue.lock().unwrap().next() else {
+                break;
+            };
+            trace!("Generating machine code for {}", id);
+            ctx.compile(isa, &mut Default::default()).unwrap();
+        };
+        std::thread::scope(|scope| {
+            for _ in 1..threads {
+                scope.spawn(compile);
+            }
+            compile();
+        });
+        pending
+    }
+
+    /// Lower functions from [crate::Object::pending_functions] on `threads` threads.
+    /// Each thread lowers into its own fork of the object, see [Self::fork].
+    /// Instances and constants, that the forks declare, are then declared
+    /// in the order of the functions, see [Self::relink]
+    fn lower_pending(&mut self, threads: usize) -> Vec<(cl::FuncId, cl::codegen::Context)> {
+        let mut pending = std::mem::take(&mut self.pending_functions);
+        pending.sort_by_key(|function| function.id);
+        let triple = self.object.isa().triple().to_string();
+        let declarations = self.object.declarations();
+        let functions = &self.functions;
+        let queue = std::sync::Mutex::new(pending.iter());
+        let lower = || {
+            let mut fork = crate::Object::fork(&triple, declarations, functions);
+            let mut lowered = Vec::new();
+            loop {
+                let Some(function) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                let body = function.function.read().unwrap();
+                fork.type_arguments = body.signature.type_arguments(&function.type_arguments);
+                let ctx = fork.lower_function(function.id, &function.name, &body);
+                let constants = fork.constant_data.take().map(|(_, data)| data);
+                lowered.push((function.id, ctx, constants));
+            }
+            (fork.pending_functions, lowered)
+        };
+        let forks = std::thread::scope(|scope| {
+            let threads = (1..threads).map(|_| scope.spawn(lower)).collect::<Vec<_>>();
+            let mut forks = vec![lower()];
+            forks.extend(threads.into_iter().map(|thread| thread.join().unwrap()));
+            forks
+        });
+
+        let declared = self.object.declarations().get_functions().count();
+        let mut instances = Vec::new();
+        let mut lowered = Vec::new();
+        for (fork, (fork_instances, functions)) in forks.into_iter().enumerate() {
+            instances.push(fork_instances);
+            lowered.extend(
+                functions
+                    .into_iter()
+                    .map(|(id, ctx, constants)| (id, fork, ctx, constants)),
+            );
+        }
+        lowered.sort_by_key(|(id, ..)| *id);
+        lowered
+            .into_iter()
+            .map(|(id, fork, mut ctx, constants)| {
+                self.relink(
+                    &mut ctx.func,
+                    declared,
+                    &instances[fork],
+                    constants.as_deref(),
+                );
+                (id, ctx)
+            })
+            .collect()
+    }
+
+    /// Object with the same declarations, that lowers functions on another thread.
+    /// Functions, that are declared after the first `declarations`, are instances,
+    /// declared by the fork, see [Self::relink]
+    fn fork(
+        triple: &str,
+        declarations: &cl::ModuleDeclarations,
+        functions: &std::collections::HashMap<String, cl::FuncId>,
+    ) -> Self {
+        let mut fork = Self::new(triple);
+        // Declared in the same order, so the ids are the same
+        for (_, declaration) in declarations.get_functions() {
+            fork.object
+                .declare_function(
+                    declaration.name.as_deref().unwrap(),
+                    declaration.linkage,
+                    &declaration.signature,
+                )
+                .unwrap();
+        }
+        fork.functions = functions.clone();
+        fork
+    }
+
+    /// Point references of a function, lowered by a fork, at this object.
+    /// Instances, declared by the fork, are declared here, and constants of the function
+    /// are moved to the constant pool. `declared` is the number of functions
+    /// the fork was made with, see [Self::fork]
+    fn relink(
+        &mut self,
+        func: &mut cl::codegen::ir::Function,
+        declared: usize,
+        instances: &[crate::PendingFunction],
+        constants: Option<&[u8]>,
+    ) {
+        use cl::codegen::ir::{ExternalName, GlobalValueData, UserExternalName};
+        let names = func
+            .params
+            .user_named_funcs()
+            .iter()
+            .map(|(reference, name)| (reference, name.clone()))
+            .collect::<Vec<_>>();
+        for (reference, name) in names {
+            let name = match name.namespace {
+                0 if name.index as usize >= declared => {
+                    let instance = instances
+                        .iter()
+                        .find(|instance| instance.id.as_u32() == name.index)
+                        .expect("Instance has to be declared by the fork");
+                    let function = instance.function.read().unwrap();
+                    let signature = function.signature.instantiate(&instance.type_arguments);
+                    let id = self.instance(
+                        function.name.as_ref().unwrap(),
+                        &signature,
+                        instance.function.clone(),
+                        instance.type_arguments.clone(),
+                    );
+                    UserExternalName::new(0, id.as_u32())
+                }
+                // The constant pool is the only data
+                1 => {
+                    let (id, offset) = self.add_constant(constants.unwrap(), 16);
+                    for value in func.global_values.values_mut() {
+                        if let GlobalValueData::Symbol {
+                            name: ExternalName::User(symbol),
+                            offset: symbol_offset,
+                            ..
+                        } = value
+                        {
+                            if *symbol == reference {
+                                *symbol_offset = (symbol_offset.bits() + offset as i64).into();
+                            }
+                        }
+                    }
+                    UserExternalName::new(1, id.as_u32())
+                }
+                _ => continue,
+            };
+            func.params.reset_user_func_name(reference, name);
+        }
     }
 
     /// Build the function's body using a function builder
@@ -63,11 +335,168 @@ impl crate::Object {
         use orco::expression::function::FunctionBody;
         match &function.body {
             FunctionBody::Block(body) => {
-                for expr in body {
-                    self.build_expression(builder, expr);
-                }
+                self.build_body(builder, body);
             }
             FunctionBody::Intrinsic(_) => unreachable!(),
         }
+
+        // Falling off the end of a function
+        if !builder.is_filled() && self.struct_return.is_some() {
+            builder.ins().return_(&[]);
+        } else if !builder.is_filled() {
+            let values = self
+                .convert_type(&function.signature.return_type)
+                .into_iter()
+                .map(|param| zero(builder, param.value_type))
+                .collect::<Vec<_>>();
+            builder.ins().return_(&values);
+        }
+    }
+
+    /// Build a list of expressions, returns the value of the last one
+    pub fn build_body(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        body: &[orco::Expression],
+    ) -> Option<cl::Value> {
+        let mut value = None;
+        for expr in body {
+            value = self.build_expression(builder, expr);
+        }
+        value
+    }
+
+    /// Get (or declare) a Cranelift variable for an OrCo variable.
+    /// Returns [None] for zero-sized variables
+    pub fn variable(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        variable: &orco::ArcLock<orco::Variable>,
+    ) -> Option<cl::Variable> {
+        let key = std::sync::Arc::as_ptr(variable);
+        if let Some(variable) = self.variables.get(&key) {
+            return Some(*variable);
+        }
+        if self.in_memory(variable) {
+            return None;
+        }
+        let r#type = variable.read().unwrap().r#type.clone();
+        let [param] = self.convert_type(&r#type)[..] else {
+            return None;
+        };
+        let variable = cl::Variable::new(self.variables.len());
+        builder.declare_var(variable, param.value_type);
+        self.variables.insert(key, variable);
+        Some(variable)
+    }
+
+    /// Initialize a parameter variable with the value of the argument
+    pub fn bind_parameter(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        parameter: &orco::ArcLock<orco::Variable>,
+        value: cl::Value,
+    ) {
+        if let Some(address) = self.variable_address(builder, parameter) {
+            // Aggregates are passed by pointer, make a copy
+            let r#type = parameter.read().unwrap().r#type.clone();
+            self.store(builder, &r#type, value, address);
+        } else if let Some(variable) = self.variable(builder, parameter) {
+            builder.def_var(variable, value);
+        }
+    }
+
+    /// Does this variable live in a stack slot? Aggregates and
+    /// variables, whose address is taken do
+    pub fn in_memory(&self, variable: &orco::ArcLock<orco::Variable>) -> bool {
+        self.is_aggregate(&variable.read().unwrap().r#type)
+            || self
+                .address_taken
+                .contains(&std::sync::Arc::as_ptr(variable))
+    }
+
+    /// Find variables, whose address is taken in an expression
+    fn collect_address_taken(&mut self, expression: &orco::Expression) {
+        use orco::expression::function::FunctionBody;
+        use orco::type_inference::intrinsics::Intrinsic;
+        match expression {
+            orco::Expression::Function(function) => {
+                if let FunctionBody::Block(body) = &function.body {
+                    for expression in body {
+                        self.collect_address_taken(expression);
+                    }
+                }
+            }
+            orco::Expression::Call(call) => {
+                let function = call.function.read().unwrap();
+                if let FunctionBody::Intrinsic(Intrinsic::AddressOf) = function.body {
+                    let mut place = &call.args[0];
+                    loop {
+                        place = match place {
+                            orco::Expression::Field(field) => &field.value,
+                            orco::Expression::Index(index) => &index.value,
+                            _ => break,
+                        };
+                    }
+                    if let orco::Expression::Variable(variable) = place {
+                        self.address_taken.insert(std::sync::Arc::as_ptr(variable));
+                    }
+                }
+                if let FunctionBody::Block(body) = &function.body {
+                    if function.signature.calling_convention
+                        == orco::types::CallingConvention::Transparent
+                    {
+                        for expression in body {
+                            self.collect_address_taken(expression);
+                        }
+                    }
+                }
+                for arg in &call.args {
+                    self.collect_address_taken(arg);
+                }
+            }
+            orco::Expression::Field(field) => self.collect_address_taken(&field.value),
+            orco::Expression::Index(index) => {
+                self.collect_address_taken(&index.value);
+                self.collect_address_taken(&index.index);
+            }
+            _ => (),
+        }
+    }
+
+    /// Address of a variable, that lives in a stack slot, see [Self::in_memory].
+    /// Returns [None] for variables, that live in registers
+    pub fn variable_address(
+        &mut self,
+        builder: &mut cl::FunctionBuilder,
+        variable: &orco::ArcLock<orco::Variable>,
+    ) -> Option<cl::Value> {
+        let key = std::sync::Arc::as_ptr(variable);
+        let slot = match self.stack_slots.get(&key) {
+            Some(slot) => *slot,
+            None => {
+                if !self.in_memory(variable) {
+                    return None;
+                }
+                let r#type = variable.read().unwrap().r#type.clone();
+                let slot = self.stack_slot(builder, &r#type);
+                self.stack_slots.insert(key, slot);
+                slot
+            }
+        };
+        Some(
+            builder
+                .ins()
+                .stack_addr(self.object.target_config().pointer_type(), slot, 0),
+        )
+    }
+}
+
+/// Zero value of a Cranelift type
+fn zero(builder: &mut cl::FunctionBuilder, r#type: cl::Type) -> cl::Value {
+    match r#type {
+        cl::types::F32 => builder.ins().f32const(0.0),
+        cl::types::F64 => builder.ins().f64const(0.0),
+        r#type => builder.ins().iconst(r#type, 0),
     }
 }
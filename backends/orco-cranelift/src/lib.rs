@@ -11,6 +11,8 @@ mod cl {
 pub mod expression;
 /// Declare and build functions
 pub mod function;
+/// Per-function code generation for [orco::query]
+pub mod query;
 /// Declare and convert types
 pub mod types;
 
@@ -22,6 +24,40 @@ pub struct Object {
     pub functions: std::collections::HashMap<String, cl::FuncId>,
     /// Constant pool
     pub constant_data: Option<(cl::DataId, Vec<u8>)>,
+    /// Cranelift variables of the function that is being built
+    pub variables:
+        std::collections::HashMap<*const std::sync::RwLock<orco::Variable>, cl::Variable>,
+    /// `next` and exit blocks of the enclosing loops, innermost last
+    pub loops: Vec<(cl::Block, cl::Block)>,
+    /// Stack slots of the variables, that live in memory
+    pub stack_slots:
+        std::collections::HashMap<*const std::sync::RwLock<orco::Variable>, cl::StackSlot>,
+    /// Variables, whose address is taken in the function that is being built
+    pub address_taken: std::collections::HashSet<*const std::sync::RwLock<orco::Variable>>,
+    /// Pointer to the caller's stack slot for the return value, if it is an aggregate
+    pub struct_return: Option<cl::Value>,
+    /// Target properties, used to lay out types
+    pub target: orco::layout::Target,
+    /// Type arguments of the generic function instance, that is being built
+    pub type_arguments: std::collections::HashMap<String, orco::Type>,
+    /// Instances of generic functions, by function name and type arguments
+    pub instances: std::collections::HashMap<(String, Vec<orco::Type>), cl::FuncId>,
+    /// Functions and instances, that are declared, but not lowered yet,
+    /// see [Object::compile_functions]
+    pub pending_functions: Vec<PendingFunction>,
+}
+
+/// Function or an instance of a generic function, that is waiting to be lowered,
+/// see [Object::build_function] and [Object::instance]
+pub struct PendingFunction {
+    /// Declared Cranelift function
+    pub id: cl::FuncId,
+    /// Name of the function, like `max<i32>` for instances
+    pub name: String,
+    /// Type arguments, in the order of the type parameters
+    pub type_arguments: Vec<orco::Type>,
+    /// OrCo function
+    pub function: orco::ArcLock<orco::expression::Function>,
 }
 
 impl Object {
@@ -32,6 +68,10 @@ impl Object {
         let isa = isa_builder
             .finish(cl::settings::Flags::new(flag_builder))
             .unwrap();
+        let target = orco::layout::Target {
+            pointer_size: isa.pointer_bytes() as _,
+            ..orco::layout::Target::X86_64
+        };
         let object = cl::ObjectModule::new(
             cl::ObjectBuilder::new(isa, "foo", cl::default_libcall_names()).unwrap(),
         );
@@ -40,26 +80,53 @@ impl Object {
             object,
             functions: std::collections::HashMap::new(),
             constant_data: None,
+            variables: std::collections::HashMap::new(),
+            loops: Vec::new(),
+            stack_slots: std::collections::HashMap::new(),
+            address_taken: std::collections::HashSet::new(),
+            struct_return: None,
+            target,
+            type_arguments: std::collections::HashMap::new(),
+            instances: std::collections::HashMap::new(),
+            pending_functions: Vec::new(),
         }
     }
 
-    fn declare_symbol(&mut self, name: &str, symbol: &orco::Expression) {
+    fn declare_symbol(&mut self, name: &str, symbol: &orco::Symbol) {
         match symbol {
-            orco::Expression::Function(function) => self.declare_function(name, function),
-            _ => todo!(),
+            orco::Symbol::Function(function) => {
+                let function = function.read().unwrap();
+                // Generic functions are only built as instances
+                if function.signature.type_parameters.is_empty() {
+                    self.declare_function(name, &function);
+                }
+            }
+            orco::Symbol::Constant(_) => unreachable!("constants are rejected by verify"),
         }
     }
 
-    fn build_symbol(&mut self, name: &str, symbol: &orco::Expression) {
+    fn build_symbol(&mut self, name: &str, symbol: &orco::Symbol) {
         match symbol {
-            orco::Expression::Function(function) => self.build_function(name, function),
-            _ => todo!(),
+            orco::Symbol::Function(lock) => {
+                let function = lock.read().unwrap();
+                if function.signature.type_parameters.is_empty() {
+                    self.build_function(name, lock.clone());
+                }
+            }
+            orco::Symbol::Constant(_) => unreachable!("constants are rejected by verify"),
         }
     }
 }
 
-/// Build OrCo IR Unit
-pub fn build(symbols: &std::collections::HashMap<String, orco::Expression>) {
+/// Build OrCo IR Unit into `foo.o`, see [compile]
+pub fn build(symbols: &orco::Symbols, threads: Option<usize>) {
+    std::fs::write("foo.o", compile(symbols, threads)).unwrap();
+}
+
+/// Compile OrCo IR Unit into the bytes of an object file. Functions are compiled
+/// on `threads` threads, [None] uses all the available cores.
+/// The same unit always compiles to the same bytes, no matter the thread count
+pub fn compile(symbols: &orco::Symbols, threads: Option<usize>) -> Vec<u8> {
     let mut object = Object::new("x86_64-unknown-linux-gnu");
     for (name, symbol) in symbols {
         object.declare_symbol(name, symbol);
@@ -67,6 +134,10 @@ pub fn build(symbols: &std::collections::HashMap<String, orco::Expression>) {
     for (name, symbol) in symbols {
         object.build_symbol(name, symbol);
     }
+    let threads = threads.unwrap_or_else(|| {
+        std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+    });
+    object.compile_functions(threads.max(1));
 
     if let Some((id, data)) = object.constant_data {
         object
@@ -82,12 +153,87 @@ pub fn build(symbols: &std::collections::HashMap<String, orco::Expression>) {
                     function_relocs: Default::default(),
                     data_relocs: Default::default(),
                     custom_segment_section: Default::default(),
-                    align: Default::default(),
+                    // Constants are aligned within the pool, 16 is enough for any scalar
+                    align: Some(16),
                 },
             )
             .unwrap();
     }
 
-    let object = object.object.finish();
-    std::fs::write("foo.o", object.emit().unwrap()).unwrap();
+    object.object.finish().emit().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    #[test]
+    fn test_reproducible() {
+        let mut source = String::new();
+        for index in 0..32 {
+            source.push_str(&format!(
+                "const f{0} = fn f{0}(x: i32) -> i32 fastest {{\n    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = add)(x: i32, {0}i32));\n}};\n",
+                index
+            ));
+        }
+        let object_file = |threads| {
+            let mut ctx = orco::TypeInferenceContext::new();
+            let mut symbols = orco::parser::parse_unit(&mut ctx, &source).unwrap();
+            ctx.infer_symbols(symbols.values_mut());
+            check!(!ctx.diagnostics.has_errors());
+            compile(&symbols, Some(threads))
+        };
+        let object = object_file(1);
+        check!(!object.is_empty());
+        check!(object_file(1) == object);
+        check!(object_file(4) == object);
+    }
+
+    #[test]
+    fn test_reproducible_instances() {
+        // Instances and constants are declared by the threads, that lower the functions
+        let mut source = "\
+const id = fn id<T>(x: T) -> T fastest {
+    (fn (value: T) -> ! transparent = return)(x: T);
+};
+const first = fn first<T>(values: [T; 2]) -> T fastest {
+    (fn (value: T) -> ! transparent = return)(id::<T>(values: [T; 2][0i32]));
+};
+"
+        .to_owned();
+        for index in 0..32 {
+            source.push_str(&format!(
+                "const f{0} = fn f{0}(x: i{1}) -> i{1} fastest {{\n    (fn (value: i{1}) -> ! transparent = return)(first::<i{1}>((fn (_: i{1}, _: i{1}) -> [i{1}; 2] transparent = array)({0}i{1}, 1i{1})));\n}};\n",
+                index,
+                [8, 16, 32, 64][index % 4]
+            ));
+        }
+        let object_file = |threads| {
+            let mut ctx = orco::TypeInferenceContext::new();
+            let mut symbols = orco::parser::parse_unit(&mut ctx, &source).unwrap();
+            ctx.infer_symbols(symbols.values_mut());
+            check!(!ctx.diagnostics.has_errors());
+            compile(&symbols, Some(threads))
+        };
+        let object = object_file(1);
+        check!(!object.is_empty());
+        check!(object_file(4) == object);
+        check!(object_file(8) == object);
+    }
+
+    #[test]
+    fn test_aggregates() {
+        const UNIT: &str = "\
+struct Point { x: i32, y: i32 };
+const sum = fn sum(point: *mut struct Point, values: [i32; 3]) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = add)((fn (pointer: *const struct Point) -> struct Point transparent = load)(point: *mut struct Point).y, values: [i32; 3][1i32]));
+};
+";
+        let mut ctx = orco::TypeInferenceContext::new();
+        let mut symbols = orco::parser::parse_unit(&mut ctx, UNIT).unwrap();
+        ctx.infer_symbols(symbols.values_mut());
+        check!(!ctx.diagnostics.has_errors());
+        check!(!compile(&symbols, Some(1)).is_empty());
+    }
 }
@@ -0,0 +1,451 @@
+use crate::cl;
+use cranelift_module::Module;
+use std::collections::HashMap;
+
+/// Code generator of an [orco::query::Database]. Every function is compiled
+/// on its own into a [Chunk], chunks are put into an object file by [link]
+pub struct Backend {
+    /// Names of the passes to run before the code is generated, see [orco::passes::pass]
+    pub passes: Vec<String>,
+}
+
+impl orco::query::Backend for Backend {
+    fn settings(&self) -> String {
+        self.passes.join(",")
+    }
+
+    fn codegen(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        symbols: &mut orco::Symbols,
+        name: &str,
+    ) -> Vec<u8> {
+        let mut passes =
+            orco::passes::PassManager::from_names(self.passes.iter().map(String::as_str))
+                .unwrap_or_else(|name| panic!("unknown pass `{}`", name));
+        passes.run(ctx, symbols);
+        if !orco::verify::verify(ctx, symbols, &orco::layout::Target::default()) {
+            return Vec::new();
+        }
+        compile_function(symbols, name).encode()
+    }
+}
+
+/// Machine code of a function, see [compile_function]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    /// The function, followed by the instances of generic functions, that it uses
+    pub functions: Vec<CompiledFunction>,
+    /// Constant pool of the functions, see [crate::Object::constant_data]
+    pub constants: Option<Vec<u8>>,
+}
+
+/// Function, compiled to machine code, that is not in an object yet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompiledFunction {
+    /// Name of the symbol
+    pub name: String,
+    /// Instances of generic functions are local to the object
+    pub local: bool,
+    /// Alignment of the code in bytes
+    pub alignment: u64,
+    /// Machine code
+    pub code: Vec<u8>,
+    /// Relocations, that are applied to the code in the object
+    pub relocations: Vec<Relocation>,
+}
+
+/// Relocation of a [CompiledFunction]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    /// Offset in the code
+    pub offset: u32,
+    /// Kind of the relocation
+    pub kind: cl::codegen::binemit::Reloc,
+    /// What the relocation points to
+    pub target: Target,
+    /// Added to the address of the target
+    pub addend: i64,
+}
+
+/// Target of a [Relocation]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Function by its symbol name
+    Function(String),
+    /// Constant pool of the chunk
+    Constants,
+    /// Runtime library function, like `memcpy`
+    LibCall(cl::codegen::ir::LibCall),
+    /// Offset in the code of the function itself
+    Offset(u32),
+}
+
+/// Relocation kinds, that x86-64 code uses
+const RELOCATION_KINDS: &[cl::codegen::binemit::Reloc] = {
+    use cl::codegen::binemit::Reloc::*;
+    &[
+        Abs4,
+        Abs8,
+        X86PCRel4,
+        X86CallPCRel4,
+        X86CallPLTRel4,
+        X86GOTPCRel4,
+    ]
+};
+
+/// Compile a single function of the unit, the other symbols are only declared.
+/// The same function always compiles to the same chunk
+pub fn compile_function(symbols: &orco::Symbols, name: &str) -> Chunk {
+    let mut object = crate::Object::new("x86_64-unknown-linux-gnu");
+    for (name, symbol) in symbols {
+        object.declare_symbol(name, symbol);
+    }
+    object.build_symbol(name, &symbols[name]);
+    let compiled = object.compile_pending(1);
+
+    let functions = compiled
+        .iter()
+        .map(|(id, ctx)| {
+            let code = ctx.compiled_code().unwrap();
+            let declaration = object.object.declarations().get_function_decl(*id);
+            let relocations = code
+                .buffer
+                .relocs()
+                .iter()
+                .map(|relocation| {
+                    use cl::codegen::ir::ExternalName;
+                    use cl::codegen::FinalizedRelocTarget;
+                    let target = match &relocation.target {
+                        FinalizedRelocTarget::Func(offset) => Target::Offset(*offset),
+                        FinalizedRelocTarget::ExternalName(ExternalName::User(reference)) => {
+                            let name = &ctx.func.params.user_named_funcs()[*reference];
+                            match name.namespace {
+                                0 => {
+                                    let id = cl::FuncId::from_u32(name.index);
+                                    let declaration =
+                                        object.object.declarations().get_function_decl(id);
+                                    Target::Function(declaration.name.clone().unwrap())
+                                }
+                                // The constant pool is the only data
+                                1 => Target::Constants,
+                                _ => unreachable!("unknown namespace"),
+                            }
+                        }
+                        FinalizedRelocTarget::ExternalName(ExternalName::LibCall(libcall)) => {
+                            Target::LibCall(*libcall)
+                        }
+                        FinalizedRelocTarget::ExternalName(_) => {
+                            unreachable!("functions are named by the module")
+                        }
+                    };
+                    Relocation {
+                        offset: relocation.offset,
+                        kind: relocation.kind,
+                        target,
+                        addend: relocation.addend,
+                    }
+                })
+                .collect();
+            CompiledFunction {
+                name: declaration.name.clone().unwrap(),
+                local: declaration.linkage == cl::Linkage::Local,
+                alignment: code.buffer.alignment as u64,
+                code: code.code_buffer().to_vec(),
+                relocations,
+            }
+        })
+        .collect();
+    Chunk {
+        functions,
+        constants: object.constant_data.map(|(_, data)| data),
+    }
+}
+
+/// Put compiled functions into the bytes of an object file.
+/// Functions, that none of the chunks define, are imported
+pub fn link<'a>(chunks: impl IntoIterator<Item = &'a Chunk>) -> Vec<u8> {
+    let chunks = chunks.into_iter().collect::<Vec<_>>();
+    let mut object = crate::Object::new("x86_64-unknown-linux-gnu");
+    // Signatures don't get into the object, the one of each function is already compiled in
+    let signature = object.object.make_signature();
+
+    let mut ids = HashMap::new();
+    let mut defined = Vec::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        for function in &chunk.functions {
+            // Instances of a generic function are the same in every chunk
+            if ids.contains_key(&function.name) {
+                continue;
+            }
+            let linkage = match function.local {
+                true => cl::Linkage::Local,
+                false => cl::Linkage::Export,
+            };
+            let id = object
+                .object
+                .declare_function(&function.name, linkage, &signature)
+                .unwrap();
+            ids.insert(function.name.clone(), id);
+            defined.push((index, id, function));
+        }
+    }
+
+    let pools = chunks
+        .iter()
+        .map(|chunk| {
+            let data = chunk.constants.as_ref()?;
+            let id = object.object.declare_anonymous_data(false, false).unwrap();
+            object
+                .object
+                .define_data(
+                    id,
+                    &cl::DataDescription {
+                        init: cl::Init::Bytes {
+                            contents: data.as_slice().into(),
+                        },
+                        function_decls: Default::default(),
+                        data_decls: Default::default(),
+                        function_relocs: Default::default(),
+                        data_relocs: Default::default(),
+                        custom_segment_section: Default::default(),
+                        align: Some(16),
+                    },
+                )
+                .unwrap();
+            Some(id)
+        })
+        .collect::<Vec<_>>();
+
+    for (index, id, function) in defined {
+        let mut func = cl::codegen::ir::Function::new();
+        let relocations = function
+            .relocations
+            .iter()
+            .map(|relocation| {
+                use cl::codegen::ir::{ExternalName, UserExternalName};
+                use cl::codegen::FinalizedRelocTarget;
+                let target = match &relocation.target {
+                    Target::Function(name) => {
+                        let id = *ids.entry(name.clone()).or_insert_with(|| {
+                            object
+                                .object
+                                .declare_function(name, cl::Linkage::Import, &signature)
+                                .unwrap()
+                        });
+                        let reference = func
+                            .declare_imported_user_function(UserExternalName::new(0, id.as_u32()));
+                        FinalizedRelocTarget::ExternalName(ExternalName::User(reference))
+                    }
+                    Target::Constants => {
+                        let id = pools[index].expect("chunk has no constant pool");
+                        let reference = func
+                            .declare_imported_user_function(UserExternalName::new(1, id.as_u32()));
+                        FinalizedRelocTarget::ExternalName(ExternalName::User(reference))
+                    }
+                    Target::LibCall(libcall) => {
+                        FinalizedRelocTarget::ExternalName(ExternalName::LibCall(*libcall))
+                    }
+                    Target::Offset(offset) => FinalizedRelocTarget::Func(*offset),
+                };
+                cl::codegen::FinalizedMachReloc {
+                    offset: relocation.offset,
+                    kind: relocation.kind,
+                    target,
+                    addend: relocation.addend,
+                }
+            })
+            .collect::<Vec<_>>();
+        object
+            .object
+            .define_function_bytes(id, &func, function.alignment, &function.code, &relocations)
+            .unwrap();
+    }
+
+    object.object.finish().emit().unwrap()
+}
+
+impl Chunk {
+    /// Bytes of the chunk, see [Chunk::decode]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder(Vec::new());
+        encoder.number(self.functions.len() as _);
+        for function in &self.functions {
+            encoder.bytes(function.name.as_bytes());
+            encoder.number(function.local as _);
+            encoder.number(function.alignment);
+            encoder.bytes(&function.code);
+            encoder.number(function.relocations.len() as _);
+            for relocation in &function.relocations {
+                encoder.number(relocation.offset as _);
+                encoder.bytes(relocation.kind.to_string().as_bytes());
+                match &relocation.target {
+                    Target::Function(name) => {
+                        encoder.number(0);
+                        encoder.bytes(name.as_bytes());
+                    }
+                    Target::Constants => encoder.number(1),
+                    Target::LibCall(libcall) => {
+                        encoder.number(2);
+                        encoder.bytes(libcall.to_string().as_bytes());
+                    }
+                    Target::Offset(offset) => {
+                        encoder.number(3);
+                        encoder.number(*offset as _);
+                    }
+                }
+                encoder.number(relocation.addend as _);
+            }
+        }
+        match &self.constants {
+            Some(constants) => {
+                encoder.number(1);
+                encoder.bytes(constants);
+            }
+            None => encoder.number(0),
+        }
+        encoder.0
+    }
+
+    /// Read a chunk, written by [Chunk::encode]. [None] if the bytes are not a chunk
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut decoder = Decoder(bytes);
+        let functions = (0..decoder.number()?)
+            .map(|_| {
+                let name = decoder.string()?;
+                let local = decoder.number()? != 0;
+                let alignment = decoder.number()?;
+                let code = decoder.bytes()?.to_vec();
+                let relocations = (0..decoder.number()?)
+                    .map(|_| {
+                        let offset = decoder.number()? as _;
+                        let kind = decoder.string()?;
+                        let kind = *RELOCATION_KINDS
+                            .iter()
+                            .find(|known| known.to_string() == kind)?;
+                        let target = match decoder.number()? {
+                            0 => Target::Function(decoder.string()?),
+                            1 => Target::Constants,
+                            2 => Target::LibCall(decoder.string()?.parse().ok()?),
+                            3 => Target::Offset(decoder.number()? as _),
+                            _ => return None,
+                        };
+                        Some(Relocation {
+                            offset,
+                            kind,
+                            target,
+                            addend: decoder.number()? as _,
+                        })
+                    })
+                    .collect::<Option<_>>()?;
+                Some(CompiledFunction {
+                    name,
+                    local,
+                    alignment,
+                    code,
+                    relocations,
+                })
+            })
+            .collect::<Option<_>>()?;
+        let constants = match decoder.number()? {
+            0 => None,
+            _ => Some(decoder.bytes()?.to_vec()),
+        };
+        decoder.0.is_empty().then_some(Self {
+            functions,
+            constants,
+        })
+    }
+}
+
+/// Numbers are little-endian, byte strings are prefixed with their length
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn number(&mut self, number: u64) {
+        self.0.extend_from_slice(&number.to_le_bytes());
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.number(bytes.len() as _);
+        self.0.extend_from_slice(bytes);
+    }
+}
+
+struct Decoder<'a>(&'a [u8]);
+
+impl<'a> Decoder<'a> {
+    fn number(&mut self) -> Option<u64> {
+        let (number, rest) = self.0.split_first_chunk()?;
+        self.0 = rest;
+        Some(u64::from_le_bytes(*number))
+    }
+
+    fn bytes(&mut self) -> Option<&'a [u8]> {
+        let length = self.number()?.try_into().ok()?;
+        let bytes = self.0.get(..length)?;
+        self.0 = &self.0[length..];
+        Some(bytes)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        String::from_utf8(self.bytes()?.to_vec()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    const UNIT: &str = "\
+const twice = fn twice(x: i32) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = mul)(x: i32, 2i32));
+};
+const main = fn main() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)(twice(21i32));
+};
+";
+
+    #[test]
+    fn test_chunks() {
+        let mut ctx = orco::TypeInferenceContext::new();
+        let mut symbols = orco::parser::parse_unit(&mut ctx, UNIT).unwrap();
+        ctx.infer_symbols(symbols.values_mut());
+        check!(!ctx.diagnostics.has_errors());
+
+        let main = compile_function(&symbols, "main");
+        check!(main == compile_function(&symbols, "main"));
+        check!(Chunk::decode(&main.encode()) == Some(main.clone()));
+        check!(Chunk::decode(&main.encode()[1..]).is_none());
+        let_assert!([function] = &main.functions[..]);
+        check!(function.name == "main");
+        check!(function
+            .relocations
+            .iter()
+            .any(|relocation| relocation.target == Target::Function("twice".to_owned())));
+
+        // Same as compiling the whole unit at once
+        let twice = compile_function(&symbols, "twice");
+        check!(link([&main, &twice]) == crate::compile(&symbols, Some(1)));
+    }
+
+    #[test]
+    fn test_backend() {
+        let mut db = orco::query::Database::new();
+        db.set_source("unit.orco", UNIT.to_owned());
+        db.set_backend(Backend {
+            passes: orco::passes::DEFAULT_PIPELINE
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        });
+        let code = db.code("unit.orco");
+        check!(code.len() == 2);
+        let chunks = code
+            .iter()
+            .map(|(_, code)| Chunk::decode(code).unwrap())
+            .collect::<Vec<_>>();
+        check!(!link(&chunks).is_empty());
+    }
+}
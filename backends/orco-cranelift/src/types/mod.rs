@@ -1,9 +1,10 @@
 use crate::cl;
+use cranelift_module::Module;
 
 impl crate::Object {
     pub fn convert_type(&self, ty: &orco::Type) -> Vec<cl::AbiParam> {
         match ty {
-            orco::Type::Wildcard => {
+            orco::Type::Wildcard | orco::Type::Variable(_) => {
                 panic!("Wildcard type encountered on backend phase. Type inference was most likely not done correctly")
             }
             orco::Type::Never => Vec::new(),
@@ -20,6 +21,20 @@ impl crate::Object {
                 _ => cl::types::INVALID,
             })],
             orco::Type::Fn(function_signature) => todo!(),
+            // Aggregates are passed by pointer
+            orco::Type::Pointer(..)
+            | orco::Type::Struct(_)
+            | orco::Type::Array(..)
+            | orco::Type::Slice(..) => vec![cl::AbiParam::new(
+                self.object.target_config().pointer_type(),
+            )],
+            orco::Type::Parameter(name) => match self.type_arguments.get(name) {
+                Some(r#type) => self.convert_type(r#type),
+                None => panic!(
+                    "Type parameter {} has no argument, generic functions can only be built as instances",
+                    name
+                ),
+            },
             orco::Type::Unresolved(_) => todo!(),
         }
     }
@@ -29,14 +44,51 @@ impl crate::Object {
         &self,
         signature: &orco::types::FunctionSignature,
     ) -> cl::Signature {
-        cl::Signature {
-            params: signature
-                .parameters
-                .iter()
-                .flat_map(|(name, ty)| self.convert_type(ty).into_iter())
+        let params = signature
+            .parameters
+            .iter()
+            .flat_map(|(name, ty)| self.convert_type(ty).into_iter());
+        // Aggregates are returned through a pointer to caller's stack slot
+        if self.is_aggregate(&signature.return_type) {
+            cl::Signature {
+                params: std::iter::once(cl::AbiParam::special(
+                    self.object.target_config().pointer_type(),
+                    cl::ArgumentPurpose::StructReturn,
+                ))
+                .chain(params)
                 .collect(),
-            returns: self.convert_type(signature.return_type.as_ref()),
-            call_conv: cl::isa::CallConv::SystemV,
+                returns: Vec::new(),
+                call_conv: cl::isa::CallConv::SystemV,
+            }
+        } else {
+            cl::Signature {
+                params: params.collect(),
+                returns: self.convert_type(signature.return_type.as_ref()),
+                call_conv: cl::isa::CallConv::SystemV,
+            }
+        }
+    }
+
+    /// Is this type a struct, an array or a slice? Aggregates live in memory,
+    /// their values are addresses
+    pub fn is_aggregate(&self, r#type: &orco::Type) -> bool {
+        match r#type {
+            orco::Type::Struct(_) | orco::Type::Array(..) | orco::Type::Slice(..) => true,
+            orco::Type::Parameter(name) => self
+                .type_arguments
+                .get(name)
+                .is_some_and(|r#type| self.is_aggregate(r#type)),
+            _ => false,
         }
     }
+
+    /// Replace type parameters with the type arguments of the instance, that is being built
+    pub fn substitute(&self, r#type: &orco::Type) -> orco::Type {
+        r#type.substitute(&self.type_arguments)
+    }
+
+    /// Layout of a type on the target, see [orco::layout::Target::layout]
+    pub fn layout(&self, r#type: &orco::Type) -> orco::layout::Layout {
+        self.target.layout(&self.substitute(r#type))
+    }
 }
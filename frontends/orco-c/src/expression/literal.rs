@@ -3,6 +3,7 @@ use super::*;
 #[derive(Clone, PartialEq, Eq, Parse, ToTokens)]
 pub enum Literal {
     Integer(LitUint),
+    Float(LitFloat),
 }
 impl Literal {
     pub fn build(&self, ctx: &mut orco::TypeInferenceContext) -> orco::expression::Literal {
@@ -10,6 +11,9 @@ impl Literal {
             Self::Integer(literal) => {
                 orco::expression::Literal::Integer(literal.value() as _, orco::Type::Wildcard)
             }
+            Self::Float(literal) => {
+                orco::expression::Literal::Float(f64::from(literal.value()), orco::Type::Wildcard)
+            }
         }
     }
 }
@@ -1,31 +1,531 @@
 use super::*;
-use parsel::{ast::LitUint, syn::Ident};
+use parsel::{
+    ast::{Brace, Bracket, LitFloat, LitStr, LitUint, Paren, Punctuated},
+    syn::{parse::ParseStream, token::Comma, Ident, Token},
+};
 
 // pub mod functions;
 // pub use functions::FunctionCall;
 pub mod literal;
 pub use literal::Literal;
+pub mod operator;
+pub use operator::{BinaryOperator, UnaryOperator};
 
-#[derive(Clone, PartialEq, Eq, Parse, ToTokens)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum Expression {
     Literal(Literal),
+    /// String literal, a `char` array with a trailing zero
+    String(LitStr),
     Variable(Ident),
+    /// Function call, `f(1, 2)`
+    Call(Ident, Paren<Punctuated<Expression, Comma>>),
+    Paren(Paren<Box<Expression>>),
+    /// Compound literal, `(struct Foo) { 1, 2 }`
+    Compound(Paren<Type>, Brace<Punctuated<Expression, Comma>>),
+    /// Initializer list, `{ 1, 2, 3 }`. Only arrays can be initialized with it for now
+    Initializer(Brace<Punctuated<Expression, Comma>>),
+    Field(Box<Expression>, Token![.], Ident),
+    /// `pointer->field`, sugar for `(*pointer).field`
+    Arrow(Box<Expression>, Token![->], Ident),
+    /// `(int) value`
+    Cast(Paren<Type>, Box<Expression>),
+    Index(Box<Expression>, Bracket<Box<Expression>>),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(Box<Expression>, BinaryOperator, Box<Expression>),
+}
+
+impl Parse for Expression {
+    fn parse(input: ParseStream) -> parsel::Result<Self> {
+        Self::parse_binary(input, 0)
+    }
+}
+
+impl Expression {
+    /// Precedence climbing, parses operators binding at least as tight as `min_precedence`
+    fn parse_binary(input: ParseStream, min_precedence: u8) -> parsel::Result<Self> {
+        let mut lhs = Self::parse_unary(input)?;
+        while let Ok(operator) = input.fork().parse::<BinaryOperator>() {
+            let precedence = operator.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            input.parse::<BinaryOperator>()?;
+            let rhs = Self::parse_binary(
+                input,
+                if operator.is_right_associative() {
+                    precedence
+                } else {
+                    precedence + 1
+                },
+            )?;
+            lhs = Self::Binary(Box::new(lhs), operator, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(input: ParseStream) -> parsel::Result<Self> {
+        if input.fork().parse::<UnaryOperator>().is_ok() {
+            let operator = input.parse()?;
+            return Ok(Self::Unary(operator, Box::new(Self::parse_unary(input)?)));
+        }
+        let mut expression = Self::parse_primary(input)?;
+        loop {
+            expression = if input.peek(Token![.]) {
+                Self::Field(Box::new(expression), input.parse()?, input.parse()?)
+            } else if input.peek(Token![->]) {
+                Self::Arrow(Box::new(expression), input.parse()?, input.parse()?)
+            } else if input.peek(parsel::syn::token::Bracket) {
+                Self::Index(Box::new(expression), input.parse()?)
+            } else {
+                break;
+            };
+        }
+        Ok(expression)
+    }
+
+    fn parse_primary(input: ParseStream) -> parsel::Result<Self> {
+        if input.peek(parsel::syn::token::Paren) {
+            let fork = input.fork();
+            if fork.parse::<Paren<Type>>().is_ok() {
+                if fork.peek(parsel::syn::token::Brace) {
+                    return Ok(Self::Compound(input.parse()?, input.parse()?));
+                }
+                return Ok(Self::Cast(
+                    input.parse()?,
+                    Box::new(Self::parse_unary(input)?),
+                ));
+            }
+            return Ok(Self::Paren(input.parse()?));
+        }
+        if input.peek(parsel::syn::token::Brace) {
+            return Ok(Self::Initializer(input.parse()?));
+        }
+        if input.peek(parsel::syn::LitInt) || input.peek(parsel::syn::LitFloat) {
+            return Ok(Self::Literal(input.parse()?));
+        }
+        if input.peek(parsel::syn::LitStr) {
+            return Ok(Self::String(input.parse()?));
+        }
+        if input.peek(Ident) && input.peek2(parsel::syn::token::Paren) {
+            return Ok(Self::Call(input.parse()?, input.parse()?));
+        }
+        Ok(Self::Variable(input.parse()?))
+    }
+}
+
+impl Expression {
+    /// Can this expression be assigned to or have it's address taken?
+    pub fn is_place(&self) -> bool {
+        match self {
+            Self::Variable(_)
+            | Self::Field(..)
+            | Self::Arrow(..)
+            | Self::Index(..)
+            | Self::Unary(UnaryOperator::Deref(_), _) => true,
+            Self::Paren(expression) => expression.is_place(),
+            _ => false,
+        }
+    }
+}
+
+impl ToTokens for Expression {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            Self::Literal(literal) => literal.to_tokens(tokens),
+            Self::String(string) => string.to_tokens(tokens),
+            Self::Variable(ident) => ident.to_tokens(tokens),
+            Self::Call(name, args) => {
+                name.to_tokens(tokens);
+                args.to_tokens(tokens);
+            }
+            Self::Paren(expression) => expression.to_tokens(tokens),
+            Self::Compound(r#type, fields) => {
+                r#type.to_tokens(tokens);
+                fields.to_tokens(tokens);
+            }
+            Self::Initializer(elements) => elements.to_tokens(tokens),
+            Self::Field(value, dot, name) => {
+                value.to_tokens(tokens);
+                dot.to_tokens(tokens);
+                name.to_tokens(tokens);
+            }
+            Self::Arrow(pointer, arrow, name) => {
+                pointer.to_tokens(tokens);
+                arrow.to_tokens(tokens);
+                name.to_tokens(tokens);
+            }
+            Self::Cast(r#type, value) => {
+                r#type.to_tokens(tokens);
+                value.to_tokens(tokens);
+            }
+            Self::Index(value, index) => {
+                value.to_tokens(tokens);
+                index.to_tokens(tokens);
+            }
+            Self::Unary(operator, value) => {
+                operator.to_tokens(tokens);
+                value.to_tokens(tokens);
+            }
+            Self::Binary(lhs, operator, rhs) => {
+                lhs.to_tokens(tokens);
+                operator.to_tokens(tokens);
+                rhs.to_tokens(tokens);
+            }
+        }
+    }
 }
 
 impl Expression {
     pub fn build(
         &self,
         ctx: &mut orco::TypeInferenceContext,
-        _expressions: &mut Vec<orco::Expression>,
+        expressions: &mut Vec<orco::Expression>,
     ) -> orco::Expression {
+        use orco::type_inference::{
+            intrinsics::{Cast, Intrinsic},
+            TypeVariableKind,
+        };
+        let span = span(ctx, self);
+        let call = |function, args| {
+            orco::Expression::Call(orco::expression::Call {
+                span,
+                ..orco::expression::Call::new(function, args)
+            })
+        };
+        // Operators dispatch through traits, so they can be overloaded
+        let dispatch = |ctx: &orco::TypeInferenceContext, intrinsic: Intrinsic, args| {
+            orco::Expression::TraitCall(orco::expression::TraitCall {
+                span,
+                ..orco::expression::TraitCall::new(
+                    ctx.operator(intrinsic),
+                    intrinsic.to_string(),
+                    args,
+                )
+            })
+        };
         match self {
-            Expression::Literal(literal) => orco::Expression::Literal(literal.build(ctx)),
+            Expression::Literal(literal) => {
+                orco::Expression::Literal(orco::Spanned::new(literal.build(ctx), span))
+            }
+            Expression::String(_) | Expression::Initializer(_) => {
+                let elements = self.build_elements(ctx, expressions).unwrap();
+                let r#type = match self {
+                    Expression::String(_) => orco::Type::Integer(8),
+                    _ => ctx.new_type_variable(TypeVariableKind::General),
+                };
+                let len = elements.len() as u64;
+                call(ctx.intrinsics.array(r#type, len), elements)
+            }
             Expression::Variable(ident) => {
                 let Some(variable) = ctx.resolve_variable(&ident.to_string()) else {
-                    todo!("Error")
+                    // There is no preprocessor yet
+                    if ident == "NULL" {
+                        return orco::Expression::Literal(orco::Spanned::new(
+                            orco::expression::Literal::Null(orco::Type::Wildcard),
+                            span,
+                        ));
+                    }
+                    return orco::Expression::Error(
+                        ctx.diagnostics.report(
+                            orco::Diagnostic::error(
+                                "E0001",
+                                format!("cannot find variable `{}` in this scope", ident),
+                            )
+                            .with_label(span, "not found in this scope"),
+                        ),
+                    );
+                };
+                orco::Expression::Variable(orco::Spanned::new(variable, span))
+            }
+            Expression::Call(name, args) => {
+                let path = orco::Path(vec![name.to_string()]);
+                let function = match ctx.module.lookup(&orco::Path::new(), &path) {
+                    Ok(orco::Symbol::Function(function)) => Ok(function.clone()),
+                    Ok(orco::Symbol::Constant(_)) => Err(orco::Diagnostic::error(
+                        "E0014",
+                        format!("`{}` is not a function", name),
+                    )),
+                    Err(error) => Err(orco::Diagnostic::from(error)),
+                };
+                let function = match function {
+                    Ok(function) => function,
+                    Err(diagnostic) => {
+                        let span = crate::span(ctx, name);
+                        return orco::Expression::Error(
+                            ctx.diagnostics
+                                .report(diagnostic.with_label(span, "can't call this")),
+                        );
+                    }
+                };
+                let args = args.iter().map(|arg| arg.build(ctx, expressions)).collect();
+                call(function, args)
+            }
+            Expression::Paren(expression) => expression.build(ctx, expressions),
+            Expression::Compound(r#type, fields) => {
+                let r#type = r#type.as_orco(ctx);
+                let fields = fields
+                    .iter()
+                    .map(|field| field.build(ctx, expressions))
+                    .collect();
+                let orco::Type::Struct(r#struct) = r#type else {
+                    return orco::Expression::Error(
+                        ctx.diagnostics.report(
+                            orco::Diagnostic::error(
+                                "E0008",
+                                "compound literals are only supported for structs",
+                            )
+                            .with_label(span, "not a struct"),
+                        ),
+                    );
+                };
+                call(ctx.intrinsics.construct(r#struct), fields)
+            }
+            Expression::Field(value, _, name) => {
+                let value = value.build(ctx, expressions);
+                orco::Expression::Field(orco::expression::FieldAccess {
+                    span,
+                    ..orco::expression::FieldAccess::new(value, name.to_string())
+                })
+            }
+            Expression::Arrow(pointer, _, name) => {
+                let pointer = pointer.build(ctx, expressions);
+                let r#type = ctx.new_type_variable(TypeVariableKind::General);
+                let value = call(ctx.intrinsics.load(r#type), vec![pointer]);
+                orco::Expression::Field(orco::expression::FieldAccess {
+                    span,
+                    ..orco::expression::FieldAccess::new(value, name.to_string())
+                })
+            }
+            Expression::Cast(r#type, value) => {
+                let r#type = r#type.as_orco(ctx);
+                let value = value.build(ctx, expressions);
+                let from = ctx.new_type_variable(TypeVariableKind::General);
+                call(
+                    ctx.intrinsics.cast(Cast::Convert, from, r#type),
+                    vec![value],
+                )
+            }
+            Expression::Index(value, index) => {
+                let value = value.build(ctx, expressions);
+                let index = index.build(ctx, expressions);
+                orco::Expression::Index(orco::expression::Index {
+                    span,
+                    ..orco::expression::Index::new(value, index)
+                })
+            }
+            Expression::Unary(operator, value) => {
+                if let UnaryOperator::AddressOf(_) = operator {
+                    if !value.is_place() {
+                        return orco::Expression::Error(
+                            ctx.diagnostics.report(
+                                orco::Diagnostic::error(
+                                    "E0009",
+                                    "can't take the address of this expression",
+                                )
+                                .with_label(span, "not a variable, a field or a dereference"),
+                            ),
+                        );
+                    }
+                }
+                let value = value.build(ctx, expressions);
+                let r#type = ctx.new_type_variable(TypeVariableKind::General);
+                match operator {
+                    UnaryOperator::Neg(_) => dispatch(ctx, Intrinsic::Neg, vec![value]),
+                    UnaryOperator::Not(_) => dispatch(ctx, Intrinsic::Not, vec![value]),
+                    // C has no booleans, `!x` is `x == 0`
+                    UnaryOperator::LogicalNot(_) => dispatch(
+                        ctx,
+                        Intrinsic::Eq,
+                        vec![
+                            value,
+                            orco::Expression::Literal(orco::Spanned::new(
+                                orco::expression::Literal::Integer(0, orco::Type::Wildcard),
+                                span,
+                            )),
+                        ],
+                    ),
+                    UnaryOperator::Deref(_) => call(ctx.intrinsics.load(r#type), vec![value]),
+                    UnaryOperator::AddressOf(_) => {
+                        call(ctx.intrinsics.address_of(r#type), vec![value])
+                    }
+                }
+            }
+            Expression::Binary(lhs, operator, rhs) => {
+                if let BinaryOperator::LogicalAnd(_) | BinaryOperator::LogicalOr(_) = operator {
+                    // `a && b` is `a ? b : 0` and `a || b` is `a ? 1 : b`,
+                    // so `b` is only evaluated if it decides the result
+                    let condition = lhs.build_condition(ctx, expressions);
+                    let mut rest = Vec::new();
+                    let value = rhs.build_condition(ctx, &mut rest);
+                    rest.push(value);
+                    let constant = |value| {
+                        vec![orco::Expression::Literal(orco::Spanned::new(
+                            orco::expression::Literal::Bool(value),
+                            span,
+                        ))]
+                    };
+                    let (then_block, else_block) = match operator {
+                        BinaryOperator::LogicalAnd(_) => (rest, constant(false)),
+                        _ => (constant(true), rest),
+                    };
+                    let block = |expressions| {
+                        orco::Expression::Function(orco::expression::Function::new(
+                            orco::function_signature![() -> bool transparent],
+                            None,
+                            expressions,
+                        ))
+                    };
+                    return call(
+                        ctx.intrinsics.branch(orco::Type::Bool),
+                        vec![condition, block(then_block), block(else_block)],
+                    );
+                }
+                if let Some(intrinsic) = operator.intrinsic() {
+                    let lhs = lhs.build(ctx, expressions);
+                    let rhs = rhs.build(ctx, expressions);
+                    return dispatch(ctx, intrinsic, vec![lhs, rhs]);
+                }
+                let r#type = ctx.new_type_variable(TypeVariableKind::General);
+                // `a op= b` assigns `a op b`, so the place is evaluated twice
+                let value = |ctx: &mut orco::TypeInferenceContext,
+                             expressions: &mut Vec<orco::Expression>| {
+                    match operator.compound() {
+                        Some(intrinsic) => {
+                            let current = lhs.build(ctx, expressions);
+                            let rhs = rhs.build(ctx, expressions);
+                            dispatch(ctx, intrinsic, vec![current, rhs])
+                        }
+                        None => rhs.build(ctx, expressions),
+                    }
                 };
-                orco::Expression::Variable(variable)
+
+                // `*pointer = value`
+                if let Expression::Unary(UnaryOperator::Deref(_), pointer) = lhs.as_ref() {
+                    let pointer = pointer.build(ctx, expressions);
+                    let value = value(ctx, expressions);
+                    return call(ctx.intrinsics.store(r#type), vec![pointer, value]);
+                }
+                if !lhs.is_place() {
+                    return orco::Expression::Error(
+                        ctx.diagnostics.report(
+                            orco::Diagnostic::error(
+                                "E0004",
+                                "invalid left-hand side of assignment",
+                            )
+                            .with_label(crate::span(ctx, lhs), "can't assign to this expression"),
+                        ),
+                    );
+                }
+                let lhs = lhs.build(ctx, expressions);
+                let rhs = value(ctx, expressions);
+                call(ctx.intrinsics.assign(r#type), vec![lhs, rhs])
+            }
+        }
+    }
+}
+
+impl Expression {
+    /// Build a condition of a branch or a loop. Conditions in C are
+    /// scalars, so they are implicitly compared to zero
+    pub fn build_condition(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        expressions: &mut Vec<orco::Expression>,
+    ) -> orco::Expression {
+        use orco::type_inference::{intrinsics::Cast, TypeVariableKind};
+        let span = span(ctx, self);
+        let value = self.build(ctx, expressions);
+        let from = ctx.new_type_variable(TypeVariableKind::General);
+        orco::Expression::Call(orco::expression::Call {
+            span,
+            ..orco::expression::Call::new(
+                ctx.intrinsics
+                    .implicit_cast(Cast::Convert, from, orco::Type::Bool),
+                vec![value],
+            )
+        })
+    }
+
+    /// Number of elements of an initializer list or a string literal, including the trailing zero
+    pub fn initializer_len(&self) -> Option<u64> {
+        match self {
+            Self::String(string) => Some(string.value().len() as u64 + 1),
+            Self::Initializer(elements) => Some(elements.len() as u64),
+            Self::Paren(expression) => expression.initializer_len(),
+            _ => None,
+        }
+    }
+
+    /// Build elements of an initializer list or bytes of a string literal.
+    /// Returns [None] for other expressions
+    pub fn build_elements(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        expressions: &mut Vec<orco::Expression>,
+    ) -> Option<Vec<orco::Expression>> {
+        match self {
+            Self::String(string) => {
+                let span = span(ctx, string);
+                Some(
+                    string
+                        .value()
+                        .bytes()
+                        .chain(std::iter::once(0))
+                        .map(|byte| {
+                            orco::Expression::Literal(orco::Spanned::new(
+                                orco::expression::Literal::Integer(
+                                    byte as _,
+                                    orco::Type::Integer(8),
+                                ),
+                                span,
+                            ))
+                        })
+                        .collect(),
+                )
             }
+            Self::Initializer(elements) => Some(
+                elements
+                    .iter()
+                    .map(|element| element.build(ctx, expressions))
+                    .collect(),
+            ),
+            Self::Paren(expression) => expression.build_elements(ctx, expressions),
+            _ => None,
+        }
+    }
+
+    /// Build an initializer of an array, missing elements are zeroed like in C
+    pub fn build_array_initializer(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        expressions: &mut Vec<orco::Expression>,
+        element: &orco::Type,
+        len: u64,
+    ) -> orco::Expression {
+        let Some(mut elements) = self.build_elements(ctx, expressions) else {
+            return self.build(ctx, expressions);
+        };
+        let span = span(ctx, self);
+        if elements.len() as u64 > len {
+            return orco::Expression::Error(
+                ctx.diagnostics.report(
+                    orco::Diagnostic::error(
+                        "E0011",
+                        format!("too many elements in an initializer of an array of {}", len),
+                    )
+                    .with_label(span, "too many elements"),
+                ),
+            );
+        }
+        while (elements.len() as u64) < len {
+            elements.push(orco::Expression::Literal(orco::Spanned::new(
+                orco::expression::Literal::Integer(0, orco::Type::Wildcard),
+                span,
+            )));
         }
+        orco::Expression::Call(orco::expression::Call {
+            span,
+            ..orco::expression::Call::new(ctx.intrinsics.array(element.clone(), len), elements)
+        })
     }
 }
@@ -0,0 +1,113 @@
+use super::*;
+use orco::type_inference::intrinsics::Intrinsic;
+use parsel::syn::Token;
+
+/// Binary operator. Multi-character operators come first,
+/// so that they are not parsed as their one-character prefixes
+#[derive(Clone, Copy, PartialEq, Eq, Parse, ToTokens)]
+pub enum BinaryOperator {
+    ShlAssign(Token![<<=]),
+    ShrAssign(Token![>>=]),
+    AddAssign(Token![+=]),
+    SubAssign(Token![-=]),
+    MulAssign(Token![*=]),
+    DivAssign(Token![/=]),
+    RemAssign(Token![%=]),
+    AndAssign(Token![&=]),
+    OrAssign(Token![|=]),
+    XorAssign(Token![^=]),
+    LogicalAnd(Token![&&]),
+    LogicalOr(Token![||]),
+    Shl(Token![<<]),
+    Shr(Token![>>]),
+    Le(Token![<=]),
+    Ge(Token![>=]),
+    Eq(Token![==]),
+    Ne(Token![!=]),
+    Add(Token![+]),
+    Sub(Token![-]),
+    Mul(Token![*]),
+    Div(Token![/]),
+    Rem(Token![%]),
+    And(Token![&]),
+    Or(Token![|]),
+    Xor(Token![^]),
+    Lt(Token![<]),
+    Gt(Token![>]),
+    Assign(Token![=]),
+}
+
+impl BinaryOperator {
+    /// Binding power, higher binds tighter
+    pub fn precedence(self) -> u8 {
+        match self {
+            Self::Mul(_) | Self::Div(_) | Self::Rem(_) => 12,
+            Self::Add(_) | Self::Sub(_) => 11,
+            Self::Shl(_) | Self::Shr(_) => 10,
+            Self::Lt(_) | Self::Le(_) | Self::Gt(_) | Self::Ge(_) => 9,
+            Self::Eq(_) | Self::Ne(_) => 8,
+            Self::And(_) => 7,
+            Self::Xor(_) => 6,
+            Self::Or(_) => 5,
+            Self::LogicalAnd(_) => 4,
+            Self::LogicalOr(_) => 3,
+            _ => 1,
+        }
+    }
+
+    /// Is `a op b op c` parsed as `a op (b op c)`?
+    pub fn is_right_associative(self) -> bool {
+        self.precedence() == 1
+    }
+
+    /// Intrinsic of a compound assignment, like [Intrinsic::Add] for `+=`
+    pub fn compound(self) -> Option<Intrinsic> {
+        Some(match self {
+            Self::ShlAssign(_) => Intrinsic::Shl,
+            Self::ShrAssign(_) => Intrinsic::Shr,
+            Self::AddAssign(_) => Intrinsic::Add,
+            Self::SubAssign(_) => Intrinsic::Sub,
+            Self::MulAssign(_) => Intrinsic::Mul,
+            Self::DivAssign(_) => Intrinsic::Div,
+            Self::RemAssign(_) => Intrinsic::Rem,
+            Self::AndAssign(_) => Intrinsic::And,
+            Self::OrAssign(_) => Intrinsic::Or,
+            Self::XorAssign(_) => Intrinsic::Xor,
+            _ => return None,
+        })
+    }
+
+    /// Intrinsic, implementing this operator. Assignments and
+    /// short-circuiting operators are handled separately
+    pub fn intrinsic(self) -> Option<Intrinsic> {
+        Some(match self {
+            Self::Shl(_) => Intrinsic::Shl,
+            Self::Shr(_) => Intrinsic::Shr,
+            Self::Le(_) => Intrinsic::Le,
+            Self::Ge(_) => Intrinsic::Ge,
+            Self::Eq(_) => Intrinsic::Eq,
+            Self::Ne(_) => Intrinsic::Ne,
+            Self::Add(_) => Intrinsic::Add,
+            Self::Sub(_) => Intrinsic::Sub,
+            Self::Mul(_) => Intrinsic::Mul,
+            Self::Div(_) => Intrinsic::Div,
+            Self::Rem(_) => Intrinsic::Rem,
+            Self::And(_) => Intrinsic::And,
+            Self::Or(_) => Intrinsic::Or,
+            Self::Xor(_) => Intrinsic::Xor,
+            Self::Lt(_) => Intrinsic::Lt,
+            Self::Gt(_) => Intrinsic::Gt,
+            _ => return None,
+        })
+    }
+}
+
+/// Prefix unary operator
+#[derive(Clone, Copy, PartialEq, Eq, Parse, ToTokens)]
+pub enum UnaryOperator {
+    Neg(Token![-]),
+    Not(Token![~]),
+    LogicalNot(Token![!]),
+    Deref(Token![*]),
+    AddressOf(Token![&]),
+}
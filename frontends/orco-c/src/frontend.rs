@@ -0,0 +1,49 @@
+use super::*;
+use orco::parser::ParseError;
+use std::collections::BTreeSet;
+
+/// C files in an [orco::query::Database], see [orco::query::Database::add_frontend].
+/// Items are the symbols of the unit, named by [Symbol::name]
+pub struct Frontend;
+
+impl orco::query::Frontend for Frontend {
+    fn items(&self, source: &str) -> Result<Vec<(String, std::ops::Range<usize>)>, ParseError> {
+        let unit = parse(source)?;
+        Ok(unit
+            .symbols
+            .iter()
+            .filter_map(|symbol| Some((symbol.name()?, range(symbol))))
+            .collect())
+    }
+
+    fn mentions(&self, text: &str, names: &BTreeSet<String>) -> BTreeSet<String> {
+        let mut mentions = BTreeSet::new();
+        let mut previous = "";
+        for word in text
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|word| !word.is_empty())
+        {
+            let mention = match previous {
+                "struct" => format!("struct {}", word),
+                _ => word.to_owned(),
+            };
+            if names.contains(&mention) {
+                mentions.insert(mention);
+            }
+            previous = word;
+        }
+        mentions
+    }
+
+    fn build(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        unit: &str,
+    ) -> Result<orco::Symbols, ParseError> {
+        Ok(parse(unit)?.build(ctx))
+    }
+}
+
+fn parse(source: &str) -> Result<Unit, ParseError> {
+    parsel::parse_str(source).map_err(|error| ParseError::new(&error, error.span().byte_range()))
+}
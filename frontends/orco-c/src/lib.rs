@@ -14,11 +14,38 @@ pub use symbol::Symbol;
 pub mod r#type;
 pub use r#type::Type;
 
+/// Incremental compilation of C files, see [orco::query]
+pub mod frontend;
+pub use frontend::Frontend;
+
+/// Byte range of a syntax node in the source code
+pub fn range(node: &impl parsel::syn::spanned::Spanned) -> std::ops::Range<usize> {
+    node.span().byte_range()
+}
+
+/// Span of a syntax node in the file that is currently being built
+pub fn span(
+    ctx: &orco::TypeInferenceContext,
+    node: &impl parsel::syn::spanned::Spanned,
+) -> Option<orco::Span> {
+    ctx.span(range(node))
+}
+
         if => If;
         else => Else;
         return => Return;
+        while => While;
+        do => Do;
+        for => For;
+        break => Break;
+        continue => Continue;
         int => Int;
+        char => Char;
+        float => Float;
+        double => Double;
         void => Void;
+        struct => Struct;
+        const => Const;
     }
 }
 
@@ -29,13 +56,21 @@ pub struct Unit {
 }
 
 impl Unit {
-    pub fn build(
-        &self,
-        ctx: &mut orco::TypeInferenceContext,
-    ) -> std::collections::HashMap<String, orco::Expression> {
-        let mut symbols = std::collections::HashMap::new();
+    /// Build the unit. Symbols are declared in [orco::TypeInferenceContext::module]
+    /// first, so that functions can be called before they are defined,
+    /// then they are built and the module is flattened
+    pub fn build(&self, ctx: &mut orco::TypeInferenceContext) -> orco::Symbols {
+        let declarations = self
+            .symbols
+            .iter()
+            .map(|symbol| symbol.declare(ctx))
+            .collect::<Vec<_>>();
+        for (symbol, declaration) in self.symbols.iter().zip(declarations) {
+            if let Some(declaration) = declaration {
+                symbol.build(ctx, &declaration);
+            }
         }
-        symbols
+        std::mem::take(&mut ctx.module).flatten()
     }
 }
 
@@ -69,30 +104,371 @@ pub fn parse_test() {
         .is_some_and(|name| name.to_string() == "x"));
 }
 
-// #[test]
-// pub fn interface_test() {
-//     let unit: Unit = parsel::parse_quote! {
-//         int main(void) {
-//             return 42;
-//         }
-
-//         void foo(int x) {}
-//     };
-
-//     let symbols = unit.symbols.iter().map(Symbol::as_orco).collect::<Vec<_>>();
-
-//     orco::test_symbols(
-//         &symbols,
-//         &[
-//             "
-//                 fn main () -> i32 {
-//                     return 42;
-//                 }
-//             ",
-//             "
-//                 fn foo (x: i32) -> () {
-//                 }
-//             ",
-//         ],
-//     );
-// }
+#[test]
+pub fn interpreter_test() {
+    use assert2::*;
+    let unit: Unit = parsel::parse_quote! {
+        int main(void) {
+            int sum = 0;
+            for (int i = 0; i < 10; i = i + 1) {
+                if (i % 2) {
+                    continue;
+                }
+                sum = sum + i;
+            }
+            return sum;
+        }
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    let mut symbols = unit.build(&mut ctx);
+    ctx.infer_symbols(symbols.values_mut());
+    check!(!ctx.diagnostics.has_errors());
+
+    let mut interpreter = orco::interpreter::Interpreter::new();
+    let result = interpreter.run(&symbols, "main", Vec::new());
+    check!(result == Ok(orco::interpreter::Value::Integer(20, 32)));
+}
+
+#[test]
+pub fn logical_operators_test() {
+    use assert2::*;
+    let unit: Unit = parsel::parse_quote! {
+        int main(void) {
+            int calls = 0;
+            int result = 0;
+            if (0 && (calls = calls + 1)) {
+                result = result + 1;
+            }
+            if (1 || (calls = calls + 10)) {
+                result = result + 2;
+            }
+            if (1 && (calls = calls + 100)) {
+                result = result + 4;
+            }
+            if (0 || (calls = calls + 1000)) {
+                result = result + 8;
+            }
+            if (0 || 1 && 0) {
+                result = result + 16;
+            }
+            return calls * 100 + result;
+        }
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    let mut symbols = unit.build(&mut ctx);
+    ctx.infer_symbols(symbols.values_mut());
+    check!(!ctx.diagnostics.has_errors());
+
+    // Right-hand sides, that don't decide the result, are never evaluated
+    let result = orco::interpreter::Interpreter::new().run(&symbols, "main", Vec::new());
+    check!(result == Ok(orco::interpreter::Value::Integer(110014, 32)));
+}
+
+#[test]
+pub fn pointers_test() {
+    use assert2::*;
+    let unit: Unit = parsel::parse_quote! {
+        struct Counter {
+            int value;
+        };
+
+        struct Node {
+            int value;
+            struct Counter *counter;
+        };
+
+        int main(void) {
+            int a[4] = {1, 2, 3, 4};
+            int *p = &a[0];
+            int *q = p + 3;
+            q = q - 1;
+            struct Counter counter = (struct Counter) { 20 };
+            struct Node first = (struct Node) { 10, &counter };
+            struct Node *node = &first;
+            node->counter->value = node->counter->value + 1;
+            return *q + *(p + 1) + node->counter->value * node->value;
+        }
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    let mut symbols = unit.build(&mut ctx);
+    ctx.infer_symbols(symbols.values_mut());
+    check!(!ctx.diagnostics.has_errors());
+    let result = orco::interpreter::Interpreter::new().run(&symbols, "main", Vec::new());
+    check!(result == Ok(orco::interpreter::Value::Integer(215, 32)));
+}
+
+#[test]
+pub fn compound_assignment_test() {
+    use assert2::*;
+    let unit: Unit = parsel::parse_quote! {
+        int main(void) {
+            int a[3] = {1, 2, 3};
+            int *p = &a[0];
+            p += 2;
+            *p *= 5;
+            a[0] += a[1] << 2;
+            int x = 100;
+            x -= 1;
+            x /= 3;
+            x %= 10;
+            x <<= 2;
+            x |= 1;
+            x ^= 3;
+            x &= 6;
+            x >>= 1;
+            int *none = NULL;
+            int calls = 0;
+            // `*none` is never evaluated
+            if (none && *none) {
+                calls = 1;
+            }
+            if (p && *p) {
+                calls += 10;
+            }
+            if (none || *p) {
+                calls += 100;
+            }
+            return a[0] + a[2] * 10 + x * 1000 + calls * 10000;
+        }
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    let mut symbols = unit.build(&mut ctx);
+    ctx.infer_symbols(symbols.values_mut());
+    check!(!ctx.diagnostics.has_errors());
+    let result = orco::interpreter::Interpreter::new().run(&symbols, "main", Vec::new());
+    check!(result == Ok(orco::interpreter::Value::Integer(1103159, 32)));
+}
+
+#[test]
+pub fn verify_test() {
+    use assert2::*;
+    let unit: Unit = parsel::parse_quote! {
+        int get(int *p) {
+            return *p;
+        }
+
+        int pick(int x) {
+            const int *q = NULL;
+            if (x) {
+                return 1;
+            } else {
+                return 2;
+            }
+        }
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    let mut symbols = unit.build(&mut ctx);
+    ctx.infer_symbols(symbols.values_mut());
+    check!(!ctx.diagnostics.has_errors());
+    check!(orco::verify::verify(
+        &mut ctx,
+        &symbols,
+        &orco::layout::Target::default()
+    ));
+}
+
+#[test]
+pub fn ir_round_trip_test() {
+    use assert2::*;
+    let unit: Unit = parsel::parse_quote! {
+        int main(void) {
+            int a[3];
+            int sum = 0;
+            for (int i = 0; i < 3; i = i + 1) {
+                a[i] = i * 2;
+                if (i % 2) {
+                    continue;
+                }
+                sum = sum + a[i];
+            }
+            return sum;
+        }
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    let mut symbols = unit.build(&mut ctx);
+    ctx.infer_symbols(symbols.values_mut());
+    check!(!ctx.diagnostics.has_errors());
+    let text = orco::parser::unit_to_string(&ctx, &symbols);
+
+    let mut ctx = orco::TypeInferenceContext::new();
+    let mut parsed = orco::parser::parse_unit(&mut ctx, &text).unwrap();
+    check!(orco::parser::unit_to_string(&ctx, &parsed) == text);
+    ctx.infer_symbols(parsed.values_mut());
+    check!(!ctx.diagnostics.has_errors());
+    let result = orco::interpreter::Interpreter::new().run(&parsed, "main", Vec::new());
+    check!(result == Ok(orco::interpreter::Value::Integer(4, 32)));
+}
+
+#[test]
+pub fn comptime_test() {
+    use assert2::*;
+    let unit: Unit = parsel::parse_quote! {
+        int main(void) {
+            int a[2 * 3 + 1];
+            a[6] = 5;
+            return a[6];
+        }
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    let mut symbols = unit.build(&mut ctx);
+    ctx.infer_symbols(symbols.values_mut());
+    check!(!ctx.diagnostics.has_errors());
+    let result = orco::interpreter::Interpreter::new().run(&symbols, "main", Vec::new());
+    check!(result == Ok(orco::interpreter::Value::Integer(5, 32)));
+
+    let unit: Unit = parsel::parse_quote! {
+        void main(void) {
+            int a[1 - 2];
+        }
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    unit.build(&mut ctx);
+    check!(ctx.diagnostics.iter().any(|diagnostic| diagnostic.code == Some("E0012")));
+}
+
+#[test]
+pub fn calls_test() {
+    use assert2::*;
+    let unit: Unit = parsel::parse_quote! {
+        int main(void) {
+            return twice(21);
+        }
+
+        int twice(int x) {
+            return x * 2;
+        }
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    let mut symbols = unit.build(&mut ctx);
+    ctx.infer_symbols(symbols.values_mut());
+    check!(!ctx.diagnostics.has_errors());
+    let result = orco::interpreter::Interpreter::new().run(&symbols, "main", Vec::new());
+    check!(result == Ok(orco::interpreter::Value::Integer(42, 32)));
+
+    let unit: Unit = parsel::parse_quote! {
+        int main(void) {
+            return missing(1);
+        }
+
+        int main(void) {
+            return 0;
+        }
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    let symbols = unit.build(&mut ctx);
+    check!(symbols.len() == 1);
+    let codes = ctx
+        .diagnostics
+        .iter()
+        .filter_map(|diagnostic| diagnostic.code)
+        .collect::<Vec<_>>();
+    check!(codes == ["E0013", "E0123"]);
+}
+
+#[test]
+pub fn query_test() {
+    use assert2::*;
+    const SOURCE: &str = "\
+int main(void) {
+    return twice(21);
+}
+
+struct Pair { int a; int b; };
+
+int twice(int x) {
+    return x * 2;
+}
+
+int zero(struct Pair pair) {
+    return 0;
+}
+";
+    let inferred = |db: &mut orco::query::Database| {
+        db.executed
+            .drain(..)
+            .filter_map(|query| match query {
+                orco::query::Query::Infer(_, name) => Some(name),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut db = orco::query::Database::new();
+    db.add_frontend("c", Frontend);
+    db.set_source("unit.c", SOURCE.to_owned());
+    let mut ctx = orco::TypeInferenceContext::new();
+    let id = ctx.source_map.add_file("unit.c", SOURCE);
+    check!(db.diagnostics("unit.c", id).is_empty());
+    let unit = db.unit("unit.c");
+    check!(inferred(&mut db) == ["main", "struct Pair", "twice", "zero"]);
+
+    let mut symbols = orco::parser::parse_unit(&mut ctx, &unit).unwrap();
+    ctx.infer_symbols(symbols.values_mut());
+    check!(!ctx.diagnostics.has_errors());
+    let result = orco::interpreter::Interpreter::new().run(&symbols, "main", Vec::new());
+    check!(result == Ok(orco::interpreter::Value::Integer(42, 32)));
+
+    // Only `twice` and `main`, which calls it, are built again
+    db.set_source("unit.c", SOURCE.replace("x * 2", "x * 3"));
+    db.unit("unit.c");
+    check!(inferred(&mut db) == ["main", "twice"]);
+
+    // Diagnostics point into the file
+    let source = SOURCE.replace("twice(21)", "twice(missing)");
+    let id = ctx.source_map.add_file("unit.c", source.clone());
+    db.set_source("unit.c", source);
+    let_assert!([error] = &db.diagnostics("unit.c", id)[..]);
+    let_assert!([label] = &error.labels[..]);
+    check!(ctx.source_map.snippet(label.span) == "missing");
+}
+
+#[test]
+pub fn interface_test() {
+    let unit: Unit = parsel::parse_quote! {
+        int main(void) {
+            return 42;
+        }
+
+        void foo(int x) {}
+    };
+    let mut ctx = orco::TypeInferenceContext::new();
+    let mut symbols = unit.build(&mut ctx);
+    ctx.infer_symbols(symbols.values_mut());
+
+    orco::test_symbols(
+        &symbols,
+        &[
+            "
+                fn foo(x: i32) -> () fastest {
+                }
+            ",
+            "
+                fn main() -> i32 fastest {
+                    (fn (value: i32) -> ! transparent = return)(42i32);
+                }
+            ",
+        ],
+    );
+}
+
+/// Lowers every `tests/golden/*.c` file and compares the IR with the `.orco`
+/// file next to it. Run with [orco::testing::UPDATE_SNAPSHOTS] set to update them
+#[test]
+pub fn golden_test() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut paths = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "c"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(!paths.is_empty(), "no golden tests found");
+    for path in paths {
+        let source = std::fs::read_to_string(&path).unwrap();
+        let unit = parsel::parse_str::<Unit>(&source).unwrap();
+        let mut ctx = orco::TypeInferenceContext::new();
+        let mut symbols = unit.build(&mut ctx);
+        ctx.infer_symbols(symbols.values_mut());
+        assert!(!ctx.diagnostics.has_errors(), "{}", path.display());
+        orco::test_snapshot(&ctx, &symbols, path.with_extension("orco"));
+    }
+}
@@ -17,7 +17,7 @@ impl If {
         ctx: &mut orco::TypeInferenceContext,
         expressions: &mut Vec<orco::Expression>,
     ) {
-        let condition = self.condition.build(ctx, expressions);
+        let condition = self.condition.build_condition(ctx, expressions);
         let then_block = {
             let mut expressions = Vec::new();
             self.then_block.build(ctx, &mut expressions);
@@ -39,12 +39,15 @@ impl If {
             )
         };
         expressions.push(orco::Expression::Call(orco::expression::Call {
-            function: ctx.intrinsics.branch(orco::Type::Unit),
-            args: vec![
-                condition,
-                orco::Expression::Function(then_block),
-                orco::Expression::Function(else_block),
-            ],
+            span: span(ctx, self),
+            ..orco::expression::Call::new(
+                ctx.intrinsics.branch(orco::Type::Unit),
+                vec![
+                    condition,
+                    orco::Expression::Function(then_block),
+                    orco::Expression::Function(else_block),
+                ],
+            )
         }));
     }
 }
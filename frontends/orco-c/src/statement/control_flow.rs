@@ -6,7 +6,79 @@ pub struct Return {
     pub expression: Expression,
     pub op_semi: Semi,
 }
-            todo!("Error")
+
+impl Return {
+    pub fn build(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        expressions: &mut Vec<orco::Expression>,
+    ) {
+        let value = self.expression.build(ctx, expressions);
+        let span = span(ctx, self);
+        if let Some(r#return) = ctx.intrinsics.r#return.clone() {
+            expressions.push(orco::Expression::Call(orco::expression::Call {
+                span,
+                ..orco::expression::Call::new(r#return, vec![value])
+            }));
+        } else {
+            ctx.diagnostics.report(
+                orco::Diagnostic::error("E0003", "return statement outside of a function")
+                    .with_label(span, "can't return from here"),
+            );
+        }
+    }
+}
+
+#[derive(Parse, ToTokens)]
+pub struct Break {
+    pub kw_break: kw::Break,
+    pub op_semi: Semi,
+}
+
+impl Break {
+    pub fn build(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        expressions: &mut Vec<orco::Expression>,
+    ) {
+        let span = span(ctx, self);
+        if let Some(r#break) = ctx.r#break(0) {
+            expressions.push(orco::Expression::Call(orco::expression::Call {
+                span,
+                ..orco::expression::Call::new(r#break, Vec::new())
+            }));
+        } else {
+            ctx.diagnostics.report(
+                orco::Diagnostic::error("E0005", "break statement outside of a loop")
+                    .with_label(span, "can't break from here"),
+            );
+        }
+    }
+}
+
+#[derive(Parse, ToTokens)]
+pub struct Continue {
+    pub kw_continue: kw::Continue,
+    pub op_semi: Semi,
+}
+
+impl Continue {
+    pub fn build(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        expressions: &mut Vec<orco::Expression>,
+    ) {
+        let span = span(ctx, self);
+        if let Some(r#continue) = ctx.r#continue(0) {
+            expressions.push(orco::Expression::Call(orco::expression::Call {
+                span,
+                ..orco::expression::Call::new(r#continue, Vec::new())
+            }));
+        } else {
+            ctx.diagnostics.report(
+                orco::Diagnostic::error("E0006", "continue statement outside of a loop")
+                    .with_label(span, "can't continue from here"),
+            );
         }
     }
 }
@@ -0,0 +1,149 @@
+use super::*;
+use parsel::ast::{Maybe, Paren};
+
+#[derive(Parse, ToTokens)]
+pub struct While {
+    pub kw_while: kw::While,
+    pub condition: Paren<Expression>,
+    #[parsel(recursive)]
+    pub body: Statement,
+}
+
+#[derive(Parse, ToTokens)]
+pub struct DoWhile {
+    pub kw_do: kw::Do,
+    #[parsel(recursive)]
+    pub body: Statement,
+    pub kw_while: kw::While,
+    pub condition: Paren<Expression>,
+    pub op_semi: Semi,
+}
+
+#[derive(Parse, ToTokens)]
+pub struct ForHeader {
+    #[parsel(recursive)]
+    pub init: Statement,
+    pub condition: Maybe<Expression>,
+    pub op_semi: Semi,
+    pub step: Maybe<Expression>,
+}
+
+#[derive(Parse, ToTokens)]
+pub struct For {
+    pub kw_for: kw::For,
+    pub header: Paren<ForHeader>,
+    #[parsel(recursive)]
+    pub body: Statement,
+}
+
+/// Wrap expressions into a transparent function, used as a loop body
+fn transparent(expressions: Vec<orco::Expression>) -> orco::Expression {
+    orco::Expression::Function(orco::expression::Function::new(
+        orco::function_signature![() -> () transparent],
+        None,
+        expressions,
+    ))
+}
+
+/// `if (!condition) break;`
+fn break_unless(
+    ctx: &mut orco::TypeInferenceContext,
+    condition: orco::Expression,
+    expressions: &mut Vec<orco::Expression>,
+) {
+    let span = condition.span();
+    let r#break = ctx.r#break(0).expect("Has to be called inside of a loop");
+    let r#break = orco::Expression::Call(orco::expression::Call {
+        span,
+        ..orco::expression::Call::new(r#break, Vec::new())
+    });
+    expressions.push(orco::Expression::Call(orco::expression::Call {
+        span,
+        ..orco::expression::Call::new(
+            ctx.intrinsics.branch(orco::Type::Unit),
+            vec![
+                condition,
+                transparent(Vec::new()),
+                transparent(vec![r#break]),
+            ],
+        )
+    }));
+}
+
+/// Push a loop call, C loops never yield a value
+fn push_loop(
+    ctx: &mut orco::TypeInferenceContext,
+    span: Option<orco::Span>,
+    body: Vec<orco::Expression>,
+    next: Vec<orco::Expression>,
+    expressions: &mut Vec<orco::Expression>,
+) {
+    expressions.push(orco::Expression::Call(orco::expression::Call {
+        span,
+        ..orco::expression::Call::new(
+            ctx.intrinsics.r#loop(orco::Type::Unit),
+            vec![transparent(body), transparent(next)],
+        )
+    }));
+}
+
+impl While {
+    pub fn build(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        expressions: &mut Vec<orco::Expression>,
+    ) {
+        ctx.enter_loop(orco::Type::Unit);
+        let mut body = Vec::new();
+        let condition = self.condition.build_condition(ctx, &mut body);
+        break_unless(ctx, condition, &mut body);
+        self.body.build(ctx, &mut body);
+        ctx.exit_loop();
+        push_loop(ctx, span(ctx, self), body, Vec::new(), expressions);
+    }
+}
+
+impl DoWhile {
+    pub fn build(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        expressions: &mut Vec<orco::Expression>,
+    ) {
+        ctx.enter_loop(orco::Type::Unit);
+        let mut body = Vec::new();
+        self.body.build(ctx, &mut body);
+        let mut next = Vec::new();
+        let condition = self.condition.build_condition(ctx, &mut next);
+        break_unless(ctx, condition, &mut next);
+        ctx.exit_loop();
+        push_loop(ctx, span(ctx, self), body, next, expressions);
+    }
+}
+
+impl For {
+    pub fn build(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        expressions: &mut Vec<orco::Expression>,
+    ) {
+        ctx.scopes.push(orco::type_inference::Scope::new());
+        self.header.init.build(ctx, expressions);
+
+        ctx.enter_loop(orco::Type::Unit);
+        let mut body = Vec::new();
+        if let Some(condition) = self.header.condition.as_prefix() {
+            let condition = condition.build_condition(ctx, &mut body);
+            break_unless(ctx, condition, &mut body);
+        }
+        self.body.build(ctx, &mut body);
+        let mut next = Vec::new();
+        if let Some(step) = self.header.step.as_prefix() {
+            let step = step.build(ctx, &mut next);
+            next.push(step);
+        }
+        ctx.exit_loop();
+
+        push_loop(ctx, span(ctx, self), body, next, expressions);
+        ctx.scopes.pop();
+    }
+}
@@ -6,7 +6,9 @@ pub use block::Block;
 pub mod branching;
 pub use branching::If;
 pub mod control_flow;
-pub use control_flow::Return;
+pub use control_flow::{Break, Continue, Return};
+pub mod loops;
+pub use loops::{DoWhile, For, While};
 pub mod variables;
 pub use variables::VariableDeclaration;
 
@@ -15,6 +17,11 @@ pub enum Statement {
     Block(Block),
     If(Box<If>),
     Return(Return),
+    Break(Break),
+    Continue(Continue),
+    While(Box<While>),
+    DoWhile(Box<DoWhile>),
+    For(Box<For>),
     VariableDeclaration(Box<VariableDeclaration>),
     Expression(Expression, Semi),
     Empty(Semi),
@@ -30,20 +37,61 @@ impl Statement {
             Statement::Block(block) => block.build(ctx, expressions),
             Statement::If(statement) => statement.build(ctx, expressions),
             Statement::Return(r#return) => r#return.build(ctx, expressions),
+            Statement::Break(r#break) => r#break.build(ctx, expressions),
+            Statement::Continue(r#continue) => r#continue.build(ctx, expressions),
+            Statement::While(r#while) => r#while.build(ctx, expressions),
+            Statement::DoWhile(do_while) => do_while.build(ctx, expressions),
+            Statement::For(r#for) => r#for.build(ctx, expressions),
             Statement::VariableDeclaration(decl) => {
+                let r#type = decl.ty.as_orco(ctx);
+                let variables = decl
+                    .variables
+                    .iter()
+                    .map(|var| {
+                        let r#type = var.r#type(ctx, r#type.clone());
+                        let variable = orco::Variable {
+                            span: span(ctx, &var.name),
+                            ..orco::Variable::new(Some(var.name.to_string()), r#type.clone())
+                        };
+                        (
+                            var,
+                            r#type,
+                            std::sync::Arc::new(std::sync::RwLock::new(variable)),
+                        )
+                    })
+                    .collect::<Vec<_>>();
                 let Some(scope) = ctx.scopes.last_mut() else {
-                    todo!("Error")
-                };
-                let r#type = decl.ty.as_orco();
-                for var in &decl.variables {
-                    let name = var.name.to_string();
-                    scope.insert(
-                        name.clone(),
-                        std::sync::Arc::new(std::sync::RwLock::new(orco::Variable::new(
-                            Some(name),
-                            r#type.clone(),
-                        ))),
+                    let span = span(ctx, decl);
+                    ctx.diagnostics.report(
+                        orco::Diagnostic::error("E0002", "variable declaration outside of a scope")
+                            .with_label(span, "declared here"),
                     );
+                    return;
+                };
+                for (var, _, variable) in &variables {
+                    scope.insert(var.name.to_string(), variable.clone());
+                }
+                for (var, r#type, variable) in variables {
+                    let Some(value) = var.value.as_ref() else {
+                        continue;
+                    };
+                    let value = match &r#type {
+                        orco::Type::Array(element, len) => {
+                            value.build_array_initializer(ctx, expressions, element, *len)
+                        }
+                        _ => value.build(ctx, expressions),
+                    };
+                    let target = orco::Expression::Variable(orco::Spanned::new(
+                        variable,
+                        span(ctx, &var.name),
+                    ));
+                    expressions.push(orco::Expression::Call(orco::expression::Call {
+                        span: span(ctx, var),
+                        ..orco::expression::Call::new(
+                            ctx.intrinsics.assign(r#type.clone()),
+                            vec![target, value],
+                        )
+                    }));
                 }
             }
             Statement::Expression(expression, _) => {
@@ -5,8 +5,60 @@ use parsel::{
 #[derive(Clone, PartialEq, Eq, Parse, ToTokens)]
 pub struct VariableDeclarationEntry {
     pub name: Word,
+    /// Array size, `int a[10]`. Can be any constant expression, like `int a[N * 2]`.
+    /// Can be left out if there is an initializer
+    pub size: Maybe<parsel::ast::Bracket<Maybe<Expression>>>,
     pub value: Maybe<Eq, Expression>,
 }
 
+impl VariableDeclarationEntry {
+    /// Type of this variable, `r#type` is the type from the declaration
+    pub fn r#type(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        r#type: orco::Type,
+    ) -> orco::Type {
+        let Some(size) = self.size.as_prefix() else {
+            return r#type;
+        };
+        let len = match size.as_prefix() {
+            Some(len) => {
+                let mut expressions = Vec::new();
+                let mut expression = len.build(ctx, &mut expressions);
+                let value = ctx
+                    .evaluate(&mut expression)
+                    .map(orco::interpreter::intrinsics::integer);
+                match value {
+                    Ok(Ok(len)) if len >= 0 && expressions.is_empty() => len as u64,
+                    Err(_) => return orco::Type::Wildcard,
+                    _ => {
+                        let span = span(ctx, len);
+                        ctx.diagnostics.report(
+                            orco::Diagnostic::error(
+                                "E0012",
+                                "array size must be a constant non-negative integer",
+                            )
+                            .with_label(span, "size of this array"),
+                        );
+                        return orco::Type::Wildcard;
+                    }
+                }
+            }
+            None => match self.value.as_ref().and_then(Expression::initializer_len) {
+                Some(len) => len,
+                None => {
+                    let span = span(ctx, size);
+                    ctx.diagnostics.report(
+                        orco::Diagnostic::error("E0010", "array size missing")
+                            .with_label(span, "specify the size or add an initializer"),
+                    );
+                    return orco::Type::Wildcard;
+                }
+            },
+        };
+        orco::Type::Array(Box::new(r#type), len)
+    }
+}
+
     pub op_semi: Semi,
 }
@@ -20,29 +20,72 @@ pub struct FunctionDefinition {
 }
 
 impl FunctionDefinition {
-    pub fn build(&self, ctx: &mut orco::TypeInferenceContext) -> orco::expression::Function {
+    /// Declared parameters, `(void)` is an empty list
+    pub fn parameters(&self) -> impl Iterator<Item = &FunctionParameter> {
+        self.params
+            .as_ref()
+            .as_ref()
+            .right()
+            .into_iter()
+            .flat_map(|params| params.iter())
+    }
+
+    /// Path of the function in [orco::TypeInferenceContext::module]
+    pub fn path(&self) -> orco::Path {
+        orco::Path(vec![self.name.to_string()])
+    }
+
+    /// Function without a body, that calls can point to before it's built
+    pub fn declare(&self, ctx: &mut orco::TypeInferenceContext) -> orco::expression::Function {
         let signature = orco::types::FunctionSignature::new(
-            self.params
-                .as_ref()
-                .as_ref()
-                .right()
-                .iter()
-                .flat_map(|params| params.iter())
+            self.parameters()
                 .map(|param| {
                     (
                         param.name.as_prefix().map(|name| name.to_string()),
-                        param.r#type.as_orco(),
+                        param.r#type.as_orco(ctx),
                     )
                 })
                 .collect(),
-            self.return_type.as_orco(),
+            self.return_type.as_orco(ctx),
             orco::types::CallingConvention::default(),
         );
+        orco::expression::Function {
+            span: span(ctx, &self.name),
+            ..orco::expression::Function::new(signature, Some(self.name.to_string()), Vec::new())
+        }
+    }
 
+    /// Build the function, `signature` is the one of the declaration, see [FunctionDefinition::declare]
+    pub fn build(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        signature: orco::types::FunctionSignature,
+    ) -> orco::expression::Function {
         let mut expressions = Vec::new();
         ctx.enter_function(&signature);
+        let parameters = self
+            .parameters()
+            .zip(&signature.parameters)
+            .map(|(param, (name, r#type))| {
+                std::sync::Arc::new(std::sync::RwLock::new(orco::Variable {
+                    span: span(ctx, param),
+                    ..orco::Variable::new(name.clone(), r#type.clone())
+                }))
+            })
+            .collect::<Vec<_>>();
+        if let Some(scope) = ctx.scopes.last_mut() {
+            for (param, parameter) in self.parameters().zip(&parameters) {
+                if let Some(name) = param.name.as_prefix() {
+                    scope.insert(name.to_string(), parameter.clone());
+                }
+            }
+        }
         self.body.build(ctx, &mut expressions);
         ctx.exit_function();
-        orco::expression::Function::new(signature, Some(self.name.to_string()), expressions)
+        orco::expression::Function {
+            span: span(ctx, &self.name),
+            parameters,
+            ..orco::expression::Function::new(signature, Some(self.name.to_string()), expressions)
+        }
     }
 }
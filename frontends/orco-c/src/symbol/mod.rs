@@ -1,4 +1,5 @@
 use super::*;
+use parsel::syn::token::Semi;
 
 /// C function declaration and definition
 pub mod function;
@@ -9,15 +10,69 @@ pub use function::FunctionDefinition;
 pub enum Symbol {
     /// Function definition
     FunctionDefinition(FunctionDefinition),
+    /// Struct declaration or definition, `struct Foo { int x; };`
+    StructDeclaration(Type, Semi),
 }
 
 impl Symbol {
-    pub fn build(&self, ctx: &mut orco::TypeInferenceContext) -> (String, orco::Expression) {
+    /// Name of the symbol, struct declarations are named `struct Name`.
+    /// Anonymous structs have none
+    pub fn name(&self) -> Option<String> {
         match self {
-            Self::FunctionDefinition(function) => (
-                function.name.to_string(),
-                orco::Expression::Function(function.build(ctx)),
-            ),
+            Self::FunctionDefinition(function) => Some(function.name.to_string()),
+            Self::StructDeclaration(Type::Struct(r#struct), _) => {
+                Some(format!("struct {}", r#struct.name.as_prefix()?))
+            }
+            Self::StructDeclaration(..) => None,
         }
     }
+
+    /// Declare this symbol. Functions are put into [orco::TypeInferenceContext::module]
+    /// without their bodies, struct types are declared in the context.
+    /// Returns the declaration of the function, that [Symbol::build] fills in
+    pub fn declare(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+    ) -> Option<orco::ArcLock<orco::expression::Function>> {
+        match self {
+            Self::FunctionDefinition(function) => {
+                let path = function.path();
+                if ctx.module.get(&path).is_some() {
+                    let span = span(ctx, &function.name);
+                    ctx.diagnostics.report(
+                        orco::Diagnostic::error(
+                            "E0013",
+                            format!("redefinition of function `{}`", function.name),
+                        )
+                        .with_label(span, "already defined"),
+                    );
+                    return None;
+                }
+                let declaration =
+                    std::sync::Arc::new(std::sync::RwLock::new(function.declare(ctx)));
+                ctx.module
+                    .insert(&path, orco::Symbol::Function(declaration.clone()));
+                Some(declaration)
+            }
+            Self::StructDeclaration(r#type, _) => {
+                r#type.as_orco(ctx);
+                None
+            }
+        }
+    }
+
+    /// Build the body of a function, that was declared by [Symbol::declare].
+    /// Calls to the function point to the declaration, so it's filled in place
+    pub fn build(
+        &self,
+        ctx: &mut orco::TypeInferenceContext,
+        declaration: &orco::ArcLock<orco::expression::Function>,
+    ) {
+        let Self::FunctionDefinition(function) = self else {
+            return;
+        };
+        let signature = declaration.read().unwrap().signature.clone();
+        let function = function.build(ctx, signature);
+        *declaration.write().unwrap() = function;
+    }
 }
@@ -1,17 +1,157 @@
 use super::*;
+use parsel::{
+    ast::{Brace, Maybe, Punctuated, Word},
+    syn::{
+        parse::ParseStream,
+        token::{Comma, Semi},
+        Token,
+    },
+};
 
 /// C types
-#[derive(Clone, PartialEq, Eq, Parse, ToTokens)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum Type {
     Void(kw::Void),
     Int(kw::Int),
+    Char(kw::Char),
+    Float(kw::Float),
+    Double(kw::Double),
+    Struct(Box<StructType>),
+    /// `const T`
+    Const(kw::Const, Box<Type>),
+    /// `T *`
+    Pointer(Box<Type>, Token![*]),
+}
+
+impl Parse for Type {
+    fn parse(input: ParseStream) -> parsel::Result<Self> {
+        let mut r#type = if input.peek(kw::Const) {
+            Self::Const(input.parse()?, Box::new(Self::parse_base(input)?))
+        } else {
+            Self::parse_base(input)?
+        };
+        while input.peek(Token![*]) {
+            r#type = Self::Pointer(Box::new(r#type), input.parse()?);
+        }
+        Ok(r#type)
+    }
+}
+
+impl Type {
+    fn parse_base(input: ParseStream) -> parsel::Result<Self> {
+        if input.peek(kw::Void) {
+            Ok(Self::Void(input.parse()?))
+        } else if input.peek(kw::Int) {
+            Ok(Self::Int(input.parse()?))
+        } else if input.peek(kw::Char) {
+            Ok(Self::Char(input.parse()?))
+        } else if input.peek(kw::Float) {
+            Ok(Self::Float(input.parse()?))
+        } else if input.peek(kw::Double) {
+            Ok(Self::Double(input.parse()?))
+        } else {
+            Ok(Self::Struct(input.parse()?))
+        }
+    }
+}
+
+impl ToTokens for Type {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            Self::Void(void) => void.to_tokens(tokens),
+            Self::Int(int) => int.to_tokens(tokens),
+            Self::Char(char) => char.to_tokens(tokens),
+            Self::Float(float) => float.to_tokens(tokens),
+            Self::Double(double) => double.to_tokens(tokens),
+            Self::Struct(r#struct) => r#struct.to_tokens(tokens),
+            Self::Const(r#const, r#type) => {
+                r#const.to_tokens(tokens);
+                r#type.to_tokens(tokens);
+            }
+            Self::Pointer(r#type, star) => {
+                r#type.to_tokens(tokens);
+                star.to_tokens(tokens);
+            }
+        }
+    }
+}
+
+/// `struct Name`, `struct Name { ... }` or `struct { ... }`
+#[derive(Clone, PartialEq, Eq, Parse, ToTokens)]
+pub struct StructType {
+    pub kw_struct: kw::Struct,
+    pub name: Maybe<Word>,
+    #[parsel(recursive)]
+    pub fields: Maybe<Brace<Many<StructField>>>,
+}
+
+/// `int x, y;`
+#[derive(Clone, PartialEq, Eq, Parse, ToTokens)]
+pub struct StructField {
+    #[parsel(recursive)]
+    pub r#type: Type,
+    pub names: Punctuated<Word, Comma>,
+    pub op_semi: Semi,
 }
 
 impl Type {
-    pub fn as_orco(&self) -> orco::Type {
+    /// Convert to an OrCo type. Struct definitions get declared in the process
+    pub fn as_orco(&self, ctx: &mut orco::TypeInferenceContext) -> orco::Type {
         match self {
             Type::Void(_) => orco::Type::Unit,
             Type::Int(_) => orco::Type::Integer(32),
+            Type::Char(_) => orco::Type::Integer(8),
+            Type::Float(_) => orco::Type::Float(32),
+            Type::Double(_) => orco::Type::Float(64),
+            Type::Struct(r#struct) => r#struct.as_orco(ctx),
+            Type::Const(_, r#type) => r#type.as_orco(ctx),
+            Type::Pointer(r#type, _) => {
+                let mutability = match r#type.as_ref() {
+                    Type::Const(..) => orco::types::Mutability::Const,
+                    _ => orco::types::Mutability::Mut,
+                };
+                orco::Type::Pointer(Box::new(r#type.as_orco(ctx)), mutability)
+            }
+        }
+    }
+}
+
+impl StructType {
+    /// Define or look up the struct
+    pub fn as_orco(&self, ctx: &mut orco::TypeInferenceContext) -> orco::Type {
+        let name = self.name.as_prefix().map(|name| name.to_string());
+        // Struct tags have their own namespace in C
+        let key = name.as_ref().map(|name| format!("struct {}", name));
+
+        let Some(fields) = self.fields.as_prefix() else {
+            if let Some(r#type) = key.as_ref().and_then(|key| ctx.types.get(key)) {
+                return r#type.clone();
+            }
+            let span = span(ctx, self);
+            ctx.diagnostics.report(
+                orco::Diagnostic::error(
+                    "E0007",
+                    format!("cannot find struct `{}`", name.unwrap_or_default()),
+                )
+                .with_label(span, "not found"),
+            );
+            return orco::Type::Wildcard;
+        };
+
+        let mut r#struct = orco::types::Struct {
+            span: span(ctx, self),
+            ..orco::types::Struct::new(name, Vec::new())
+        };
+        for field in fields.iter() {
+            let r#type = field.r#type.as_orco(ctx);
+            for name in field.names.iter() {
+                r#struct.fields.push((name.to_string(), r#type.clone()));
+            }
+        }
+        let r#type = orco::Type::Struct(orco::types::StructRef::new(r#struct));
+        if let Some(key) = key {
+            ctx.types.insert(key, r#type.clone());
         }
+        r#type
     }
 }
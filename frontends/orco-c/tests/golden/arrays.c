@@ -0,0 +1,5 @@
+int main(void) {
+    int a[3] = {1, 2, 3};
+    a[1] = a[0] + a[2];
+    return a[1];
+}
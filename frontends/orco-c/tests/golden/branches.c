@@ -0,0 +1,7 @@
+int abs(int x) {
+    if (x < 0) {
+        return -x;
+    } else {
+        return x;
+    }
+}
@@ -0,0 +1,14 @@
+int factorial(int n) {
+    if (n <= 1) {
+        return 1;
+    }
+    return n * factorial(n - 1);
+}
+
+int main(void) {
+    return factorial(add(2, 3));
+}
+
+int add(int a, int b) {
+    return a + b;
+}
@@ -0,0 +1,10 @@
+int main(void) {
+    int x = 0;
+    if (x && x / x) {
+        x = 1;
+    }
+    if (x || x - 1) {
+        x = 2;
+    }
+    return x;
+}
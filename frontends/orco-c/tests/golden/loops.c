@@ -0,0 +1,13 @@
+int sum(int n) {
+    int sum = 0;
+    for (int i = 0; i < n; i = i + 1) {
+        if (i % 2) {
+            continue;
+        }
+        sum = sum + i;
+    }
+    while (sum > 100) {
+        break;
+    }
+    return sum;
+}
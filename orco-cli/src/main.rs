@@ -7,6 +7,23 @@ use std::io::Read;
 struct Cli {
     /// Input file, use '-' for stdin
     path: std::path::PathBuf,
+    /// Optimisation passes to run, in order. Defaults to the default pipeline,
+    /// an empty list disables them
+    #[arg(long, value_delimiter = ',')]
+    passes: Option<Vec<String>>,
+    /// Print the IR after these passes, 'all' prints it after every pass
+    #[arg(long, value_delimiter = ',')]
+    dump_after: Vec<String>,
+    /// Print the time every pass took
+    #[arg(long)]
+    time_passes: bool,
+    /// Number of threads to compile functions on, defaults to the number of cores
+    #[arg(long, short = 'j')]
+    threads: Option<usize>,
+    /// Cache of the compilation results of `.orco` and `.c` files, only the items,
+    /// that changed since the last run, are inferred and compiled again, one by one
+    #[arg(long)]
+    cache: Option<std::path::PathBuf>,
 }
 
 fn main() {
@@ -16,26 +33,153 @@ fn main() {
         .init();
     let cli = Cli::parse();
 
-    let unit: Result<orco_c::Unit, _> = if cli.path == std::path::Path::new("-") {
+    let path = cli.path.display().to_string();
+    let source = if cli.path == std::path::Path::new("-") {
         let mut source = String::new();
         std::io::stdin().read_to_string(&mut source).unwrap();
-        orco_c::parsel::parse_str(&source)
+        source
     } else {
-        orco_c::parsel::parse_str(&std::fs::read_to_string(cli.path).unwrap())
+        std::fs::read_to_string(&cli.path).unwrap()
     };
-    let unit = match unit {
-        Ok(unit) => unit,
-        Err(err) => {
-            println!("{}", err);
-            panic!();
+    let mut ctx = orco::TypeInferenceContext::new();
+    let file = ctx.source_map.add_file(path, source);
+    ctx.current_file = Some(file);
+
+    let source = ctx.source_map.file(file).contents.clone();
+    let extension = cli
+        .path
+        .extension()
+        .and_then(|extension| extension.to_str());
+    let mut cache = cli.cache.as_ref().and_then(|path| {
+        if !matches!(extension, Some("orco" | "c")) {
+            eprintln!("warning: --cache only applies to `.orco` and `.c` files");
+            return None;
+        }
+        let mut db = orco::query::Database::load(path).unwrap_or_default();
+        db.add_frontend("c", orco_c::Frontend);
+        Some((path, db))
+    });
+    // `.orco` files hold the textual IR, everything else is C
+    let symbols = match &mut cache {
+        Some((path, db)) => Ok(cached(&mut ctx, db, path, file)),
+        None if extension == Some("orco") => {
+            orco::parser::parse_unit(&mut ctx, &source).map_err(|err| (err.to_string(), err.range))
         }
+        None => orco_c::parsel::parse_str::<orco_c::Unit>(&source)
+            .map(|unit| unit.build(&mut ctx))
+            .map_err(|err| (err.to_string(), err.span().byte_range())),
     };
+    let mut symbols = match symbols {
+        Ok(symbols) => symbols,
+        Err((message, range)) => {
+            let diagnostic = orco::Diagnostic::error("E0000", message)
+                .with_label(ctx.span(range), "syntax error");
+            eprint!("{}", diagnostic.render(&ctx.source_map));
+            std::process::exit(1);
+        }
+    };
+    // The database has inferred the cached symbols already
+    if cache.is_none() {
+        ctx.infer_symbols(symbols.values_mut());
+    }
+    for diagnostic in ctx.diagnostics.iter() {
+        eprint!("{}", diagnostic.render(&ctx.source_map));
+    }
+    if ctx.diagnostics.has_errors() {
+        std::process::exit(1);
+    }
 
-    let mut ctx = orco::TypeInferenceContext::new();
-    let symbols = unit.build(&mut ctx);
-    for (name, symbol) in &symbols {
-        println!("const {} = {};", name, symbol);
+    let names = match &cli.passes {
+        Some(names) => names
+            .iter()
+            .filter(|name| !name.is_empty())
+            .cloned()
+            .collect(),
+        None => orco::passes::DEFAULT_PIPELINE
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+    };
+    let mut passes = orco::passes::PassManager::from_names(names.iter().map(String::as_str))
+        .unwrap_or_else(|name| {
+            eprintln!("unknown pass `{}`", name);
+            std::process::exit(1);
+        });
+    passes.dump_after = cli.dump_after;
+    passes.run(&mut ctx, &mut symbols);
+    for (pass, unit) in &passes.dumps {
+        eprint!("// after {}\n{}", pass, unit);
+    }
+    if cli.time_passes {
+        eprint!("{}", passes.timings_report());
     }
+    print!("{}", orco::parser::unit_to_string(&ctx, &symbols));
 
-    orco_cranelift::build(&symbols);
+    let reported = ctx.diagnostics.iter().len();
+    if !orco::verify::verify(&mut ctx, &symbols, &orco::layout::Target::default()) {
+        for diagnostic in ctx.diagnostics.iter().skip(reported) {
+            eprint!("{}", diagnostic.render(&ctx.source_map));
+        }
+        std::process::exit(1);
+    }
+
+    let Some((path, mut db)) = cache else {
+        orco_cranelift::build(&symbols, cli.threads);
+        return;
+    };
+    db.set_backend(orco_cranelift::query::Backend { passes: names });
+    let name = ctx.source_map.file(file).name.clone();
+    let chunks = db
+        .code(&name)
+        .into_iter()
+        .map(|(function, code)| {
+            orco_cranelift::query::Chunk::decode(&code).unwrap_or_else(|| {
+                eprintln!("invalid code of `{}` in the cache", function);
+                std::process::exit(1);
+            })
+        })
+        .collect::<Vec<_>>();
+    save(&db, path);
+    std::fs::write("foo.o", orco_cranelift::query::link(&chunks)).unwrap();
+}
+
+/// Infer the types through a [orco::query::Database], stored in the cache file
+/// between the runs. Diagnostics go into the context. The symbols are parsed
+/// from the inferred unit, that is added to the source map to give them spans
+fn cached(
+    ctx: &mut orco::TypeInferenceContext,
+    db: &mut orco::query::Database,
+    cache: &std::path::Path,
+    file: orco::span::FileId,
+) -> orco::Symbols {
+    let name = ctx.source_map.file(file).name.clone();
+    db.set_source(&name, ctx.source_map.file(file).contents.clone());
+    for diagnostic in db.diagnostics(&name, file) {
+        ctx.diagnostics.report(diagnostic);
+    }
+    let unit = db.unit(&name);
+    save(db, cache);
+    if ctx.diagnostics.has_errors() {
+        return orco::Symbols::new();
+    }
+
+    let inferred = ctx
+        .source_map
+        .add_file(format!("{} (inferred)", name), unit.clone());
+    ctx.current_file = Some(inferred);
+    let symbols = orco::parser::parse_unit(ctx, &unit).unwrap_or_else(|err| {
+        let diagnostic = orco::Diagnostic::error("E0000", err.message)
+            .with_label(ctx.span(err.range), "syntax error");
+        ctx.diagnostics.report(diagnostic);
+        orco::Symbols::new()
+    });
+    ctx.current_file = Some(file);
+    symbols
+}
+
+/// Write the database to the cache file, failing to do so is not an error
+fn save(db: &orco::query::Database, cache: &std::path::Path) {
+    if let Err(err) = db.save(cache) {
+        eprintln!("warning: can't write the cache: {}", err);
+    }
 }
@@ -0,0 +1,262 @@
+use orco::{Visitor, Walk};
+use std::ops::Range;
+
+/// Entry of the document outline
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    /// Name, as it is written in the source
+    pub name: String,
+    /// Is it a function or a type?
+    pub kind: lsp_types::SymbolKind,
+    /// Whole declaration
+    pub range: Range<usize>,
+    /// Name of the declaration
+    pub selection: Range<usize>,
+}
+
+/// Everything the server knows about a document, computed once per change.
+/// Locations are byte ranges in the document
+pub struct Analysis {
+    /// Parsing and type inference diagnostics
+    pub diagnostics: Vec<orco::Diagnostic>,
+    /// Inferred types of the expressions and variables
+    pub types: Vec<(Range<usize>, orco::Type)>,
+    /// Uses of variables and functions with their declarations
+    pub definitions: Vec<(Range<usize>, Range<usize>)>,
+    /// Outline of the document
+    pub symbols: Vec<Symbol>,
+    source_map: orco::SourceMap,
+    file: orco::span::FileId,
+}
+
+impl Analysis {
+    /// Parse the document and infer the types. `.orco` files hold
+    /// the textual IR, everything else goes through the C frontend
+    pub fn new(name: &str, source: String) -> Self {
+        let mut ctx = orco::TypeInferenceContext::new();
+        let file = ctx.source_map.add_file(name, source.clone());
+        ctx.current_file = Some(file);
+
+        let parsed = if name.ends_with(".orco") {
+            orco::parser::items(&source)
+                .and_then(|items| {
+                    let symbols = orco::parser::parse_unit(&mut ctx, &source)?;
+                    Ok((symbols, outline(items)))
+                })
+                .map_err(|err| (err.to_string(), err.range))
+        } else {
+            crate::c::build(&mut ctx, &source)
+        };
+
+        let mut analysis = Self {
+            diagnostics: Vec::new(),
+            types: Vec::new(),
+            definitions: Vec::new(),
+            symbols: Vec::new(),
+            source_map: orco::SourceMap::new(),
+            file,
+        };
+        match parsed {
+            Ok((mut symbols, outline)) => {
+                ctx.infer_symbols(symbols.values_mut());
+                let mut collector = Collector {
+                    analysis: &mut analysis,
+                    file,
+                };
+                for symbol in symbols.values() {
+                    collector.r#type(symbol.span(), symbol.r#type());
+                    symbol.walk(&mut collector);
+                }
+                analysis.symbols = outline;
+            }
+            Err((message, range)) => {
+                ctx.diagnostics.report(
+                    orco::Diagnostic::error("E0000", message)
+                        .with_label(ctx.span(range), "syntax error"),
+                );
+            }
+        }
+        analysis.diagnostics = ctx.diagnostics.iter().cloned().collect();
+        analysis.source_map = ctx.source_map;
+        analysis
+    }
+
+    /// Text of the document
+    pub fn source(&self) -> &str {
+        &self.source_map.file(self.file).contents
+    }
+
+    /// Id of the document in the source map of the analysis
+    pub fn file(&self) -> orco::span::FileId {
+        self.file
+    }
+
+    /// Type of the innermost expression at a byte offset
+    pub fn type_at(&self, offset: usize) -> Option<&orco::Type> {
+        innermost(&self.types, offset)
+    }
+
+    /// Declaration of the variable or the function, used at a byte offset
+    pub fn definition_at(&self, offset: usize) -> Option<&Range<usize>> {
+        innermost(&self.definitions, offset)
+    }
+}
+
+/// Outline of a `.orco` file, see [orco::parser::items]
+fn outline(items: Vec<(String, Range<usize>)>) -> Vec<Symbol> {
+    items
+        .into_iter()
+        .map(|(name, range)| Symbol {
+            kind: match name.starts_with("struct ") {
+                true => lsp_types::SymbolKind::STRUCT,
+                false => lsp_types::SymbolKind::CONSTANT,
+            },
+            name,
+            selection: range.clone(),
+            range,
+        })
+        .collect()
+}
+
+/// Value of the shortest range, that contains the offset
+fn innermost<T>(entries: &[(Range<usize>, T)], offset: usize) -> Option<&T> {
+    entries
+        .iter()
+        .filter(|(range, _)| range.contains(&offset) || range.end == offset)
+        .min_by_key(|(range, _)| range.len())
+        .map(|(_, value)| value)
+}
+
+/// Collects types and definitions from the inferred IR
+struct Collector<'a> {
+    analysis: &'a mut Analysis,
+    file: orco::span::FileId,
+}
+
+impl Collector<'_> {
+    fn range(&self, span: Option<orco::Span>) -> Option<Range<usize>> {
+        span.filter(|span| span.file == self.file)
+            .map(|span| span.range())
+    }
+
+    fn r#type(&mut self, span: Option<orco::Span>, r#type: orco::Type) {
+        if let Some(range) = self.range(span) {
+            if r#type != orco::Type::Wildcard {
+                self.analysis.types.push((range, r#type));
+            }
+        }
+    }
+}
+
+impl Visitor for Collector<'_> {
+    fn visit_expression(&mut self, expression: &orco::Expression) {
+        self.r#type(expression.span(), expression.r#type());
+        expression.walk(self);
+    }
+
+    /// Variables are resolved while building, see
+    /// [orco::TypeInferenceContext::resolve_variable], so they point to their declarations
+    fn visit_variable(&mut self, variable: &orco::Spanned<orco::ArcLock<orco::Variable>>) {
+        let declaration = variable.read().unwrap();
+        self.r#type(declaration.span, declaration.r#type.clone());
+        if let (Some(used), Some(declared)) =
+            (self.range(variable.span), self.range(declaration.span))
+        {
+            self.analysis.definitions.push((used, declared));
+        }
+    }
+
+    fn visit_function(&mut self, function: &orco::expression::Function) {
+        for parameter in &function.parameters {
+            let parameter = parameter.read().unwrap();
+            self.r#type(parameter.span, parameter.r#type.clone());
+        }
+        function.walk(self);
+    }
+
+    /// Calls of named functions point to the function symbols
+    fn visit_call(&mut self, call: &orco::expression::Call) {
+        let function = call.function.read().unwrap();
+        if function.name.is_some() {
+            if let (Some(used), Some(declared)) = (self.range(call.span), self.range(function.span))
+            {
+                self.analysis.definitions.push((used, declared));
+            }
+        }
+        drop(function);
+        call.walk(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    const UNIT: &str = "\
+const twice = fn twice(x: i32) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = mul)(x: i32, 2i32));
+};
+const main = fn main() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)(twice(21i32));
+};
+";
+
+    #[test]
+    fn test_analysis() {
+        let analysis = Analysis::new("unit.orco", UNIT.to_owned());
+        check!(analysis.diagnostics.is_empty());
+        check!(
+            analysis
+                .symbols
+                .iter()
+                .map(|symbol| symbol.name.as_str())
+                .collect::<Vec<_>>()
+                == ["twice", "main"]
+        );
+
+        let call = UNIT.find("twice(21i32)").unwrap();
+        check!(analysis.type_at(call + 7) == Some(&orco::Type::Integer(32)));
+        let_assert!(Some(definition) = analysis.definition_at(call));
+        check!(UNIT[definition.clone()].starts_with("fn twice(x: i32)"));
+
+        let x = UNIT.find("x: i32, 2i32").unwrap();
+        check!(analysis.type_at(x) == Some(&orco::Type::Integer(32)));
+
+        let analysis = Analysis::new("unit.orco", "const x = ;".to_owned());
+        let_assert!([error] = &analysis.diagnostics[..]);
+        check!(error.code == Some("E0000"));
+    }
+
+    #[test]
+    fn test_c() {
+        const SOURCE: &str = "\
+int twice(int x) {
+    return x * 2;
+}
+
+int main(void) {
+    int answer = twice(21);
+    return answer;
+}
+";
+        // Spans of the previous analysis are invalidated every time
+        for _ in 0..1000 {
+            let analysis = Analysis::new("main.c", SOURCE.to_owned());
+            check!(analysis.diagnostics.is_empty());
+
+            let x = SOURCE.find("x * 2").unwrap();
+            let_assert!(Some(definition) = analysis.definition_at(x));
+            check!(SOURCE[definition.clone()] == *"int x");
+
+            let answer = SOURCE.rfind("answer").unwrap();
+            let_assert!(Some(definition) = analysis.definition_at(answer));
+            check!(definition.start == SOURCE.find("answer").unwrap());
+
+            let call = SOURCE.find("twice(21)").unwrap();
+            let_assert!(Some(definition) = analysis.definition_at(call));
+            check!(definition.start == SOURCE.find("twice").unwrap());
+            check!(SOURCE[definition.clone()] == *"twice");
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::analysis::Symbol;
+use orco_c::symbol::Symbol as CSymbol;
+
+/// Build a C document, along with its outline from [orco_c::Unit::symbols].
+/// Spans of the documents, that were built before on this thread, are invalidated
+pub fn build(
+    ctx: &mut orco::TypeInferenceContext,
+    source: &str,
+) -> Result<(orco::Symbols, Vec<Symbol>), (String, std::ops::Range<usize>)> {
+    // Otherwise every version of every document stays in the source map of proc-macro2,
+    // and the server runs out of memory or span positions
+    proc_macro2::extra::invalidate_current_thread_spans();
+    let unit = orco_c::parsel::parse_str::<orco_c::Unit>(source)
+        .map_err(|err| (err.to_string(), err.span().byte_range()))?;
+    let outline = unit
+        .symbols
+        .iter()
+        .filter_map(|symbol| match symbol {
+            CSymbol::FunctionDefinition(function) => Some(Symbol {
+                name: function.name.to_string(),
+                kind: lsp_types::SymbolKind::FUNCTION,
+                range: orco_c::range(function),
+                selection: orco_c::range(&function.name),
+            }),
+            CSymbol::StructDeclaration(orco_c::Type::Struct(r#struct), _) => {
+                let name = r#struct.name.as_prefix()?;
+                Some(Symbol {
+                    name: format!("struct {}", name),
+                    kind: lsp_types::SymbolKind::STRUCT,
+                    range: orco_c::range(symbol),
+                    selection: orco_c::range(name),
+                })
+            }
+            CSymbol::StructDeclaration(..) => None,
+        })
+        .collect();
+    Ok((unit.build(ctx), outline))
+}
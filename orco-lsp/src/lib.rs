@@ -0,0 +1,479 @@
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::Notification as _;
+use lsp_types::request::Request as _;
+use lsp_types::{notification, request, Url};
+use std::collections::HashMap;
+
+/// See [analysis::Analysis]
+pub mod analysis;
+pub use analysis::Analysis;
+
+/// Documents, that go through the C frontend
+pub mod c;
+
+/// Error, that stops the server
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// What the server can do, sent in response to `initialize`
+pub fn capabilities() -> lsp_types::ServerCapabilities {
+    lsp_types::ServerCapabilities {
+        text_document_sync: Some(lsp_types::TextDocumentSyncCapability::Kind(
+            lsp_types::TextDocumentSyncKind::FULL,
+        )),
+        hover_provider: Some(lsp_types::HoverProviderCapability::Simple(true)),
+        definition_provider: Some(lsp_types::OneOf::Left(true)),
+        document_symbol_provider: Some(lsp_types::OneOf::Left(true)),
+        ..Default::default()
+    }
+}
+
+/// Serve a client until it asks to shut down. Documents are analyzed
+/// from scratch on every change, see [Analysis]
+pub fn run(connection: &Connection) -> Result<(), Error> {
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+    let mut server = Server {
+        connection,
+        documents: HashMap::new(),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                server.request(request)?;
+            }
+            Message::Notification(notification) => server.notification(notification)?,
+            Message::Response(_) => (),
+        }
+    }
+    Ok(())
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    documents: HashMap<Url, Analysis>,
+}
+
+impl Server<'_> {
+    fn request(&mut self, request: Request) -> Result<(), Error> {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            request::HoverRequest::METHOD => {
+                let Some(params) = self.extract::<request::HoverRequest>(request)? else {
+                    return Ok(());
+                };
+                let position = params.text_document_position_params;
+                serde_json::to_value(self.hover(&position))?
+            }
+            request::GotoDefinition::METHOD => {
+                let Some(params) = self.extract::<request::GotoDefinition>(request)? else {
+                    return Ok(());
+                };
+                let position = params.text_document_position_params;
+                serde_json::to_value(self.definition(&position))?
+            }
+            request::DocumentSymbolRequest::METHOD => {
+                let Some(params) = self.extract::<request::DocumentSymbolRequest>(request)? else {
+                    return Ok(());
+                };
+                serde_json::to_value(self.symbols(&params.text_document.uri))?
+            }
+            method => {
+                return self.respond(Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request `{}`", method),
+                ))
+            }
+        };
+        self.respond(Response::new_ok(id, result))
+    }
+
+    /// Parameters of a request. Invalid ones are answered with
+    /// [lsp_server::ErrorCode::InvalidParams], there are none then
+    fn extract<R: request::Request>(&self, request: Request) -> Result<Option<R::Params>, Error> {
+        let id = request.id.clone();
+        match request.extract(R::METHOD) {
+            Ok((_, params)) => Ok(Some(params)),
+            Err(err) => {
+                self.respond(Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::InvalidParams as i32,
+                    err.to_string(),
+                ))?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn respond(&self, response: Response) -> Result<(), Error> {
+        self.connection.sender.send(Message::Response(response))?;
+        Ok(())
+    }
+
+    fn notification(&mut self, notification: Notification) -> Result<(), Error> {
+        match notification.method.as_str() {
+            notification::DidOpenTextDocument::METHOD => {
+                let Some(params) =
+                    self.extract_notification::<notification::DidOpenTextDocument>(notification)?
+                else {
+                    return Ok(());
+                };
+                self.open(params.text_document.uri, params.text_document.text)
+            }
+            notification::DidChangeTextDocument::METHOD => {
+                let Some(params) =
+                    self.extract_notification::<notification::DidChangeTextDocument>(notification)?
+                else {
+                    return Ok(());
+                };
+                // Documents are synced in full, the last change is the whole text
+                match params.content_changes.into_iter().last() {
+                    Some(change) => self.open(params.text_document.uri, change.text),
+                    None => Ok(()),
+                }
+            }
+            notification::DidCloseTextDocument::METHOD => {
+                let Some(params) =
+                    self.extract_notification::<notification::DidCloseTextDocument>(notification)?
+                else {
+                    return Ok(());
+                };
+                self.documents.remove(&params.text_document.uri);
+                self.publish(params.text_document.uri, Vec::new())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Parameters of a notification. There is no one to answer,
+    /// so invalid ones are logged to the client and skipped
+    fn extract_notification<N: notification::Notification>(
+        &self,
+        notification: Notification,
+    ) -> Result<Option<N::Params>, Error> {
+        match notification.extract(N::METHOD) {
+            Ok(params) => Ok(Some(params)),
+            Err(err) => {
+                let params = lsp_types::LogMessageParams {
+                    typ: lsp_types::MessageType::WARNING,
+                    message: format!("skipped `{}`: {}", N::METHOD, err),
+                };
+                self.connection
+                    .sender
+                    .send(Message::Notification(Notification::new(
+                        notification::LogMessage::METHOD.to_owned(),
+                        params,
+                    )))?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Analyze a new version of a document and publish its diagnostics
+    fn open(&mut self, uri: Url, text: String) -> Result<(), Error> {
+        let analysis = Analysis::new(uri.path(), text);
+        let diagnostics = analysis
+            .diagnostics
+            .iter()
+            .map(|diagnostic| self::diagnostic(&analysis, diagnostic))
+            .collect();
+        self.documents.insert(uri.clone(), analysis);
+        self.publish(uri, diagnostics)
+    }
+
+    fn publish(&self, uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> Result<(), Error> {
+        let params = lsp_types::PublishDiagnosticsParams::new(uri, diagnostics, None);
+        self.connection
+            .sender
+            .send(Message::Notification(Notification::new(
+                notification::PublishDiagnostics::METHOD.to_owned(),
+                params,
+            )))?;
+        Ok(())
+    }
+
+    fn hover(&self, position: &lsp_types::TextDocumentPositionParams) -> Option<lsp_types::Hover> {
+        let analysis = self.documents.get(&position.text_document.uri)?;
+        let r#type = analysis.type_at(offset(analysis.source(), position.position))?;
+        Some(lsp_types::Hover {
+            contents: lsp_types::HoverContents::Markup(lsp_types::MarkupContent {
+                kind: lsp_types::MarkupKind::Markdown,
+                value: format!("```orco\n{}\n```", r#type),
+            }),
+            range: None,
+        })
+    }
+
+    fn definition(
+        &self,
+        position: &lsp_types::TextDocumentPositionParams,
+    ) -> Option<lsp_types::GotoDefinitionResponse> {
+        let uri = &position.text_document.uri;
+        let analysis = self.documents.get(uri)?;
+        let source = analysis.source();
+        let definition = analysis.definition_at(offset(source, position.position))?;
+        Some(lsp_types::GotoDefinitionResponse::Scalar(
+            lsp_types::Location::new(uri.clone(), range(source, definition.clone())),
+        ))
+    }
+
+    fn symbols(&self, uri: &Url) -> Option<lsp_types::DocumentSymbolResponse> {
+        let analysis = self.documents.get(uri)?;
+        let source = analysis.source();
+        let symbols = analysis
+            .symbols
+            .iter()
+            .map(|symbol| {
+                #[allow(deprecated)]
+                lsp_types::DocumentSymbol {
+                    name: symbol.name.clone(),
+                    detail: None,
+                    kind: symbol.kind,
+                    tags: None,
+                    deprecated: None,
+                    range: range(source, symbol.range.clone()),
+                    selection_range: range(source, symbol.selection.clone()),
+                    children: None,
+                }
+            })
+            .collect();
+        Some(lsp_types::DocumentSymbolResponse::Nested(symbols))
+    }
+}
+
+/// Convert a diagnostic, the first label of the document is the range
+fn diagnostic(analysis: &Analysis, diagnostic: &orco::Diagnostic) -> lsp_types::Diagnostic {
+    let source = analysis.source();
+    let label = diagnostic
+        .labels
+        .iter()
+        .find(|label| label.span.file == analysis.file());
+    let mut message = diagnostic.message.clone();
+    if let Some(text) = label.and_then(|label| label.message.as_ref()) {
+        message.push_str(&format!(": {}", text));
+    }
+    for note in &diagnostic.notes {
+        message.push_str(&format!("\nnote: {}", note));
+    }
+    lsp_types::Diagnostic {
+        range: range(source, label.map_or(0..0, |label| label.span.range())),
+        severity: Some(match diagnostic.severity {
+            orco::diagnostics::Severity::Error => lsp_types::DiagnosticSeverity::ERROR,
+            orco::diagnostics::Severity::Warning => lsp_types::DiagnosticSeverity::WARNING,
+            orco::diagnostics::Severity::Note => lsp_types::DiagnosticSeverity::INFORMATION,
+            orco::diagnostics::Severity::Help => lsp_types::DiagnosticSeverity::HINT,
+        }),
+        code: diagnostic
+            .code
+            .map(|code| lsp_types::NumberOrString::String(code.to_owned())),
+        source: Some("orco".to_owned()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// LSP position of a byte offset. Columns are counted in UTF-16 code units
+pub fn position(source: &str, offset: usize) -> lsp_types::Position {
+    let offset = offset.min(source.len());
+    let line_start = source[..offset].rfind('\n').map_or(0, |index| index + 1);
+    lsp_types::Position::new(
+        source[..line_start].matches('\n').count() as u32,
+        source[line_start..offset].encode_utf16().count() as u32,
+    )
+}
+
+/// Byte offset of an LSP position, clamped to the line and the document
+pub fn offset(source: &str, position: lsp_types::Position) -> usize {
+    let Some(line_start) = (0..position.line).try_fold(0, |start, _| {
+        source[start..].find('\n').map(|index| start + index + 1)
+    }) else {
+        return source.len();
+    };
+    let line = source[line_start..].split('\n').next().unwrap_or_default();
+    let mut units = 0;
+    for (index, char) in line.char_indices() {
+        if units >= position.character as usize {
+            return line_start + index;
+        }
+        units += char.len_utf16();
+    }
+    line_start + line.len()
+}
+
+fn range(source: &str, range: std::ops::Range<usize>) -> lsp_types::Range {
+    lsp_types::Range::new(position(source, range.start), position(source, range.end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+    use lsp_server::RequestId;
+    use serde_json::{json, Value};
+
+    const SOURCE: &str = "\
+struct Point { int x; int y; };
+
+int main(void) {
+    int answer = 42;
+    return answer;
+}
+";
+
+    /// Scripted client, talking JSON-RPC to the server over an in-memory connection
+    struct Client {
+        connection: Connection,
+        id: i32,
+    }
+
+    impl Client {
+        fn response(&mut self, method: &str, params: Value) -> Response {
+            self.id += 1;
+            let request = Request::new(RequestId::from(self.id), method.to_owned(), params);
+            self.connection.sender.send(request.into()).unwrap();
+            loop {
+                match self.connection.receiver.recv().unwrap() {
+                    Message::Response(response) => {
+                        check!(response.id == RequestId::from(self.id));
+                        return response;
+                    }
+                    _ => continue,
+                }
+            }
+        }
+
+        fn request(&mut self, method: &str, params: Value) -> Value {
+            let response = self.response(method, params);
+            check!(response.error.is_none());
+            response.result.unwrap_or_default()
+        }
+
+        fn notify(&self, method: &str, params: Value) {
+            let notification = Notification::new(method.to_owned(), params);
+            self.connection.sender.send(notification.into()).unwrap();
+        }
+
+        /// Parameters of the next notification with the method
+        fn notification(&self, method: &str) -> Value {
+            loop {
+                if let Message::Notification(notification) =
+                    self.connection.receiver.recv().unwrap()
+                {
+                    if notification.method == method {
+                        return notification.params;
+                    }
+                }
+            }
+        }
+
+        fn diagnostics(&self) -> Value {
+            self.notification(notification::PublishDiagnostics::METHOD)["diagnostics"].clone()
+        }
+    }
+
+    fn at(uri: &str, offset: usize) -> Value {
+        json!({
+            "textDocument": { "uri": uri },
+            "position": position(SOURCE, offset),
+        })
+    }
+
+    #[test]
+    fn test_server() {
+        let (server, connection) = Connection::memory();
+        let server = std::thread::spawn(move || run(&server));
+        let mut client = Client { connection, id: 0 };
+        let uri = "file:///main.c";
+
+        let result = client.request("initialize", json!({ "capabilities": {} }));
+        check!(result["capabilities"]["hoverProvider"] == true);
+        client.notify("initialized", json!({}));
+
+        let document = json!({ "uri": uri, "languageId": "c", "version": 1, "text": SOURCE });
+        client.notify("textDocument/didOpen", json!({ "textDocument": document }));
+        check!(client.diagnostics() == json!([]));
+
+        let answer = SOURCE.rfind("answer").unwrap();
+        let hover = client.request("textDocument/hover", at(uri, answer));
+        check!(hover["contents"]["value"] == "```orco\ni32\n```");
+
+        let definition = client.request("textDocument/definition", at(uri, answer));
+        check!(definition["uri"] == uri);
+        check!(
+            definition["range"]["start"] == json!(position(SOURCE, SOURCE.find("answer").unwrap()))
+        );
+
+        let symbols = client.request(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": uri } }),
+        );
+        let names = symbols
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|symbol| symbol["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        check!(names == ["struct Point", "main"]);
+
+        let change = json!({ "text": SOURCE.replace("return answer", "return missing") });
+        client.notify(
+            "textDocument/didChange",
+            json!({ "textDocument": { "uri": uri, "version": 2 }, "contentChanges": [change] }),
+        );
+        let diagnostics = client.diagnostics();
+        check!(diagnostics[0]["code"] == "E0001");
+        check!(diagnostics[0]["range"]["start"]["line"] == 4);
+
+        client.request("shutdown", Value::Null);
+        client.notify("exit", Value::Null);
+        check!(server.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let (server, connection) = Connection::memory();
+        let server = std::thread::spawn(move || run(&server));
+        let mut client = Client { connection, id: 0 };
+        client.request("initialize", json!({ "capabilities": {} }));
+        client.notify("initialized", json!({}));
+
+        // Notifications can't be answered, so they are logged and skipped
+        client.notify("textDocument/didOpen", json!({ "textDocument": 42 }));
+        let log = client.notification(notification::LogMessage::METHOD);
+        check!(log["type"] == json!(lsp_types::MessageType::WARNING));
+        check!(log["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("skipped `textDocument/didOpen`"));
+
+        let response = client.response("textDocument/hover", json!({ "position": 0 }));
+        let_assert!(Some(error) = response.error);
+        check!(error.code == lsp_server::ErrorCode::InvalidParams as i32);
+
+        // The server keeps going
+        let uri = "file:///main.c";
+        let document = json!({ "uri": uri, "languageId": "c", "version": 1, "text": SOURCE });
+        client.notify("textDocument/didOpen", json!({ "textDocument": document }));
+        check!(client.diagnostics() == json!([]));
+        let hover = client.request(
+            "textDocument/hover",
+            at(uri, SOURCE.rfind("answer").unwrap()),
+        );
+        check!(hover["contents"]["value"] == "```orco\ni32\n```");
+
+        client.request("shutdown", Value::Null);
+        client.notify("exit", Value::Null);
+        check!(server.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_positions() {
+        let source = "a\nλb\n";
+        check!(position(source, 4) == lsp_types::Position::new(1, 1));
+        check!(offset(source, lsp_types::Position::new(1, 1)) == 4);
+        check!(offset(source, lsp_types::Position::new(1, 9)) == 5);
+        check!(offset(source, lsp_types::Position::new(7, 0)) == source.len());
+    }
+}
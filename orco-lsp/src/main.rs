@@ -0,0 +1,8 @@
+//! Language server for OrCo, speaks LSP over stdio
+fn main() -> Result<(), orco_lsp::Error> {
+    let (connection, io_threads) = lsp_server::Connection::stdio();
+    orco_lsp::run(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
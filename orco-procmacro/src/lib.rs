@@ -227,3 +227,113 @@ pub fn mutref_clonecopy(item: TokenStream) -> TokenStream {
     }
     .into()
 }
+
+/// Generate `Visitor` and `VisitorMut` traits for an enum of single-field variants,
+/// with a `visit_<variant>` method per variant, and the `Walk` impl that dispatches
+/// to them. Every method walks into the children by default, see `orco::visitor::Walk`
+#[proc_macro_derive(Visit)]
+pub fn derive_visit(item: TokenStream) -> TokenStream {
+    let ast: DeriveInput = parse_macro_input!(item);
+    let syn::Data::Enum(data) = &ast.data else {
+        panic!("Visit can only be derived for enums")
+    };
+
+    let name = &ast.ident;
+    let vis = &ast.vis;
+    let visit = syn::Ident::new(
+        &format!("visit_{}", snake_case(&name.to_string())),
+        name.span(),
+    );
+
+    let mut methods = Vec::new();
+    let mut methods_mut = Vec::new();
+    let mut arms = Vec::new();
+    let mut arms_mut = Vec::new();
+    for variant in &data.variants {
+        let syn::Fields::Unnamed(fields) = &variant.fields else {
+            panic!(
+                "variant {} should have exactly one unnamed field",
+                variant.ident
+            )
+        };
+        let [field] = fields.unnamed.iter().collect::<Vec<_>>()[..] else {
+            panic!(
+                "variant {} should have exactly one unnamed field",
+                variant.ident
+            )
+        };
+        let r#type = &field.ty;
+        let variant = &variant.ident;
+        let method = syn::Ident::new(
+            &format!("visit_{}", snake_case(&variant.to_string())),
+            variant.span(),
+        );
+        let doc = format!("Visit [{}::{}]", name, variant);
+
+        methods.push(quote! {
+            #[doc = #doc]
+            fn #method(&mut self, node: &#r#type) {
+                crate::visitor::Walk::walk(node, self)
+            }
+        });
+        methods_mut.push(quote! {
+            #[doc = #doc]
+            fn #method(&mut self, node: &mut #r#type) {
+                crate::visitor::Walk::walk_mut(node, self)
+            }
+        });
+        arms.push(quote! { Self::#variant(node) => visitor.#method(node) });
+        arms_mut.push(quote! { Self::#variant(node) => visitor.#method(node) });
+    }
+
+    let doc = format!("Visit [{}], dispatches on its kind by default", name);
+    quote! {
+        /// Visitor over the IR by reference.
+        /// Every method walks into the children by default
+        #vis trait Visitor {
+            #[doc = #doc]
+            fn #visit(&mut self, node: &#name) {
+                crate::visitor::Walk::walk(node, self)
+            }
+
+            #(#methods)*
+        }
+
+        /// Visitor over the IR by mutable reference, used for rewrites.
+        /// Every method walks into the children by default
+        #vis trait VisitorMut {
+            #[doc = #doc]
+            fn #visit(&mut self, node: &mut #name) {
+                crate::visitor::Walk::walk_mut(node, self)
+            }
+
+            #(#methods_mut)*
+        }
+
+        impl crate::visitor::Walk for #name {
+            fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+                match self {
+                    #(#arms,)*
+                }
+            }
+
+            fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+                match self {
+                    #(#arms_mut,)*
+                }
+            }
+        }
+    }
+    .into()
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (index, c) in name.char_indices() {
+        if c.is_uppercase() && index > 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
@@ -0,0 +1,395 @@
+use crate::expression::function::FunctionBody;
+use crate::expression::{Call, Function, Literal};
+use crate::interpreter::{value::wrap_unsigned, Interpreter, Value};
+use crate::type_inference::intrinsics::{Intrinsic, Intrinsics};
+use crate::{Expression, Type, VisitorMut, Walk};
+
+/// Does calling this function only compute a value?
+/// Pure functions don't write through pointers or take addresses
+/// and only call other pure functions. Recursion is assumed to be pure
+pub fn is_pure(function: &crate::ArcLock<Function>) -> bool {
+    pure_function(function, &mut Vec::new())
+}
+
+fn pure_function(
+    function: &crate::ArcLock<Function>,
+    visiting: &mut Vec<*const std::sync::RwLock<Function>>,
+) -> bool {
+    let key = std::sync::Arc::as_ptr(function);
+    if visiting.contains(&key) {
+        return true;
+    }
+    // Functions, that are being written to, are the ones being inferred right now
+    let Ok(function) = function.try_read() else {
+        return false;
+    };
+    let FunctionBody::Block(body) = &function.body else {
+        return false;
+    };
+    visiting.push(key);
+    let pure = body.iter().all(|expression| pure(expression, visiting));
+    visiting.pop();
+    pure
+}
+
+fn pure(expression: &Expression, visiting: &mut Vec<*const std::sync::RwLock<Function>>) -> bool {
+    match expression {
+        Expression::Literal(_) | Expression::Variable(_) => true,
+        Expression::Function(function) => match &function.body {
+            FunctionBody::Block(body) => body.iter().all(|expression| pure(expression, visiting)),
+            FunctionBody::Intrinsic(_) => false,
+        },
+        Expression::Call(call) => {
+            let args = call.args.iter().all(|arg| pure(arg, visiting));
+            let Ok(function) = call.function.try_read() else {
+                return false;
+            };
+            match function.body {
+                FunctionBody::Intrinsic(Intrinsic::Store | Intrinsic::AddressOf) => false,
+                FunctionBody::Intrinsic(Intrinsic::Assign) => args && local_place(&call.args[0]),
+                FunctionBody::Intrinsic(_) => args,
+                FunctionBody::Block(_) => {
+                    drop(function);
+                    args && pure_function(&call.function, visiting)
+                }
+            }
+        }
+        Expression::TraitCall(_) | Expression::Comptime(_) | Expression::Error(_) => false,
+        Expression::Field(field) => pure(&field.value, visiting),
+        Expression::Index(index) => pure(&index.value, visiting) && pure(&index.index, visiting),
+    }
+}
+
+/// Is this place inside of a variable, and not behind a pointer?
+fn local_place(expression: &Expression) -> bool {
+    match expression {
+        Expression::Variable(_) => true,
+        Expression::Field(field) => local_place(&field.value),
+        Expression::Index(index) => {
+            matches!(index.container, Some(Type::Array(..))) && local_place(&index.value)
+        }
+        _ => false,
+    }
+}
+
+/// Can this expression be evaluated at compile time? Literals, operators,
+/// casts, aggregates and branches of constants and calls to pure
+/// functions with constant arguments are constant
+pub fn is_constant(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal(_) => true,
+        Expression::Function(function) => match &function.body {
+            FunctionBody::Block(body)
+                if function.signature.calling_convention
+                    == crate::types::CallingConvention::Transparent =>
+            {
+                body.iter().all(is_constant)
+            }
+            _ => false,
+        },
+        Expression::Call(call) => {
+            // Functions, that are being rewritten, are locked for writing
+            let Ok(function) = call.function.try_read() else {
+                return false;
+            };
+            let callee = match function.body {
+                FunctionBody::Intrinsic(intrinsic) => {
+                    intrinsic.is_operator()
+                        || intrinsic.cast().is_some()
+                        || matches!(
+                            intrinsic,
+                            Intrinsic::Branch | Intrinsic::Construct | Intrinsic::Array
+                        )
+                }
+                FunctionBody::Block(_) => {
+                    drop(function);
+                    is_pure(&call.function)
+                }
+            };
+            callee && call.args.iter().all(is_constant)
+        }
+        Expression::Field(field) => field.field.is_some() && is_constant(&field.value),
+        Expression::Index(index) => {
+            matches!(index.container, Some(Type::Array(..)))
+                && is_constant(&index.value)
+                && is_constant(&index.index)
+        }
+        Expression::Variable(_)
+        | Expression::TraitCall(_)
+        | Expression::Comptime(_)
+        | Expression::Error(_) => false,
+    }
+}
+
+/// Replace constant subtrees, that evaluate to a scalar, with literals.
+/// Subtrees, that fail to evaluate, f.e. divide by zero, are left for the runtime.
+/// Returns true if anything was folded
+pub fn fold(expression: &mut Expression) -> bool {
+    let mut folder = Folder { folded: false };
+    folder.visit_expression(expression);
+    folder.folded
+}
+
+/// [fold] every expression of a symbol
+pub fn fold_symbol(symbol: &mut crate::Symbol) -> bool {
+    let mut folder = Folder { folded: false };
+    symbol.walk_mut(&mut folder);
+    folder.folded
+}
+
+/// See [fold]
+struct Folder {
+    folded: bool,
+}
+
+impl VisitorMut for Folder {
+    fn visit_expression(&mut self, expression: &mut Expression) {
+        if !matches!(expression, Expression::Literal(_)) && is_constant(expression) {
+            if let Some(literal) = Interpreter::comptime()
+                .evaluate(expression)
+                .ok()
+                .as_ref()
+                .and_then(literal)
+            {
+                *expression = Expression::Literal(crate::Spanned::new(literal, expression.span()));
+                self.folded = true;
+                return;
+            }
+        }
+        expression.walk_mut(self);
+    }
+}
+
+/// Literal of a scalar value. Signed integers are stored
+/// as their bits, wrapped to the size of the type
+pub fn literal(value: &Value) -> Option<Literal> {
+    match *value {
+        Value::Bool(value) => Some(Literal::Bool(value)),
+        Value::Integer(value, size) => Some(Literal::Integer(
+            wrap_unsigned(value as u128, size),
+            Type::Integer(size),
+        )),
+        Value::Unsigned(value, size) => Some(Literal::Integer(value, Type::Unsigned(size))),
+        Value::Float(value, size) => Some(Literal::Float(value, Type::Float(size))),
+        _ => None,
+    }
+}
+
+/// Expression, that evaluates to a value of a type. [None] for
+/// values, that only make sense at compile time, f.e. pointers
+pub fn to_expression(
+    value: &Value,
+    r#type: &Type,
+    intrinsics: &Intrinsics,
+    span: Option<crate::Span>,
+) -> Option<Expression> {
+    let call = |function, args| {
+        Expression::Call(Call {
+            span,
+            ..Call::new(function, args)
+        })
+    };
+    match (value, r#type) {
+        (Value::Unit, Type::Unit | Type::Never) => Some(call(
+            std::sync::Arc::new(std::sync::RwLock::new(Function::new(
+                crate::function_signature![() -> () transparent],
+                None,
+                Vec::new(),
+            ))),
+            Vec::new(),
+        )),
+        (Value::Array(elements), Type::Array(element, len)) => {
+            let args = elements
+                .iter()
+                .map(|value| to_expression(value, element, intrinsics, span))
+                .collect::<Option<Vec<_>>>()?;
+            Some(call(intrinsics.array(element.as_ref().clone(), *len), args))
+        }
+        (Value::Struct(fields), Type::Struct(r#struct)) => {
+            let args = fields
+                .iter()
+                .zip(&r#struct.read().unwrap().fields)
+                .map(|(value, (_, r#type))| to_expression(value, r#type, intrinsics, span))
+                .collect::<Option<Vec<_>>>()?;
+            Some(call(intrinsics.construct(r#struct.clone()), args))
+        }
+        (Value::Pointer(pointer), Type::Pointer(..)) if pointer.is_null() => Some(
+            Expression::Literal(crate::Spanned::new(Literal::Null(r#type.clone()), span)),
+        ),
+        (value, _) => Some(Expression::Literal(crate::Spanned::new(
+            literal(value)?,
+            span,
+        ))),
+    }
+}
+
+impl crate::TypeInferenceContext {
+    /// Evaluate a standalone expression at compile time, f.e. an array size.
+    /// Types of the expression are inferred first, untyped literals get their defaults
+    pub fn evaluate(
+        &mut self,
+        expression: &mut Expression,
+    ) -> Result<Value, crate::diagnostics::DiagnosticId> {
+        let first = self.type_variables.len();
+        let reported = self.diagnostics.len();
+        expression.infer_types(self);
+        self.apply_defaults_since(first);
+        expression.finish_types(self);
+        if let Some(index) = self
+            .diagnostics
+            .iter()
+            .skip(reported)
+            .position(|diagnostic| diagnostic.severity == crate::diagnostics::Severity::Error)
+        {
+            return Err(crate::diagnostics::DiagnosticId(reported + index));
+        }
+        let span = expression.span();
+        self.run_comptime(std::slice::from_ref(expression), span)
+    }
+
+    /// Run code with inferred types at compile time, returns the value of the last expression
+    pub fn run_comptime(
+        &mut self,
+        body: &[Expression],
+        span: Option<crate::Span>,
+    ) -> Result<Value, crate::diagnostics::DiagnosticId> {
+        Interpreter::comptime()
+            .evaluate_block(body)
+            .map_err(|error| {
+                self.diagnostics.report(
+                    crate::Diagnostic::from(error)
+                        .with_label(span, "while evaluating this at compile time"),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    fn arc<T>(value: T) -> crate::ArcLock<T> {
+        std::sync::Arc::new(std::sync::RwLock::new(value))
+    }
+
+    fn integer(value: u128) -> Expression {
+        Expression::Literal(crate::Spanned::new(
+            Literal::Integer(value, Type::Integer(32)),
+            None,
+        ))
+    }
+
+    fn binary(intrinsic: Intrinsic, a: Expression, b: Expression) -> Expression {
+        Expression::Call(Call::new(
+            Intrinsics::new().binary(intrinsic, Type::Integer(32)),
+            vec![a, b],
+        ))
+    }
+
+    #[test]
+    fn test_fold() {
+        let x = arc(crate::Variable::new(
+            Some("x".to_owned()),
+            Type::Integer(32),
+        ));
+        let variable = Expression::Variable(crate::Spanned::new(x, None));
+        // x * (2 - 5)
+        let mut expression = binary(
+            Intrinsic::Mul,
+            variable,
+            binary(Intrinsic::Sub, integer(2), integer(5)),
+        );
+        check!(!is_constant(&expression));
+        fold(&mut expression);
+
+        let_assert!(Expression::Call(call) = &expression);
+        let_assert!(Expression::Variable(_) = &call.args[0]);
+        let_assert!(Expression::Literal(literal) = &call.args[1]);
+        let_assert!(Literal::Integer(bits, Type::Integer(32)) = &literal.inner);
+        check!(Value::from_bits(*bits, &Type::Integer(32)).unwrap() == Value::Integer(-3, 32));
+
+        // Division by zero is left for the runtime
+        let mut expression = binary(Intrinsic::Div, integer(1), integer(0));
+        fold(&mut expression);
+        let_assert!(Expression::Call(_) = expression);
+    }
+
+    #[test]
+    fn test_pure_function() {
+        let n = arc(crate::Variable::new(
+            Some("n".to_owned()),
+            Type::Integer(32),
+        ));
+        let double = arc(Function::new(
+            crate::function_signature![(n: {Type::Integer(32)}) -> {Type::Integer(32)}],
+            Some("double".to_owned()),
+            Vec::new(),
+        ));
+        double.write().unwrap().parameters = vec![n.clone()];
+        double.write().unwrap().body = FunctionBody::Block(vec![Expression::Call(Call::new(
+            arc(Function::intrinsic(
+                crate::function_signature![(value: {Type::Integer(32)}) -> ! transparent],
+                Intrinsic::Return,
+            )),
+            vec![binary(
+                Intrinsic::Add,
+                Expression::Variable(crate::Spanned::new(n.clone(), None)),
+                Expression::Variable(crate::Spanned::new(n, None)),
+            )],
+        ))]);
+        check!(is_pure(&double));
+
+        let mut ctx = crate::TypeInferenceContext::new();
+        let mut expression = Expression::Call(Call::new(double, vec![integer(21)]));
+        check!(ctx.evaluate(&mut expression) == Ok(Value::Integer(42, 32)));
+
+        // Writing through a pointer is a side effect
+        let pointer = Type::Pointer(Box::new(Type::Integer(32)), crate::types::Mutability::Mut);
+        let p = arc(crate::Variable::new(Some("p".to_owned()), pointer.clone()));
+        let write = arc(Function::new(
+            crate::function_signature![(p: {pointer}) -> ()],
+            Some("write".to_owned()),
+            vec![Expression::Call(Call::new(
+                ctx.intrinsics.store(Type::Integer(32)),
+                vec![
+                    Expression::Variable(crate::Spanned::new(p.clone(), None)),
+                    integer(1),
+                ],
+            ))],
+        ));
+        write.write().unwrap().parameters = vec![p];
+        check!(!is_pure(&write));
+    }
+
+    #[test]
+    fn test_comptime_block() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let x = arc(crate::Variable::new(Some("x".to_owned()), Type::Wildcard));
+        let variable = || Expression::Variable(crate::Spanned::new(x.clone(), None));
+        let assign = Expression::Call(Call::new(
+            ctx.intrinsics.assign(Type::Integer(32)),
+            vec![variable(), integer(6)],
+        ));
+        let mut block = Expression::Comptime(crate::expression::Comptime::new(vec![
+            assign,
+            binary(Intrinsic::Mul, variable(), integer(7)),
+        ]));
+        ctx.infer([&mut block]);
+        check!(!ctx.diagnostics.has_errors());
+        let_assert!(Expression::Literal(literal) = block);
+        let_assert!(Literal::Integer(42, Type::Integer(32)) = literal.inner);
+
+        // Runtime variables can't be read at compile time
+        let runtime = arc(crate::Variable::new(
+            Some("y".to_owned()),
+            Type::Integer(32),
+        ));
+        let mut block = Expression::Comptime(crate::expression::Comptime::new(vec![
+            Expression::Variable(crate::Spanned::new(runtime, None)),
+        ]));
+        ctx.infer([&mut block]);
+        let_assert!(Expression::Error(id) = block);
+        check!(ctx.diagnostics.get(id).code == Some("E0112"));
+    }
+}
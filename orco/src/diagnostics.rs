@@ -0,0 +1,238 @@
+/// Severity of a [Diagnostic]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Compilation can't continue past this point
+    Error,
+    /// Code compiles, but is most likely wrong
+    Warning,
+    /// Additional information
+    Note,
+    /// Suggestion on how to fix something
+    Help,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+            Self::Note => write!(f, "note"),
+            Self::Help => write!(f, "help"),
+        }
+    }
+}
+
+/// A location in the source code with an optional message attached
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+    /// Location in the source code
+    pub span: crate::Span,
+    /// Message to show next to the location
+    pub message: Option<String>,
+}
+
+/// An error, a warning or anything else worth reporting to the user
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// See [Severity]
+    pub severity: Severity,
+    /// Error code, f.e. `E0001`
+    pub code: Option<&'static str>,
+    /// Main message
+    pub message: String,
+    /// Source locations, the first one is the primary location
+    pub labels: Vec<Label>,
+    /// Notes, shown after the source code
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic without labels and notes
+    pub fn new(severity: Severity, code: Option<&'static str>, message: impl ToString) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.to_string(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    /// Create a new error
+    pub fn error(code: &'static str, message: impl ToString) -> Self {
+        Self::new(Severity::Error, Some(code), message)
+    }
+
+    /// Create a new warning
+    pub fn warning(code: &'static str, message: impl ToString) -> Self {
+        Self::new(Severity::Warning, Some(code), message)
+    }
+
+    /// Add a labeled source location. Does nothing if the location is unknown
+    pub fn with_label(
+        mut self,
+        span: impl Into<Option<crate::Span>>,
+        message: impl ToString,
+    ) -> Self {
+        if let Some(span) = span.into() {
+            self.labels.push(Label {
+                span,
+                message: Some(message.to_string()),
+            });
+        }
+        self
+    }
+
+    /// Add a note
+    pub fn with_note(mut self, note: impl ToString) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    /// Render this diagnostic with source code snippets
+    pub fn render(&self, source_map: &crate::SourceMap) -> String {
+        use std::fmt::Write;
+
+        let mut output = String::new();
+        writeln!(output, "{}", self.header()).unwrap();
+        for label in &self.labels {
+            let file = source_map.file(label.span.file);
+            let (line, column) = file.location(label.span.range().start);
+            let text = file.line(line - 1);
+            let width = text
+                .chars()
+                .skip(column - 1)
+                .take(source_map.snippet(label.span).chars().count())
+                .count()
+                .max(1);
+
+            let gutter = " ".repeat(line.to_string().len());
+            writeln!(output, "{}--> {}:{}:{}", gutter, file.name, line, column).unwrap();
+            writeln!(output, "{} |", gutter).unwrap();
+            writeln!(output, "{} | {}", line, text).unwrap();
+            writeln!(
+                output,
+                "{} | {}{} {}",
+                gutter,
+                " ".repeat(column - 1),
+                "^".repeat(width),
+                label.message.as_deref().unwrap_or("")
+            )
+            .unwrap();
+        }
+        for note in &self.notes {
+            writeln!(output, "  = note: {}", note).unwrap();
+        }
+        output
+    }
+
+    fn header(&self) -> String {
+        match self.code {
+            Some(code) => format!("{}[{}]: {}", self.severity, code, self.message),
+            None => format!("{}: {}", self.severity, self.message),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.header())?;
+        for note in &self.notes {
+            write!(f, "\n  = note: {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Handle to a reported [Diagnostic], see [Diagnostics::report]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DiagnosticId(pub usize);
+
+/// Diagnostic sink. Collects everything reported by frontends and passes,
+/// so that many errors can be shown in one run
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Create an empty sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report a diagnostic
+    pub fn report(&mut self, diagnostic: Diagnostic) -> DiagnosticId {
+        self.diagnostics.push(diagnostic);
+        DiagnosticId(self.diagnostics.len() - 1)
+    }
+
+    /// Get a reported diagnostic
+    pub fn get(&self, id: DiagnosticId) -> &Diagnostic {
+        &self.diagnostics[id.0]
+    }
+
+    /// Were any errors reported?
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Iterate over all reported diagnostics
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    /// Number of reported diagnostics
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    /// Were no diagnostics reported?
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    #[test]
+    fn test_render() {
+        let source = "int main() {\n    return x;\n}\n";
+        let mut source_map = crate::SourceMap::new();
+        let file = source_map.add_file("main.c", source);
+        let start = source.find('x').unwrap();
+        let diagnostic = Diagnostic::error("E0001", "cannot find variable `x` in this scope")
+            .with_label(
+                crate::Span::new(file, start..start + 1),
+                "not found in this scope",
+            );
+        check!(
+            diagnostic.render(&source_map)
+                == [
+                    "error[E0001]: cannot find variable `x` in this scope",
+                    " --> main.c:2:12",
+                    "  |",
+                    "2 |     return x;",
+                    "  |            ^ not found in this scope",
+                    "",
+                ]
+                .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_sink() {
+        let mut diagnostics = Diagnostics::new();
+        check!(!diagnostics.has_errors());
+        diagnostics.report(Diagnostic::warning("W0001", "unused variable"));
+        check!(!diagnostics.has_errors());
+        let id = diagnostics.report(Diagnostic::error("E0001", "oops"));
+        check!(diagnostics.has_errors());
+        check!(diagnostics.get(id).message == "oops");
+    }
+}
@@ -1,12 +1,162 @@
+/// See [TraitCall]
+pub mod trait_call;
+pub use trait_call::TraitCall;
+
 pub struct Call {
     /// Function to call
     pub function: crate::ArcLock<crate::expression::Function>,
     /// Args for the function
     pub args: Vec<crate::Expression>,
+    /// Type arguments of a generic function, inferred at the call site
+    pub type_arguments: Vec<crate::Type>,
+    /// Location of this call in the source code
+    pub span: Option<crate::Span>,
+}
+
+impl Call {
+    /// Create a new call without a location
+    pub fn new(
+        function: crate::ArcLock<crate::expression::Function>,
+        args: Vec<crate::Expression>,
+    ) -> Self {
+        Self {
+            function,
+            args,
+            type_arguments: Vec::new(),
+            span: None,
+        }
+    }
+
+    /// Infer types of the arguments against the signature
+    /// of the called function, returns the type of the call
+    pub fn infer_types(&mut self, ctx: &mut crate::TypeInferenceContext) -> crate::Type {
+        use crate::expression::function::FunctionBody;
+        use crate::type_inference::intrinsics::Intrinsic;
+
+        let (mut signature, is_return) = {
+            let function = self.function.read().unwrap();
+            (
+                function.signature.clone(),
+                matches!(function.body, FunctionBody::Intrinsic(Intrinsic::Return)),
+            )
+        };
+
+        // Every call gets it's own instance of a generic function
+        if !signature.type_parameters.is_empty() {
+            self.type_arguments = signature
+                .type_parameters
+                .iter()
+                .map(|_| ctx.new_type_variable(crate::type_inference::TypeVariableKind::General))
+                .collect();
+            signature = signature.instantiate(&self.type_arguments);
+        }
+
+        if self.args.len() != signature.parameters.len() {
+            ctx.diagnostics.report(
+                crate::Diagnostic::from(crate::type_inference::TypeError::ArgumentCount {
+                    expected: signature.parameters.len(),
+                    found: self.args.len(),
+                })
+                .with_label(self.span, "in this call"),
+            );
+        }
+
+        for (index, arg) in self.args.iter_mut().enumerate() {
+            let found = arg.infer_types(ctx);
+            let expected = match ctx.return_types.last() {
+                Some(return_type) if is_return => Some(return_type.clone()),
+                _ => signature
+                    .parameters
+                    .get(index)
+                    .map(|(_, r#type)| r#type.clone()),
+            };
+            if let Some(expected) = expected {
+                ctx.expect(&expected, &found, arg.span().or(self.span));
+            }
+        }
+
+        ctx.instantiate_wildcards(&mut signature.return_type);
+        *signature.return_type
+    }
+
+    /// Write solved types back into the arguments. Intrinsics are
+    /// instantiated per call, so their signatures are finished too,
+    /// and [Cast::Convert](crate::type_inference::intrinsics::Cast::Convert)
+    /// gets resolved to a concrete conversion
+    pub fn finish_types(&mut self, ctx: &mut crate::TypeInferenceContext) {
+        use crate::type_inference::intrinsics::{Cast, Intrinsic};
+        for arg in &mut self.args {
+            arg.finish_types(ctx);
+        }
+        for r#type in &mut self.type_arguments {
+            ctx.finish_type(r#type, self.span);
+        }
+
+        if let Ok(mut function) = self.function.try_write() {
+            if let crate::expression::function::FunctionBody::Intrinsic(intrinsic) = function.body {
+                function.finish_types(ctx);
+                if let Some(cast) = intrinsic.cast() {
+                    let from = &function.signature.parameters[0].1;
+                    let to = function.signature.return_type.as_ref();
+                    if !cast.supports(from, to) {
+                        let mut diagnostic = crate::Diagnostic::error(
+                            "E0108",
+                            format!("can't convert `{}` to `{}` with `{}`", from, to, intrinsic),
+                        )
+                        .with_label(self.span, "invalid conversion");
+                        if let Intrinsic::ImplicitCast(_) = intrinsic {
+                            diagnostic =
+                                diagnostic.with_note("the conversion was inserted implicitly");
+                        }
+                        ctx.diagnostics.report(diagnostic);
+                    } else if cast == Cast::Convert {
+                        // Concrete kind is only known after type inference
+                        let cast = Cast::between(from, to).unwrap_or(Cast::Bitcast);
+                        function.body =
+                            crate::expression::function::FunctionBody::Intrinsic(match intrinsic {
+                                Intrinsic::ImplicitCast(_) => Intrinsic::ImplicitCast(cast),
+                                _ => Intrinsic::Cast(cast),
+                            });
+                    }
+                }
+                if let Some((_, r#type)) = function.signature.parameters.first() {
+                    if !intrinsic.supports(r#type) {
+                        ctx.diagnostics.report(
+                            crate::Diagnostic::error(
+                                "E0104",
+                                format!(
+                                    "`{}` can't be applied to values of type `{}`",
+                                    intrinsic, r#type
+                                ),
+                            )
+                            .with_label(self.span, "here"),
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
 
-                .unwrap_or("<unnamed function>")
-        )?;
+impl std::fmt::Display for Call {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let function = self.function.read().unwrap();
+        match &function.name {
+            Some(name) => write!(f, "{}", name)?,
+            // Intrinsics and other unnamed functions are printed in place
+            None => write!(f, "({})", function)?,
+        }
+        if !self.type_arguments.is_empty() {
+            write!(f, "::<")?;
+            for (index, r#type) in self.type_arguments.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", r#type)?;
+            }
+            write!(f, ">")?;
+        }
+        write!(f, "(")?;
         for (index, arg) in self.args.iter().enumerate() {
             if index > 0 {
                 write!(f, ", ")?;
@@ -17,3 +167,48 @@ pub struct Call {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{Function, Literal};
+    use crate::Type;
+    use assert2::*;
+
+    #[test]
+    fn test_generic_call() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let parameter = Type::Parameter("T".to_owned());
+        let signature = crate::types::FunctionSignature {
+            type_parameters: vec!["T".to_owned()],
+            ..crate::function_signature![(value: {parameter.clone()}) -> {parameter}]
+        };
+        let identity = std::sync::Arc::new(std::sync::RwLock::new(Function::new(
+            signature,
+            Some("identity".to_owned()),
+            Vec::new(),
+        )));
+
+        let mut call = crate::Expression::Call(Call::new(
+            identity.clone(),
+            vec![crate::Expression::Literal(crate::Spanned::new(
+                Literal::Bool(true),
+                None,
+            ))],
+        ));
+        let mut other = crate::Expression::Call(Call::new(
+            identity,
+            vec![crate::Expression::Literal(crate::Spanned::new(
+                Literal::Float(1.0, Type::Float(32)),
+                None,
+            ))],
+        ));
+        ctx.infer([&mut call, &mut other]);
+        check!(!ctx.diagnostics.has_errors());
+
+        let_assert!(crate::Expression::Call(call) = call);
+        check!(call.type_arguments == [Type::Bool]);
+        let_assert!(crate::Expression::Call(other) = other);
+        check!(other.type_arguments == [Type::Float(32)]);
+    }
+}
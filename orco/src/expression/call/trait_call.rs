@@ -0,0 +1,300 @@
+/// Call of a trait method. The implementation is picked by the type of
+/// `Self`, once it is known, and the call is replaced with a regular [Call](super::Call)
+pub struct TraitCall {
+    /// Trait, that declares the method
+    pub r#trait: crate::traits::TraitRef,
+    /// Method name
+    pub method: String,
+    /// Args for the method
+    pub args: Vec<crate::Expression>,
+    /// Type, that implements the trait, filled in by type inference
+    pub self_type: Option<crate::Type>,
+    /// Location of this call in the source code
+    pub span: Option<crate::Span>,
+}
+
+impl TraitCall {
+    /// Create a new trait method call without a location
+    pub fn new(
+        r#trait: crate::traits::TraitRef,
+        method: String,
+        args: Vec<crate::Expression>,
+    ) -> Self {
+        Self {
+            r#trait,
+            method,
+            args,
+            self_type: None,
+            span: None,
+        }
+    }
+
+    /// Infer types of the arguments against the method signature,
+    /// `Self` gets a fresh type variable. Returns the type of the call
+    pub fn infer_types(&mut self, ctx: &mut crate::TypeInferenceContext) -> crate::Type {
+        let signature = self.r#trait.read().unwrap().method(&self.method).cloned();
+        let Some(signature) = signature else {
+            // Reported once the types are finished
+            for arg in &mut self.args {
+                arg.infer_types(ctx);
+            }
+            return crate::Type::Wildcard;
+        };
+
+        let self_type = ctx.new_type_variable(crate::type_inference::TypeVariableKind::General);
+        let signature = signature.substitute(&[("Self".to_owned(), self_type.clone())].into());
+        self.self_type = Some(self_type.clone());
+        let arithmetic = self.is_pointer_arithmetic();
+
+        if self.args.len() != signature.parameters.len() {
+            ctx.diagnostics.report(
+                crate::Diagnostic::from(crate::type_inference::TypeError::ArgumentCount {
+                    expected: signature.parameters.len(),
+                    found: self.args.len(),
+                })
+                .with_label(self.span, "in this call"),
+            );
+        }
+        for (index, (arg, (_, expected))) in
+            self.args.iter_mut().zip(&signature.parameters).enumerate()
+        {
+            let found = arg.infer_types(ctx);
+            // Pointers are offset by a number of elements, see [TraitCall::offset]
+            if index == 1
+                && arithmetic
+                && matches!(ctx.shallow_resolve(&self_type), crate::Type::Pointer(..))
+            {
+                let offset =
+                    ctx.new_type_variable(crate::type_inference::TypeVariableKind::Integer);
+                ctx.expect(&offset, &found, arg.span().or(self.span));
+                continue;
+            }
+            ctx.expect(expected, &found, arg.span().or(self.span));
+        }
+        for arg in self.args.iter_mut().skip(signature.parameters.len()) {
+            arg.infer_types(ctx);
+        }
+
+        let mut return_type = *signature.return_type;
+        ctx.instantiate_wildcards(&mut return_type);
+        return_type
+    }
+
+    /// Write solved types back into the arguments and resolve the implementation,
+    /// see [crate::TypeInferenceContext::find_method]. Returns the expression,
+    /// that replaces this call: a [Call](super::Call) or an error
+    pub fn finish_types(&mut self, ctx: &mut crate::TypeInferenceContext) -> crate::Expression {
+        for arg in &mut self.args {
+            arg.finish_types(ctx);
+        }
+        let Some(self_type) = &mut self.self_type else {
+            let diagnostic = crate::Diagnostic::error(
+                "E0109",
+                format!(
+                    "no method `{}` in trait `{}`",
+                    self.method,
+                    self.r#trait.read().unwrap().name
+                ),
+            )
+            .with_label(self.span, "unknown method");
+            return crate::Expression::Error(ctx.diagnostics.report(diagnostic));
+        };
+        ctx.finish_type(self_type, self.span);
+        let self_type = self_type.clone();
+        if let crate::Type::Pointer(r#type, mutability) = &self_type {
+            if self.is_pointer_arithmetic() && self.args.len() == 2 {
+                return self.offset(ctx, r#type.as_ref().clone(), *mutability);
+            }
+        }
+
+        match ctx.find_method(&self.r#trait, &self.method, &self_type) {
+            Some(function) => crate::Expression::Call(super::Call {
+                span: self.span,
+                ..super::Call::new(function, std::mem::take(&mut self.args))
+            }),
+            None => {
+                let diagnostic = crate::Diagnostic::error(
+                    "E0109",
+                    format!(
+                        "trait `{}` is not implemented for `{}`",
+                        self.r#trait.read().unwrap().name,
+                        self_type
+                    ),
+                )
+                .with_label(self.span, format!("`{}` is required here", self.method));
+                crate::Expression::Error(ctx.diagnostics.report(diagnostic))
+            }
+        }
+    }
+}
+
+impl TraitCall {
+    /// Is this an `Add` or a `Sub` operator? A pointer on the left
+    /// and an integer on the right offset the pointer, see [TraitCall::offset]
+    fn is_pointer_arithmetic(&self) -> bool {
+        use crate::type_inference::intrinsics::Intrinsic;
+        matches!(
+            self.r#trait.read().unwrap().intrinsic,
+            Some(Intrinsic::Add | Intrinsic::Sub)
+        )
+    }
+
+    /// Lower `pointer + offset` and `pointer - offset` to [Intrinsic::Offset].
+    /// Offsets are signed, unsigned ones are extended first
+    ///
+    /// [Intrinsic::Offset]: crate::type_inference::intrinsics::Intrinsic::Offset
+    fn offset(
+        &mut self,
+        ctx: &mut crate::TypeInferenceContext,
+        r#type: crate::Type,
+        mutability: crate::types::Mutability,
+    ) -> crate::Expression {
+        use crate::type_inference::intrinsics::{Cast, Intrinsic};
+        let span = self.span;
+        let call = |function, args| {
+            crate::Expression::Call(super::Call {
+                span,
+                ..super::Call::new(function, args)
+            })
+        };
+        let mut offset = self.args.pop().unwrap();
+        let mut offset_type = offset.r#type();
+        if let crate::Type::Unsigned(bits) = offset_type {
+            let signed = crate::Type::Integer(bits.max(64));
+            let cast = Cast::between(&offset_type, &signed).unwrap();
+            offset = call(
+                ctx.intrinsics
+                    .implicit_cast(cast, offset_type, signed.clone()),
+                vec![offset],
+            );
+            offset_type = signed;
+        }
+        if self.r#trait.read().unwrap().intrinsic == Some(Intrinsic::Sub) {
+            offset = call(
+                ctx.intrinsics.unary(Intrinsic::Neg, offset_type.clone()),
+                vec![offset],
+            );
+        }
+        let pointer = self.args.pop().unwrap();
+        call(
+            ctx.intrinsics.offset(r#type, mutability, offset_type),
+            vec![pointer, offset],
+        )
+    }
+}
+
+impl std::fmt::Display for TraitCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}(", self.r#trait.read().unwrap().name, self.method)?;
+        for (index, arg) in self.args.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            arg.fmt(f)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{Function, Literal};
+    use crate::type_inference::intrinsics::Intrinsic;
+    use crate::Type;
+    use assert2::*;
+
+    fn integer(value: u128) -> crate::Expression {
+        crate::Expression::Literal(crate::Spanned::new(
+            Literal::Integer(value, Type::Wildcard),
+            None,
+        ))
+    }
+
+    #[test]
+    fn test_operator_dispatch() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let add = ctx.operator(Intrinsic::Add);
+        let mut call = crate::Expression::TraitCall(TraitCall::new(
+            add.clone(),
+            "add".to_owned(),
+            vec![integer(1), integer(2)],
+        ));
+        ctx.infer([&mut call]);
+        check!(!ctx.diagnostics.has_errors());
+        let_assert!(crate::Expression::Call(call) = call);
+        check!(let crate::expression::function::FunctionBody::Intrinsic(Intrinsic::Add) = call.function.read().unwrap().body);
+
+        let unit = Type::Struct(crate::types::StructRef::new(crate::types::Struct::new(
+            Some("Unit".to_owned()),
+            Vec::new(),
+        )));
+        let unit_add = std::sync::Arc::new(std::sync::RwLock::new(Function::new(
+            crate::function_signature![(lhs: {unit.clone()}, rhs: {unit.clone()}) -> {unit.clone()}],
+            Some("unit_add".to_owned()),
+            Vec::new(),
+        )));
+        let variable = || {
+            crate::Expression::Variable(crate::Spanned::new(
+                std::sync::Arc::new(std::sync::RwLock::new(crate::Variable::new(
+                    None,
+                    unit.clone(),
+                ))),
+                None,
+            ))
+        };
+        let mut call = crate::Expression::TraitCall(TraitCall::new(
+            add.clone(),
+            "add".to_owned(),
+            vec![variable(), variable()],
+        ));
+        ctx.infer([&mut call]);
+        check!(let crate::Expression::Error(_) = call);
+        check!(ctx.diagnostics.has_errors());
+
+        let mut ctx = crate::TypeInferenceContext::new();
+        let add = ctx.operator(Intrinsic::Add);
+        ctx.implement(crate::traits::Impl::new(
+            add.clone(),
+            unit.clone(),
+            vec![("add".to_owned(), unit_add.clone())],
+        ));
+        let mut call = crate::Expression::TraitCall(TraitCall::new(
+            add,
+            "add".to_owned(),
+            vec![variable(), variable()],
+        ));
+        ctx.infer([&mut call]);
+        check!(!ctx.diagnostics.has_errors());
+        let_assert!(crate::Expression::Call(call) = call);
+        check!(std::sync::Arc::ptr_eq(&call.function, &unit_add));
+    }
+
+    #[test]
+    fn test_pointer_offset() {
+        use crate::expression::function::FunctionBody;
+        let mut ctx = crate::TypeInferenceContext::new();
+        let pointer = Type::Pointer(Box::new(Type::Integer(32)), crate::types::Mutability::Mut);
+        let variable = crate::Expression::Variable(crate::Spanned::new(
+            std::sync::Arc::new(std::sync::RwLock::new(crate::Variable::new(
+                None,
+                pointer.clone(),
+            ))),
+            None,
+        ));
+        let mut call = crate::Expression::TraitCall(TraitCall::new(
+            ctx.operator(Intrinsic::Sub),
+            "sub".to_owned(),
+            vec![variable, integer(1)],
+        ));
+        ctx.infer([&mut call]);
+        check!(!ctx.diagnostics.has_errors());
+        check!(call.r#type() == pointer);
+
+        // `offset(pointer, neg(1))`
+        let_assert!(crate::Expression::Call(call) = call);
+        check!(let FunctionBody::Intrinsic(Intrinsic::Offset) = call.function.read().unwrap().body);
+        let_assert!(crate::Expression::Call(neg) = &call.args[1]);
+        check!(let FunctionBody::Intrinsic(Intrinsic::Neg) = neg.function.read().unwrap().body);
+    }
+}
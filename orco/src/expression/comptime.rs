@@ -0,0 +1,66 @@
+/// Block of code, that is run during type inference.
+/// It's replaced with the value it evaluates to
+pub struct Comptime {
+    /// Expressions of the block, the last one is the value
+    pub body: Vec<crate::Expression>,
+    /// Type of the value, known after type inference
+    pub r#type: crate::Type,
+    /// Location of this block in the source code
+    pub span: Option<crate::Span>,
+}
+
+impl Comptime {
+    /// Create a new comptime block without a location
+    pub fn new(body: Vec<crate::Expression>) -> Self {
+        Self {
+            body,
+            r#type: crate::Type::Wildcard,
+            span: None,
+        }
+    }
+
+    /// Infer types of the body, returns the type of the last expression
+    pub fn infer_types(&mut self, ctx: &mut crate::TypeInferenceContext) -> crate::Type {
+        self.r#type = crate::Type::Unit;
+        for expression in &mut self.body {
+            self.r#type = expression.infer_types(ctx);
+        }
+        self.r#type.clone()
+    }
+
+    /// Finish types of the body and evaluate it. Returns the expression
+    /// that replaces this block: the value or an [crate::Expression::Error]
+    pub fn finish_types(&mut self, ctx: &mut crate::TypeInferenceContext) -> crate::Expression {
+        for expression in &mut self.body {
+            expression.finish_types(ctx);
+        }
+        ctx.finish_type(&mut self.r#type, self.span);
+
+        let value = match ctx.run_comptime(&self.body, self.span) {
+            Ok(value) => value,
+            Err(id) => return crate::Expression::Error(id),
+        };
+        match crate::comptime::to_expression(&value, &self.r#type, &ctx.intrinsics, self.span) {
+            Some(expression) => expression,
+            None => crate::Expression::Error(
+                ctx.diagnostics.report(
+                    crate::Diagnostic::error(
+                        "E0113",
+                        format!("comptime value `{}` can't be used at runtime", value),
+                    )
+                    .with_label(self.span, "evaluated here"),
+                ),
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Comptime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "comptime {{")?;
+        for expression in &self.body {
+            writeln!(f, "{}", indent::indent_all_by(4, format!("{expression};")))?;
+        }
+        write!(f, "}}")
+    }
+}
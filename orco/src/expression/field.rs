@@ -0,0 +1,105 @@
+/// Access a field of a struct value
+pub struct FieldAccess {
+    /// Struct value
+    pub value: Box<crate::Expression>,
+    /// Field name
+    pub name: String,
+    /// Struct that is accessed and index of the field in it, filled in by type inference
+    pub field: Option<(crate::types::StructRef, usize)>,
+    /// Location of this expression in the source code
+    pub span: Option<crate::Span>,
+}
+
+impl FieldAccess {
+    /// Create a new field access without a location
+    pub fn new(value: crate::Expression, name: String) -> Self {
+        Self {
+            value: Box::new(value),
+            name,
+            field: None,
+            span: None,
+        }
+    }
+
+    /// Infer the type of the value and look the field up, returns the type of the field
+    pub fn infer_types(&mut self, ctx: &mut crate::TypeInferenceContext) -> crate::Type {
+        use crate::Type;
+        let r#type = self.value.infer_types(ctx);
+        let r#type = ctx.shallow_resolve(&r#type);
+        let diagnostic = match &r#type {
+            Type::Struct(r#struct) => {
+                if let Some((index, field)) = r#struct.read().unwrap().field(&self.name) {
+                    self.field = Some((r#struct.clone(), index));
+                    return field.clone();
+                }
+                crate::Diagnostic::error(
+                    "E0105",
+                    format!("no field `{}` on type `{}`", self.name, r#type),
+                )
+                .with_label(self.span, "unknown field")
+            }
+            Type::Wildcard | Type::Never => return Type::Wildcard,
+            Type::Variable(_) => crate::Diagnostic::error(
+                "E0106",
+                "type annotations needed, type must be known to access a field",
+            )
+            .with_label(
+                self.value.span().or(self.span),
+                "type must be known at this point",
+            ),
+            _ => crate::Diagnostic::error(
+                "E0105",
+                format!("no field `{}` on type `{}`", self.name, r#type),
+            )
+            .with_label(self.span, "unknown field"),
+        };
+        ctx.diagnostics.report(diagnostic);
+        Type::Wildcard
+    }
+
+    /// Write solved types back into the value
+    pub fn finish_types(&mut self, ctx: &mut crate::TypeInferenceContext) {
+        self.value.finish_types(ctx);
+    }
+}
+
+impl std::fmt::Display for FieldAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.value, self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Struct, StructRef};
+    use crate::Type;
+    use assert2::*;
+
+    #[test]
+    fn test_field_inference() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let r#struct = StructRef::new(Struct::new(
+            Some("Point".to_owned()),
+            vec![
+                ("x".to_owned(), Type::Integer(32)),
+                ("y".to_owned(), Type::Float(64)),
+            ],
+        ));
+        let variable = crate::Expression::Variable(crate::Spanned::new(
+            std::sync::Arc::new(std::sync::RwLock::new(crate::Variable::new(
+                Some("point".to_owned()),
+                Type::Struct(r#struct),
+            ))),
+            None,
+        ));
+
+        let mut field = FieldAccess::new(variable, "y".to_owned());
+        check!(field.infer_types(&mut ctx) == Type::Float(64));
+        check!(let Some((_, 1)) = field.field);
+
+        let mut field = FieldAccess::new(*field.value, "z".to_owned());
+        check!(field.infer_types(&mut ctx) == Type::Wildcard);
+        check!(ctx.diagnostics.has_errors());
+    }
+}
@@ -12,8 +12,12 @@ pub struct Function {
     pub signature: FunctionSignature,
     /// Function name
     pub name: Option<String>,
+    /// Variables, holding values of the parameters inside of the body
+    pub parameters: Vec<crate::ArcLock<crate::Variable>>,
     /// Function body
     pub body: FunctionBody,
+    /// Location of this function in the source code
+    pub span: Option<crate::Span>,
 }
 
 impl Function {
@@ -25,7 +29,9 @@ impl Function {
         Self {
             signature,
             name,
+            parameters: Vec::new(),
             body: FunctionBody::Block(body),
+            span: None,
         }
     }
 
@@ -33,7 +39,57 @@ impl Function {
         Self {
             signature,
             name: None,
+            parameters: Vec::new(),
             body: FunctionBody::Intrinsic(intrinsic),
+            span: None,
+        }
+    }
+
+    /// Infer types in the signature and the body of this function
+    pub fn infer_types(&mut self, ctx: &mut crate::TypeInferenceContext) {
+        for (_, parameter) in &mut self.signature.parameters {
+            ctx.instantiate_wildcards(parameter);
+        }
+        ctx.instantiate_wildcards(&mut self.signature.return_type);
+        for (variable, (_, r#type)) in self.parameters.iter().zip(self.signature.parameters.iter())
+        {
+            let mut variable = variable.write().unwrap();
+            ctx.instantiate_wildcards(&mut variable.r#type);
+            ctx.expect(r#type, &variable.r#type, variable.span);
+        }
+
+        if let FunctionBody::Block(body) = &mut self.body {
+            let transparent =
+                self.signature.calling_convention == crate::types::CallingConvention::Transparent;
+            if !transparent {
+                ctx.return_types
+                    .push(self.signature.return_type.as_ref().clone());
+            }
+            for expression in body {
+                expression.infer_types(ctx);
+            }
+            if !transparent {
+                ctx.return_types.pop();
+            }
+        }
+    }
+
+    /// Write solved types back into this function
+    pub fn finish_types(&mut self, ctx: &mut crate::TypeInferenceContext) {
+        for (_, parameter) in &mut self.signature.parameters {
+            ctx.finish_type(parameter, self.span);
+        }
+        ctx.finish_type(&mut self.signature.return_type, self.span);
+        for variable in &self.parameters {
+            let mut variable = variable.write().unwrap();
+            let span = variable.span;
+            ctx.finish_type(&mut variable.r#type, span);
+        }
+
+        if let FunctionBody::Block(body) = &mut self.body {
+            for expression in body {
+                expression.finish_types(ctx);
+            }
         }
     }
 }
@@ -42,13 +98,20 @@ impl std::fmt::Display for Function {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
+            "fn {}{}",
+            self.name.as_deref().unwrap_or(""),
+            self.signature
+        )?;
+        match &self.body {
+            FunctionBody::Block(body) => {
+                writeln!(f, " {{")?;
                 for expression in body {
                     writeln!(f, "{}", indent::indent_all_by(4, format!("{expression};")))?;
                 }
                 write!(f, "}}")?;
                 Ok(())
             }
-            FunctionBody::Intrinsic(intrinsic) => write!(f, " = {:?}", intrinsic),
+            FunctionBody::Intrinsic(intrinsic) => write!(f, " = {}", intrinsic),
         }
     }
 }
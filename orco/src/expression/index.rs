@@ -0,0 +1,121 @@
+/// Access an element of an array, a slice or a value behind a pointer
+pub struct Index {
+    /// Array, slice or pointer value
+    pub value: Box<crate::Expression>,
+    /// Index of the element, an integer of any size
+    pub index: Box<crate::Expression>,
+    /// Type of the index, filled in by type inference
+    pub index_type: Option<crate::Type>,
+    /// Trap if the index is out of bounds. Pointers have no length, so they are never checked
+    pub checked: bool,
+    /// Type of the indexed value, filled in by type inference
+    pub container: Option<crate::Type>,
+    /// Location of this expression in the source code
+    pub span: Option<crate::Span>,
+}
+
+impl Index {
+    /// Create a new unchecked index without a location
+    pub fn new(value: crate::Expression, index: crate::Expression) -> Self {
+        Self {
+            value: Box::new(value),
+            index: Box::new(index),
+            index_type: None,
+            checked: false,
+            container: None,
+            span: None,
+        }
+    }
+
+    /// Infer the types of the value and the index, returns the type of the element
+    pub fn infer_types(&mut self, ctx: &mut crate::TypeInferenceContext) -> crate::Type {
+        use crate::Type;
+        let index = self.index.infer_types(ctx);
+        let integer = ctx.new_type_variable(crate::type_inference::TypeVariableKind::Integer);
+        ctx.expect(&integer, &index, self.index.span().or(self.span));
+        self.index_type = Some(integer);
+
+        let r#type = self.value.infer_types(ctx);
+        let r#type = ctx.shallow_resolve(&r#type);
+        let diagnostic = match &r#type {
+            Type::Array(element, _) | Type::Slice(element, _) | Type::Pointer(element, _) => {
+                let element = element.as_ref().clone();
+                self.container = Some(r#type);
+                return element;
+            }
+            Type::Wildcard | Type::Never => return Type::Wildcard,
+            Type::Variable(_) => crate::Diagnostic::error(
+                "E0106",
+                "type annotations needed, type must be known to index into a value",
+            )
+            .with_label(
+                self.value.span().or(self.span),
+                "type must be known at this point",
+            ),
+            _ => crate::Diagnostic::error(
+                "E0107",
+                format!("can't index into a value of type `{}`", r#type),
+            )
+            .with_label(self.span, "not an array, a slice or a pointer"),
+        };
+        ctx.diagnostics.report(diagnostic);
+        Type::Wildcard
+    }
+
+    /// Write solved types back into the value, the index and the filled in types
+    pub fn finish_types(&mut self, ctx: &mut crate::TypeInferenceContext) {
+        self.value.finish_types(ctx);
+        self.index.finish_types(ctx);
+        if let Some(index_type) = &mut self.index_type {
+            ctx.finish_type(index_type, self.index.span());
+        }
+        if let Some(container) = &mut self.container {
+            ctx.finish_type(container, self.span);
+        }
+    }
+}
+
+impl std::fmt::Display for Index {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}]", self.value, self.index)?;
+        if self.checked {
+            write!(f, "?")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::Literal;
+    use crate::Type;
+    use assert2::*;
+
+    #[test]
+    fn test_index_inference() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let variable = crate::Expression::Variable(crate::Spanned::new(
+            std::sync::Arc::new(std::sync::RwLock::new(crate::Variable::new(
+                Some("array".to_owned()),
+                Type::Array(Box::new(Type::Float(32)), 4),
+            ))),
+            None,
+        ));
+        let index = crate::Expression::Literal(crate::Spanned::new(
+            Literal::Integer(2, Type::Wildcard),
+            None,
+        ));
+
+        let mut expression = Index::new(variable, index);
+        check!(expression.infer_types(&mut ctx) == Type::Float(32));
+        check!(!ctx.diagnostics.has_errors());
+
+        let mut expression = Index::new(
+            *expression.value,
+            crate::Expression::Literal(crate::Spanned::new(Literal::Bool(true), None)),
+        );
+        expression.infer_types(&mut ctx);
+        check!(ctx.diagnostics.has_errors());
+    }
+}
@@ -3,6 +3,12 @@
 pub enum Literal {
     /// Unsigned integer literal, holding type and value
     Integer(u128, crate::Type),
+    /// Floating point literal, holding type and value
+    Float(f64, crate::Type),
+    /// Boolean literal
+    Bool(bool),
+    /// Null pointer, holding the pointer type
+    Null(crate::Type),
 }
 
 impl Literal {
@@ -10,6 +16,53 @@ impl Literal {
     pub fn r#type(&self) -> &crate::Type {
         match self {
             Self::Integer(_, r#type) => r#type,
+            Self::Float(_, r#type) => r#type,
+            Self::Bool(_) => &crate::Type::Bool,
+            Self::Null(r#type) => r#type,
+        }
+    }
+
+    /// Infer the type of this literal. Untyped number literals get an integer or a float type variable
+    pub fn infer_types(&mut self, ctx: &mut crate::TypeInferenceContext) -> crate::Type {
+        use crate::type_inference::TypeVariableKind;
+        match self {
+            Self::Integer(_, r#type) => {
+                if let crate::Type::Wildcard = r#type {
+                    *r#type = ctx.new_type_variable(TypeVariableKind::Integer);
+                }
+                r#type.clone()
+            }
+            Self::Float(_, r#type) => {
+                if let crate::Type::Wildcard = r#type {
+                    *r#type = ctx.new_type_variable(TypeVariableKind::Float);
+                }
+                r#type.clone()
+            }
+            Self::Bool(_) => crate::Type::Bool,
+            // Null is a mutable pointer to anything, so it fits const pointers too
+            Self::Null(r#type) => {
+                if let crate::Type::Wildcard = r#type {
+                    *r#type = crate::Type::Pointer(
+                        Box::new(ctx.new_type_variable(TypeVariableKind::General)),
+                        crate::types::Mutability::Mut,
+                    );
+                }
+                r#type.clone()
+            }
+        }
+    }
+
+    /// Write the solved type back into this literal
+    pub fn finish_types(
+        &mut self,
+        ctx: &mut crate::TypeInferenceContext,
+        span: Option<crate::Span>,
+    ) {
+        match self {
+            Self::Integer(_, r#type) => ctx.finish_type(r#type, span),
+            Self::Float(_, r#type) => ctx.finish_type(r#type, span),
+            Self::Bool(_) => (),
+            Self::Null(r#type) => ctx.finish_type(r#type, span),
         }
     }
 }
@@ -17,7 +70,25 @@ impl Literal {
 impl std::fmt::Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Integer(literal, _) => write!(f, "{}", literal),
+            Self::Integer(literal, r#type) => write!(f, "{}{}", literal, Suffix(r#type)),
+            Self::Float(literal, r#type) => write!(f, "{:?}{}", literal, Suffix(r#type)),
+            Self::Bool(literal) => write!(f, "{}", literal),
+            Self::Null(crate::Type::Wildcard) => write!(f, "null"),
+            Self::Null(r#type) => write!(f, "null as {}", r#type),
+        }
+    }
+}
+
+/// Type suffix of a number literal, like in `42i32`. Untyped literals have none
+struct Suffix<'a>(&'a crate::Type);
+
+impl std::fmt::Display for Suffix<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            crate::Type::Integer(_) | crate::Type::Unsigned(_) | crate::Type::Float(_) => {
+                write!(f, "{}", self.0)
+            }
+            _ => Ok(()),
         }
     }
 }
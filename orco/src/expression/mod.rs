@@ -6,30 +6,130 @@ pub mod function;
 pub use function::Function;
 /// See [Call]
 pub mod call;
-pub use call::Call;
+pub use call::{Call, TraitCall};
+/// See [FieldAccess]
+pub mod field;
+pub use field::FieldAccess;
+/// See [Index]
+pub mod index;
+pub use index::Index;
+/// See [Comptime]
+pub mod comptime;
+pub use comptime::Comptime;
 
 /// Expressions in orco are all the actual code. Statements are expressions
+#[derive(crate::Visit)]
 pub enum Expression {
     /// See [Literal]
-    Literal(Literal),
-    /// See [Function]
-    Variable(crate::ArcLock<crate::Variable>),
+    Literal(crate::Spanned<Literal>),
+    /// See [crate::Variable]
+    Variable(crate::Spanned<crate::ArcLock<crate::Variable>>),
     /// See [Function]
     Function(Function),
     /// See [Call]
     Call(Call),
-    /// Invalid expression
-    Error,
+    /// See [TraitCall]
+    TraitCall(TraitCall),
+    /// See [FieldAccess]
+    Field(FieldAccess),
+    /// See [Index]
+    Index(Index),
+    /// See [Comptime]
+    Comptime(Comptime),
+    /// Invalid expression, points to the diagnostic that produced it
+    Error(crate::diagnostics::DiagnosticId),
 }
 
 impl Expression {
-    // /// Get the type of this expression
-    // pub fn r#type(&self) -> &crate::Type {
-    //     match self {
-    //         Self::Literal(literal) => literal.r#type(),
-    //         Self::Function(function) => function.r#type(),
-    //     }
-    // }
+    /// Location of this expression in the source code
+    pub fn span(&self) -> Option<crate::Span> {
+        match self {
+            Self::Literal(literal) => literal.span,
+            Self::Variable(variable) => variable.span,
+            Self::Function(function) => function.span,
+            Self::Call(call) => call.span,
+            Self::TraitCall(call) => call.span,
+            Self::Field(field) => field.span,
+            Self::Index(index) => index.span,
+            Self::Comptime(comptime) => comptime.span,
+            Self::Error(_) => None,
+        }
+    }
+
+    /// Infer types in this expression, returns the type of the expression
+    pub fn infer_types(&mut self, ctx: &mut crate::TypeInferenceContext) -> crate::Type {
+        match self {
+            Self::Literal(literal) => literal.infer_types(ctx),
+            Self::Variable(variable) => {
+                let mut variable = variable.write().unwrap();
+                ctx.instantiate_wildcards(&mut variable.r#type);
+                variable.r#type.clone()
+            }
+            Self::Function(function) => {
+                function.infer_types(ctx);
+                crate::Type::Fn(function.signature.clone())
+            }
+            Self::Call(call) => call.infer_types(ctx),
+            Self::TraitCall(call) => call.infer_types(ctx),
+            Self::Field(field) => field.infer_types(ctx),
+            Self::Index(index) => index.infer_types(ctx),
+            Self::Comptime(comptime) => comptime.infer_types(ctx),
+            Self::Error(_) => crate::Type::Never,
+        }
+    }
+
+    /// Write solved types back into this expression, see [crate::TypeInferenceContext::infer]
+    pub fn finish_types(&mut self, ctx: &mut crate::TypeInferenceContext) {
+        match self {
+            Self::Literal(literal) => literal.inner.finish_types(ctx, literal.span),
+            Self::Variable(variable) => {
+                ctx.finish_type(&mut variable.write().unwrap().r#type, variable.span)
+            }
+            Self::Function(function) => function.finish_types(ctx),
+            Self::Call(call) => call.finish_types(ctx),
+            Self::TraitCall(call) => *self = call.finish_types(ctx),
+            Self::Field(field) => field.finish_types(ctx),
+            Self::Index(index) => index.finish_types(ctx),
+            Self::Comptime(comptime) => *self = comptime.finish_types(ctx),
+            Self::Error(_) => (),
+        }
+    }
+
+    /// Get the type of this expression, once types are inferred.
+    /// [crate::Type::Wildcard] if it isn't known
+    pub fn r#type(&self) -> crate::Type {
+        match self {
+            Self::Literal(literal) => literal.r#type().clone(),
+            Self::Variable(variable) => variable.read().unwrap().r#type.clone(),
+            Self::Function(function) => crate::Type::Fn(function.signature.clone()),
+            Self::Call(call) => {
+                let function = call.function.read().unwrap();
+                if function.signature.type_parameters.is_empty() {
+                    function.signature.return_type.as_ref().clone()
+                } else {
+                    *function
+                        .signature
+                        .instantiate(&call.type_arguments)
+                        .return_type
+                }
+            }
+            Self::TraitCall(_) => crate::Type::Wildcard,
+            Self::Field(field) => match &field.field {
+                Some((r#struct, index)) => r#struct.read().unwrap().fields[*index].1.clone(),
+                None => crate::Type::Wildcard,
+            },
+            Self::Index(index) => match &index.container {
+                Some(
+                    crate::Type::Array(element, _)
+                    | crate::Type::Slice(element, _)
+                    | crate::Type::Pointer(element, _),
+                ) => element.as_ref().clone(),
+                _ => crate::Type::Wildcard,
+            },
+            Self::Comptime(comptime) => comptime.r#type.clone(),
+            Self::Error(_) => crate::Type::Never,
+        }
+    }
 }
 
 impl std::fmt::Display for Expression {
@@ -39,7 +139,11 @@ impl std::fmt::Display for Expression {
             Self::Variable(variable) => variable.read().unwrap().fmt(f),
             Self::Function(function) => function.fmt(f),
             Self::Call(call) => call.fmt(f),
-            Self::Error => write!(f, "<ERROR>"),
+            Self::TraitCall(call) => call.fmt(f),
+            Self::Field(field) => field.fmt(f),
+            Self::Index(index) => index.fmt(f),
+            Self::Comptime(comptime) => comptime.fmt(f),
+            Self::Error(_) => write!(f, "<ERROR>"),
         }
     }
 }
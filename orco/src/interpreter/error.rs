@@ -0,0 +1,59 @@
+/// Errors that can occur while interpreting a program
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InterpreterError {
+    /// Element index is past the end of an array or a slice
+    OutOfBounds {
+        /// Index of the element
+        index: u64,
+        /// Number of elements
+        len: u64,
+    },
+    /// Null pointer was dereferenced
+    NullPointer,
+    /// Integer division or remainder by zero
+    DivisionByZero,
+    /// Float is NaN or out of range of the integer type it is converted to
+    InvalidConversion,
+    /// Too many nested calls
+    StackOverflow,
+    /// Evaluation took more steps than allowed, see [super::Interpreter::max_steps]
+    StepLimit,
+    /// Value of a variable is only known at runtime
+    NotConstant(String),
+    /// Symbol, that is called by name, doesn't exist or is not a function
+    UnknownSymbol(String),
+    /// Expression or type, that can't be evaluated, f.e. an [crate::Expression::Error]
+    Unsupported(String),
+}
+
+impl std::fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfBounds { index, len } => write!(
+                f,
+                "index out of bounds: the len is {} but the index is {}",
+                len, index
+            ),
+            Self::NullPointer => write!(f, "null pointer dereference"),
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::InvalidConversion => {
+                write!(f, "float is out of range of the integer type")
+            }
+            Self::StackOverflow => write!(f, "stack overflow"),
+            Self::StepLimit => write!(f, "evaluation took too many steps"),
+            Self::NotConstant(name) => {
+                write!(f, "value of `{}` is not known at compile time", name)
+            }
+            Self::UnknownSymbol(name) => write!(f, "no function named `{}`", name),
+            Self::Unsupported(what) => write!(f, "can't evaluate {}", what),
+        }
+    }
+}
+
+impl std::error::Error for InterpreterError {}
+
+impl From<InterpreterError> for crate::Diagnostic {
+    fn from(error: InterpreterError) -> Self {
+        crate::Diagnostic::error("E0112", error)
+    }
+}
@@ -0,0 +1,366 @@
+use super::value::{wrap_signed, wrap_unsigned};
+use super::{Eval, Flow, Interpreter, InterpreterError, Pointer, Value};
+use crate::type_inference::intrinsics::{Cast, Intrinsic};
+use crate::Type;
+
+impl Interpreter {
+    /// Evaluate a call to an intrinsic. The signature has type parameters substituted
+    pub(super) fn intrinsic(
+        &mut self,
+        intrinsic: Intrinsic,
+        signature: &crate::types::FunctionSignature,
+        args: &[crate::Expression],
+    ) -> Eval<Value> {
+        match intrinsic {
+            Intrinsic::Return => {
+                let value = match args.first() {
+                    Some(arg) => self.eval(arg)?,
+                    None => Value::Unit,
+                };
+                Err(Flow::Return(value))
+            }
+            Intrinsic::Branch => {
+                if !self.eval(&args[0])?.is_zero() {
+                    self.transparent(&args[1])
+                } else {
+                    self.transparent(&args[2])
+                }
+            }
+            Intrinsic::Loop => loop {
+                for body in &args[..2] {
+                    match self.transparent(body) {
+                        Ok(_) | Err(Flow::Continue(0)) => (),
+                        Err(Flow::Break(0, value)) => return Ok(value),
+                        Err(Flow::Break(depth, value)) => {
+                            return Err(Flow::Break(depth - 1, value))
+                        }
+                        Err(Flow::Continue(depth)) => return Err(Flow::Continue(depth - 1)),
+                        Err(flow) => return Err(flow),
+                    }
+                }
+            },
+            Intrinsic::Break(depth) => {
+                let value = match args.first() {
+                    Some(arg) => self.eval(arg)?,
+                    None => Value::Unit,
+                };
+                Err(Flow::Break(depth, value))
+            }
+            Intrinsic::Continue(depth) => Err(Flow::Continue(depth)),
+            Intrinsic::Assign => {
+                let value = self.eval(&args[1])?;
+                self.place(&args[0])?.write(value.clone())?;
+                Ok(value)
+            }
+            Intrinsic::Construct => Ok(Value::Struct(self.eval_all(args)?)),
+            Intrinsic::Array => Ok(Value::Array(self.eval_all(args)?)),
+            Intrinsic::AddressOf => Ok(Value::Pointer(self.place(&args[0])?)),
+            Intrinsic::Load => Ok(pointer(self.eval(&args[0])?)?.read()?),
+            Intrinsic::Store => {
+                let pointer = pointer(self.eval(&args[0])?)?;
+                pointer.write(self.eval(&args[1])?)?;
+                Ok(Value::Unit)
+            }
+            Intrinsic::Offset => {
+                let pointer = pointer(self.eval(&args[0])?)?;
+                let offset = integer(self.eval(&args[1])?)?;
+                Ok(Value::Pointer(pointer.offset(offset as i64)?))
+            }
+            Intrinsic::Slice => {
+                let pointer = pointer(self.eval(&args[0])?)?;
+                let len = integer(self.eval(&args[1])?)?;
+                Ok(Value::Slice(pointer, len as u64))
+            }
+            Intrinsic::Length => match self.eval(&args[0])? {
+                Value::Slice(_, len) => Ok(Value::from_bits(len as u128, &signature.return_type)?),
+                value => Err(unsupported(format!("length of `{}`", value)).into()),
+            },
+            Intrinsic::Cast(kind) | Intrinsic::ImplicitCast(kind) => {
+                let value = self.eval(&args[0])?;
+                Ok(cast(
+                    kind,
+                    value,
+                    &signature.parameters[0].1,
+                    &signature.return_type,
+                )?)
+            }
+            _ => {
+                let values = self.eval_all(args)?;
+                Ok(operator(intrinsic, &values)?)
+            }
+        }
+    }
+
+    fn eval_all(&mut self, args: &[crate::Expression]) -> Eval<Vec<Value>> {
+        args.iter().map(|arg| self.eval(arg)).collect()
+    }
+}
+
+fn unsupported(what: impl ToString) -> InterpreterError {
+    InterpreterError::Unsupported(what.to_string())
+}
+
+/// Pointer from a pointer value
+pub fn pointer(value: Value) -> Result<Pointer, InterpreterError> {
+    match value {
+        Value::Pointer(pointer) => Ok(pointer),
+        value => Err(unsupported(format!("dereferencing `{}`", value))),
+    }
+}
+
+/// Integer from an integer value of any size, unsigned values are reinterpreted
+pub fn integer(value: Value) -> Result<i128, InterpreterError> {
+    match value {
+        Value::Integer(value, _) => Ok(value),
+        Value::Unsigned(value, _) => Ok(value as i128),
+        value => Err(unsupported(format!("`{}` as an integer", value))),
+    }
+}
+
+/// Apply an arithmetic, bitwise or comparison operator
+pub fn operator(intrinsic: Intrinsic, values: &[Value]) -> Result<Value, InterpreterError> {
+    use std::cmp::Ordering;
+    if intrinsic.is_comparison() {
+        let ordering = match (&values[0], &values[1]) {
+            (Value::Integer(a, _), Value::Integer(b, _)) => a.partial_cmp(b),
+            (Value::Unsigned(a, _), Value::Unsigned(b, _)) => a.partial_cmp(b),
+            (Value::Float(a, _), Value::Float(b, _)) => a.partial_cmp(b),
+            (Value::Bool(a), Value::Bool(b)) => a.partial_cmp(b),
+            (Value::Pointer(a), Value::Pointer(b)) => a.partial_cmp(b),
+            (a, b) => (a == b).then_some(Ordering::Equal),
+        };
+        return Ok(Value::Bool(match intrinsic {
+            Intrinsic::Eq => ordering == Some(Ordering::Equal),
+            Intrinsic::Ne => ordering != Some(Ordering::Equal),
+            Intrinsic::Lt => ordering == Some(Ordering::Less),
+            Intrinsic::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Intrinsic::Gt => ordering == Some(Ordering::Greater),
+            _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }));
+    }
+
+    Ok(match (intrinsic, values) {
+        (Intrinsic::Neg, [Value::Integer(value, size)]) => {
+            Value::Integer(wrap_signed(value.wrapping_neg(), *size), *size)
+        }
+        (Intrinsic::Neg, [Value::Float(value, size)]) => Value::Float(-value, *size),
+        (Intrinsic::Not, [Value::Bool(value)]) => Value::Bool(!value),
+        (Intrinsic::Not, [Value::Integer(value, size)]) => Value::Integer(!value, *size),
+        (Intrinsic::Not, [Value::Unsigned(value, size)]) => {
+            Value::Unsigned(wrap_unsigned(!value, *size), *size)
+        }
+        (_, [Value::Integer(a, size), Value::Integer(b, _)]) => {
+            Value::Integer(wrap_signed(signed(intrinsic, *a, *b, *size)?, *size), *size)
+        }
+        (_, [Value::Unsigned(a, size), Value::Unsigned(b, _)]) => Value::Unsigned(
+            wrap_unsigned(unsigned(intrinsic, *a, *b, *size)?, *size),
+            *size,
+        ),
+        (_, [Value::Float(a, size), Value::Float(b, _)]) => Value::float(
+            match intrinsic {
+                Intrinsic::Add => a + b,
+                Intrinsic::Sub => a - b,
+                Intrinsic::Mul => a * b,
+                Intrinsic::Div => a / b,
+                _ => return Err(unsupported(format!("`{}` on floats", intrinsic))),
+            },
+            *size,
+        ),
+        (_, [Value::Bool(a), Value::Bool(b)]) => Value::Bool(match intrinsic {
+            Intrinsic::And => a & b,
+            Intrinsic::Or => a | b,
+            Intrinsic::Xor => a ^ b,
+            _ => return Err(unsupported(format!("`{}` on bools", intrinsic))),
+        }),
+        _ => return Err(unsupported(format!("`{}` on these operands", intrinsic))),
+    })
+}
+
+/// Shift amounts are taken modulo the size, like in the backend
+fn shift(amount: u128, size: u16) -> u32 {
+    (amount % size.max(1) as u128) as u32
+}
+
+fn signed(intrinsic: Intrinsic, a: i128, b: i128, size: u16) -> Result<i128, InterpreterError> {
+    Ok(match intrinsic {
+        Intrinsic::Add => a.wrapping_add(b),
+        Intrinsic::Sub => a.wrapping_sub(b),
+        Intrinsic::Mul => a.wrapping_mul(b),
+        Intrinsic::Div | Intrinsic::Rem if b == 0 => return Err(InterpreterError::DivisionByZero),
+        Intrinsic::Div => a.wrapping_div(b),
+        Intrinsic::Rem => a.wrapping_rem(b),
+        Intrinsic::And => a & b,
+        Intrinsic::Or => a | b,
+        Intrinsic::Xor => a ^ b,
+        Intrinsic::Shl => a.wrapping_shl(shift(b as u128, size)),
+        Intrinsic::Shr => a.wrapping_shr(shift(b as u128, size)),
+        _ => return Err(unsupported(format!("`{}` on integers", intrinsic))),
+    })
+}
+
+fn unsigned(intrinsic: Intrinsic, a: u128, b: u128, size: u16) -> Result<u128, InterpreterError> {
+    Ok(match intrinsic {
+        Intrinsic::Add => a.wrapping_add(b),
+        Intrinsic::Sub => a.wrapping_sub(b),
+        Intrinsic::Mul => a.wrapping_mul(b),
+        Intrinsic::Div | Intrinsic::Rem if b == 0 => return Err(InterpreterError::DivisionByZero),
+        Intrinsic::Div => a / b,
+        Intrinsic::Rem => a % b,
+        Intrinsic::And => a & b,
+        Intrinsic::Or => a | b,
+        Intrinsic::Xor => a ^ b,
+        Intrinsic::Shl => a.wrapping_shl(shift(b, size)),
+        Intrinsic::Shr => a.wrapping_shr(shift(b, size)),
+        _ => return Err(unsupported(format!("`{}` on integers", intrinsic))),
+    })
+}
+
+/// Convert a value from one type to another, see [Cast]
+pub fn cast(cast: Cast, value: Value, from: &Type, to: &Type) -> Result<Value, InterpreterError> {
+    let cast = match cast {
+        Cast::Convert => Cast::between(from, to).unwrap_or(Cast::Bitcast),
+        cast => cast,
+    };
+    let float_size = || match to {
+        Type::Float(size) => Ok(*size),
+        _ => Err(unsupported(format!("a conversion to `{}`", to))),
+    };
+    match (cast, value) {
+        (Cast::ToBool, value) => Ok(Value::Bool(!value.is_zero())),
+        (
+            Cast::Bitcast,
+            value @ (Value::Pointer(_) | Value::Slice(..) | Value::Function(_) | Value::Bool(_)),
+        ) => Ok(value),
+        (Cast::IntToFloat, Value::Integer(value, _)) => {
+            Ok(Value::float(value as f64, float_size()?))
+        }
+        (Cast::IntToFloat, Value::Unsigned(value, _)) => {
+            Ok(Value::float(value as f64, float_size()?))
+        }
+        (Cast::FloatResize, Value::Float(value, _)) => Ok(Value::float(value, float_size()?)),
+        (Cast::FloatToInt | Cast::FloatToIntSaturating, Value::Float(value, _)) => {
+            float_to_int(value, to, cast == Cast::FloatToIntSaturating)
+        }
+        (Cast::ZeroExtend, Value::Integer(value, size)) => {
+            Value::from_bits(wrap_unsigned(value as u128, size), to)
+        }
+        (Cast::SignExtend, Value::Unsigned(value, size)) => {
+            Value::from_bits(wrap_signed(value as i128, size) as u128, to)
+        }
+        (_, value) => match value.bits() {
+            Some(bits) => Value::from_bits(bits, to),
+            None => Err(unsupported(format!(
+                "a conversion of `{}` to `{}`",
+                value, to
+            ))),
+        },
+    }
+}
+
+/// Truncate a float towards zero. Out of range values and NaN
+/// either saturate or fail, like in the backend
+fn float_to_int(value: f64, to: &Type, saturating: bool) -> Result<Value, InterpreterError> {
+    let (min, max, signed) = match to {
+        Type::Integer(size) => (
+            -(2f64.powi(*size as i32 - 1)),
+            2f64.powi(*size as i32 - 1),
+            true,
+        ),
+        Type::Unsigned(size) => (0.0, 2f64.powi(*size as i32), false),
+        _ => return Err(unsupported(format!("a conversion to `{}`", to))),
+    };
+    let value = value.trunc();
+    if value.is_nan() || value < min || value >= max {
+        if !saturating {
+            return Err(InterpreterError::InvalidConversion);
+        }
+        if value.is_nan() {
+            return Value::from_bits(0, to);
+        }
+    }
+    let value = value.clamp(min, max);
+    match (signed, value >= max) {
+        (true, true) => Value::from_bits(i128::MAX as u128 >> (128 - to_size(to)), to),
+        (false, true) => Value::from_bits(u128::MAX, to),
+        (true, false) => Value::from_bits(value as i128 as u128, to),
+        (false, false) => Value::from_bits(value as u128, to),
+    }
+}
+
+fn to_size(r#type: &Type) -> u32 {
+    match r#type {
+        Type::Integer(size) | Type::Unsigned(size) => *size as u32,
+        _ => 128,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    #[test]
+    fn test_operators_and_casts() {
+        let byte = |value| Value::Integer(value, 8);
+        check!(operator(Intrinsic::Add, &[byte(127), byte(1)]) == Ok(byte(-128)));
+        check!(operator(Intrinsic::Lt, &[byte(-1), byte(1)]) == Ok(Value::Bool(true)));
+        check!(
+            operator(Intrinsic::Div, &[byte(1), byte(0)]) == Err(InterpreterError::DivisionByZero)
+        );
+        check!(
+            operator(
+                Intrinsic::Shr,
+                &[Value::Unsigned(128, 8), Value::Unsigned(9, 8)]
+            ) == Ok(Value::Unsigned(64, 8))
+        );
+
+        check!(
+            cast(
+                Cast::Convert,
+                byte(-1),
+                &Type::Integer(8),
+                &Type::Unsigned(32)
+            ) == Ok(Value::Unsigned(u32::MAX as u128, 32))
+        );
+        check!(
+            cast(
+                Cast::ZeroExtend,
+                byte(-1),
+                &Type::Integer(8),
+                &Type::Integer(32)
+            ) == Ok(Value::Integer(255, 32))
+        );
+        check!(
+            cast(
+                Cast::Truncate,
+                Value::Integer(300, 32),
+                &Type::Integer(32),
+                &Type::Unsigned(8)
+            ) == Ok(Value::Unsigned(44, 8))
+        );
+        check!(
+            cast(
+                Cast::FloatToIntSaturating,
+                Value::Float(1e10, 64),
+                &Type::Float(64),
+                &Type::Integer(8)
+            ) == Ok(byte(127))
+        );
+        check!(
+            cast(
+                Cast::FloatToInt,
+                Value::Float(f64::NAN, 64),
+                &Type::Float(64),
+                &Type::Integer(8)
+            ) == Err(InterpreterError::InvalidConversion)
+        );
+        check!(
+            cast(
+                Cast::IntToFloat,
+                byte(-3),
+                &Type::Integer(8),
+                &Type::Float(32)
+            ) == Ok(Value::Float(-3.0, 32))
+        );
+    }
+}
@@ -0,0 +1,555 @@
+/// Values and memory, see [Value] and [Pointer]
+pub mod value;
+pub use value::{Pointer, Value};
+
+/// Runtime errors
+pub mod error;
+pub use error::InterpreterError;
+
+/// Evaluation of intrinsics, operators and casts
+pub mod intrinsics;
+
+use crate::expression::function::FunctionBody;
+use crate::Expression;
+
+/// Control flow, that interrupts the evaluation of expressions
+pub(crate) enum Flow {
+    /// Return from the innermost non-transparent function
+    Return(Value),
+    /// Break out of a loop, `depth` levels up
+    Break(usize, Value),
+    /// Continue a loop, `depth` levels up
+    Continue(usize),
+    /// Evaluation failed
+    Error(InterpreterError),
+}
+
+impl From<InterpreterError> for Flow {
+    fn from(error: InterpreterError) -> Self {
+        Self::Error(error)
+    }
+}
+
+type Eval<T> = Result<T, Flow>;
+
+/// Variables and type arguments of a function call
+#[derive(Default)]
+struct Frame {
+    variables: std::collections::HashMap<*const std::sync::RwLock<crate::Variable>, value::Cell>,
+    type_arguments: std::collections::HashMap<String, crate::Type>,
+}
+
+/// Tree-walking interpreter, runs the IR directly.
+/// Types have to be inferred before running it
+pub struct Interpreter {
+    frames: Vec<Frame>,
+    /// Maximum number of nested calls
+    pub max_depth: usize,
+    /// Maximum number of evaluated expressions, [None] for no limit
+    pub max_steps: Option<u64>,
+    /// Number of expressions evaluated so far
+    pub steps: u64,
+    /// Reject variables, that were not assigned by the evaluated code itself.
+    /// Used for compile-time evaluation, see [crate::comptime]
+    pub comptime: bool,
+}
+
+impl Interpreter {
+    /// Create a new interpreter
+    pub fn new() -> Self {
+        Self {
+            frames: vec![Frame::default()],
+            max_depth: 256,
+            max_steps: None,
+            steps: 0,
+            comptime: false,
+        }
+    }
+
+    /// Create an interpreter for compile-time evaluation.
+    /// Runtime variables are rejected and the number of steps is limited
+    pub fn comptime() -> Self {
+        Self {
+            max_steps: Some(1_000_000),
+            comptime: true,
+            ..Self::new()
+        }
+    }
+
+    /// Call a function of a unit by name
+    pub fn run(
+        &mut self,
+        symbols: &crate::Symbols,
+        name: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, InterpreterError> {
+        let Some(crate::Symbol::Function(function)) = symbols.get(name) else {
+            return Err(InterpreterError::UnknownSymbol(name.to_owned()));
+        };
+        self.call(&function.read().unwrap(), args, &[])
+    }
+
+    /// Call a function with evaluated arguments. Generic functions need type arguments
+    pub fn call(
+        &mut self,
+        function: &crate::expression::Function,
+        args: Vec<Value>,
+        type_arguments: &[crate::Type],
+    ) -> Result<Value, InterpreterError> {
+        self.call_function(function, args, type_arguments)
+            .map_err(unexpected_flow)
+    }
+
+    /// Evaluate an expression outside of any function
+    pub fn evaluate(&mut self, expression: &Expression) -> Result<Value, InterpreterError> {
+        self.eval(expression).map_err(unexpected_flow)
+    }
+
+    /// Evaluate a list of expressions outside of any function, returns the value of the last one
+    pub fn evaluate_block(&mut self, body: &[Expression]) -> Result<Value, InterpreterError> {
+        self.body(body).map_err(unexpected_flow)
+    }
+
+    fn call_function(
+        &mut self,
+        function: &crate::expression::Function,
+        args: Vec<Value>,
+        type_arguments: &[crate::Type],
+    ) -> Eval<Value> {
+        let FunctionBody::Block(body) = &function.body else {
+            return Err(
+                InterpreterError::Unsupported("an intrinsic without a call".to_owned()).into(),
+            );
+        };
+        if self.frames.len() > self.max_depth {
+            return Err(InterpreterError::StackOverflow.into());
+        }
+        self.frames.push(Frame {
+            type_arguments: function.signature.type_arguments(type_arguments),
+            ..Frame::default()
+        });
+        let result = self
+            .bind_parameters(function, args)
+            .and_then(|()| self.body(body));
+        let return_type = self.substitute(&function.signature.return_type);
+        self.frames.pop();
+        match result {
+            // Falling off the end of a function returns zero, like in the backend
+            Ok(_) => Ok(Value::zero(&return_type)?),
+            Err(Flow::Return(value)) => Ok(value),
+            Err(flow) => Err(flow),
+        }
+    }
+
+    fn bind_parameters(
+        &mut self,
+        function: &crate::expression::Function,
+        args: Vec<Value>,
+    ) -> Eval<()> {
+        let frame = self.frames.last_mut().unwrap();
+        for (parameter, value) in function.parameters.iter().zip(args) {
+            frame.variables.insert(
+                std::sync::Arc::as_ptr(parameter),
+                std::rc::Rc::new(std::cell::RefCell::new(value)),
+            );
+        }
+        Ok(())
+    }
+
+    /// Evaluate a list of expressions, returns the value of the last one
+    fn body(&mut self, body: &[Expression]) -> Eval<Value> {
+        let mut value = Value::Unit;
+        for expression in body {
+            value = self.eval(expression)?;
+        }
+        Ok(value)
+    }
+
+    /// Evaluate the body of a transparent function in place
+    fn transparent(&mut self, expression: &Expression) -> Eval<Value> {
+        match expression {
+            Expression::Function(function) => match &function.body {
+                FunctionBody::Block(body) => self.body(body),
+                FunctionBody::Intrinsic(_) => Err(InterpreterError::Unsupported(
+                    "an intrinsic without a call".to_owned(),
+                )
+                .into()),
+            },
+            expression => self.eval(expression),
+        }
+    }
+
+    fn eval(&mut self, expression: &Expression) -> Eval<Value> {
+        self.steps += 1;
+        if self
+            .max_steps
+            .is_some_and(|max_steps| self.steps > max_steps)
+        {
+            return Err(InterpreterError::StepLimit.into());
+        }
+        match expression {
+            Expression::Literal(literal) => Ok(self.literal(literal)?),
+            Expression::Variable(variable) => {
+                // Variables of the evaluated code are assigned before they are read,
+                // anything else comes from the surrounding runtime code
+                let key = std::sync::Arc::as_ptr(variable);
+                if self.comptime
+                    && self.frames.len() == 1
+                    && !self.frames[0].variables.contains_key(&key)
+                {
+                    let name = variable.read().unwrap().name.clone();
+                    return Err(InterpreterError::NotConstant(
+                        name.unwrap_or_else(|| "unnamed variable".to_owned()),
+                    )
+                    .into());
+                }
+                let value = self.variable(variable)?.borrow().clone();
+                Ok(value)
+            }
+            Expression::Function(_) => {
+                Err(InterpreterError::Unsupported("a nested function".to_owned()).into())
+            }
+            Expression::Call(call) => {
+                let function = call.function.read().unwrap();
+                match &function.body {
+                    FunctionBody::Intrinsic(intrinsic) => {
+                        let signature = self.substitute_signature(&function.signature);
+                        self.intrinsic(*intrinsic, &signature, &call.args)
+                    }
+                    FunctionBody::Block(body) => {
+                        let args = call
+                            .args
+                            .iter()
+                            .map(|arg| self.eval(arg))
+                            .collect::<Eval<Vec<_>>>()?;
+                        if function.signature.calling_convention
+                            == crate::types::CallingConvention::Transparent
+                        {
+                            self.bind_parameters(&function, args)?;
+                            return self.body(body);
+                        }
+                        // Type arguments might refer to our own type parameters
+                        let type_arguments = call
+                            .type_arguments
+                            .iter()
+                            .map(|r#type| self.substitute(r#type))
+                            .collect::<Vec<_>>();
+                        self.call_function(&function, args, &type_arguments)
+                    }
+                }
+            }
+            Expression::TraitCall(_) => {
+                Err(InterpreterError::Unsupported("an unresolved trait call".to_owned()).into())
+            }
+            Expression::Field(field) => {
+                let Some((_, index)) = &field.field else {
+                    return Err(InterpreterError::Unsupported(
+                        "an unresolved field access".to_owned(),
+                    )
+                    .into());
+                };
+                match self.eval(&field.value)? {
+                    Value::Struct(mut fields) => Ok(fields.swap_remove(*index)),
+                    value => Err(InterpreterError::Unsupported(format!(
+                        "a field of a non-struct value `{}`",
+                        value
+                    ))
+                    .into()),
+                }
+            }
+            Expression::Index(_) => Ok(self.place(expression)?.read()?),
+            Expression::Comptime(comptime) => self.body(&comptime.body),
+            Expression::Error(_) => {
+                Err(InterpreterError::Unsupported("an error expression".to_owned()).into())
+            }
+        }
+    }
+
+    fn literal(&self, literal: &crate::expression::Literal) -> Result<Value, InterpreterError> {
+        use crate::expression::Literal;
+        match literal {
+            Literal::Integer(value, r#type) => Value::from_bits(*value, &self.substitute(r#type)),
+            Literal::Float(value, r#type) => match self.substitute(r#type) {
+                crate::Type::Float(size) => Ok(Value::float(*value, size)),
+                r#type => Err(InterpreterError::Unsupported(format!(
+                    "a float literal of type `{}`",
+                    r#type
+                ))),
+            },
+            Literal::Bool(value) => Ok(Value::Bool(*value)),
+            Literal::Null(r#type) => Value::zero(&self.substitute(r#type)),
+        }
+    }
+
+    /// Memory cell of a variable, declared on first use
+    fn variable(
+        &mut self,
+        variable: &crate::ArcLock<crate::Variable>,
+    ) -> Result<value::Cell, InterpreterError> {
+        let key = std::sync::Arc::as_ptr(variable);
+        if let Some(cell) = self.frames.last().unwrap().variables.get(&key) {
+            return Ok(cell.clone());
+        }
+        let r#type = self.substitute(&variable.read().unwrap().r#type);
+        let cell = std::rc::Rc::new(std::cell::RefCell::new(Value::zero(&r#type)?));
+        self.frames
+            .last_mut()
+            .unwrap()
+            .variables
+            .insert(key, cell.clone());
+        Ok(cell)
+    }
+
+    /// Pointer to a place: a variable, a field, an element or a loaded value.
+    /// Other expressions are evaluated into a temporary
+    fn place(&mut self, expression: &Expression) -> Eval<Pointer> {
+        use crate::type_inference::intrinsics::Intrinsic;
+        match expression {
+            Expression::Variable(variable) => Ok(Pointer::new(self.variable(variable)?)),
+            Expression::Field(field) => match &field.field {
+                Some((_, index)) => Ok(self.place(&field.value)?.child(*index as u64)),
+                None => Err(
+                    InterpreterError::Unsupported("an unresolved field access".to_owned()).into(),
+                ),
+            },
+            Expression::Index(index) => {
+                let container = match &index.container {
+                    Some(container) => self.substitute(container),
+                    None => {
+                        return Err(
+                            InterpreterError::Unsupported("an unresolved index".to_owned()).into(),
+                        )
+                    }
+                };
+                let (pointer, len) = match container {
+                    crate::Type::Array(_, len) => (self.place(&index.value)?, Some(len)),
+                    _ => match self.eval(&index.value)? {
+                        Value::Slice(pointer, len) => (pointer, Some(len)),
+                        Value::Pointer(pointer) => (pointer, None),
+                        value => {
+                            return Err(InterpreterError::Unsupported(format!(
+                                "indexing into `{}`",
+                                value
+                            ))
+                            .into())
+                        }
+                    },
+                };
+                let element = intrinsics::integer(self.eval(&index.index)?)?;
+                match (container, len) {
+                    (_, Some(len)) if index.checked && element as u64 >= len => {
+                        Err(InterpreterError::OutOfBounds {
+                            index: element as u64,
+                            len,
+                        }
+                        .into())
+                    }
+                    (crate::Type::Array(..), _) => Ok(pointer.child(element as u64)),
+                    _ => Ok(pointer.offset(element as i64)?),
+                }
+            }
+            Expression::Call(call)
+                if matches!(
+                    call.function.read().unwrap().body,
+                    FunctionBody::Intrinsic(Intrinsic::Load)
+                ) =>
+            {
+                Ok(intrinsics::pointer(self.eval(&call.args[0])?)?)
+            }
+            expression => Ok(Pointer::allocate(self.eval(expression)?)),
+        }
+    }
+
+    /// Replace type parameters with the type arguments of the current call
+    fn substitute(&self, r#type: &crate::Type) -> crate::Type {
+        let type_arguments = &self.frames.last().unwrap().type_arguments;
+        if type_arguments.is_empty() {
+            return r#type.clone();
+        }
+        r#type.substitute(type_arguments)
+    }
+
+    fn substitute_signature(
+        &self,
+        signature: &crate::types::FunctionSignature,
+    ) -> crate::types::FunctionSignature {
+        signature.substitute(&self.frames.last().unwrap().type_arguments)
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `break`, `continue` and `return` can't escape the evaluated code
+fn unexpected_flow(flow: Flow) -> InterpreterError {
+    match flow {
+        Flow::Return(_) => {
+            InterpreterError::Unsupported("a return outside of a function".to_owned())
+        }
+        Flow::Break(..) | Flow::Continue(_) => {
+            InterpreterError::Unsupported("a break or a continue outside of a loop".to_owned())
+        }
+        Flow::Error(error) => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{Call, Function, Literal};
+    use crate::type_inference::intrinsics::{Intrinsic, Intrinsics};
+    use crate::Type;
+    use assert2::*;
+
+    fn arc<T>(value: T) -> crate::ArcLock<T> {
+        std::sync::Arc::new(std::sync::RwLock::new(value))
+    }
+
+    fn call(function: crate::ArcLock<Function>, args: Vec<Expression>) -> Expression {
+        Expression::Call(Call::new(function, args))
+    }
+
+    fn integer(value: u128) -> Expression {
+        Expression::Literal(crate::Spanned::new(
+            Literal::Integer(value, Type::Integer(32)),
+            None,
+        ))
+    }
+
+    fn variable(variable: &crate::ArcLock<crate::Variable>) -> Expression {
+        Expression::Variable(crate::Spanned::new(variable.clone(), None))
+    }
+
+    fn transparent(body: Vec<Expression>) -> Expression {
+        Expression::Function(Function::new(
+            crate::function_signature![() -> {Type::Integer(32)} transparent],
+            None,
+            body,
+        ))
+    }
+
+    #[test]
+    fn test_recursion() {
+        let intrinsics = Intrinsics::new();
+        let r#return = arc(Function::intrinsic(
+            crate::function_signature![(value: {Type::Integer(32)}) -> ! transparent],
+            Intrinsic::Return,
+        ));
+        let n = arc(crate::Variable::new(
+            Some("n".to_owned()),
+            Type::Integer(32),
+        ));
+        let factorial = arc(Function::new(
+            crate::function_signature![(n: {Type::Integer(32)}) -> {Type::Integer(32)}],
+            Some("factorial".to_owned()),
+            Vec::new(),
+        ));
+        // return if n <= 1 { 1 } else { n * factorial(n - 1) }
+        let branch = call(
+            intrinsics.branch(Type::Integer(32)),
+            vec![
+                call(
+                    intrinsics.binary(Intrinsic::Le, Type::Integer(32)),
+                    vec![variable(&n), integer(1)],
+                ),
+                transparent(vec![integer(1)]),
+                transparent(vec![call(
+                    intrinsics.binary(Intrinsic::Mul, Type::Integer(32)),
+                    vec![
+                        variable(&n),
+                        call(
+                            factorial.clone(),
+                            vec![call(
+                                intrinsics.binary(Intrinsic::Sub, Type::Integer(32)),
+                                vec![variable(&n), integer(1)],
+                            )],
+                        ),
+                    ],
+                )]),
+            ],
+        );
+        {
+            let mut function = factorial.write().unwrap();
+            function.parameters.push(n.clone());
+            function.body = FunctionBody::Block(vec![call(r#return, vec![branch])]);
+        }
+
+        let mut interpreter = Interpreter::new();
+        let function = factorial.read().unwrap();
+        let result = interpreter.call(&function, vec![Value::Integer(5, 32)], &[]);
+        check!(result == Ok(Value::Integer(120, 32)));
+
+        interpreter.max_depth = 3;
+        let result = interpreter.call(&function, vec![Value::Integer(5, 32)], &[]);
+        check!(result == Err(InterpreterError::StackOverflow));
+    }
+
+    #[test]
+    fn test_memory() {
+        let intrinsics = Intrinsics::new();
+        let array = Type::Array(Box::new(Type::Integer(32)), 3);
+        let a = arc(crate::Variable::new(Some("a".to_owned()), array.clone()));
+        let index = |value| {
+            Expression::Index(crate::expression::Index {
+                container: Some(array.clone()),
+                index_type: Some(Type::Integer(32)),
+                checked: true,
+                ..crate::expression::Index::new(variable(&a), integer(value))
+            })
+        };
+        let pointer = Type::Pointer(Box::new(Type::Integer(32)), crate::types::Mutability::Mut);
+
+        let mut interpreter = Interpreter::new();
+        // a = [1, 2, 3]; *(&a[0] + 2) = 10; a[2]
+        let body = [
+            call(
+                intrinsics.assign(array.clone()),
+                vec![
+                    variable(&a),
+                    call(
+                        intrinsics.array(Type::Integer(32), 3),
+                        vec![integer(1), integer(2), integer(3)],
+                    ),
+                ],
+            ),
+            call(
+                intrinsics.store(Type::Integer(32)),
+                vec![
+                    call(
+                        intrinsics.offset(
+                            Type::Integer(32),
+                            crate::types::Mutability::Mut,
+                            Type::Integer(32),
+                        ),
+                        vec![
+                            call(intrinsics.address_of(Type::Integer(32)), vec![index(0)]),
+                            integer(2),
+                        ],
+                    ),
+                    integer(10),
+                ],
+            ),
+            index(2),
+        ];
+        let values = body
+            .iter()
+            .map(|expression| interpreter.evaluate(expression))
+            .collect::<Vec<_>>();
+        check!(values[2] == Ok(Value::Integer(10, 32)));
+        check!(interpreter.evaluate(&variable(&a)).is_ok_and(|value| value
+            == Value::Array(vec![
+                Value::Integer(1, 32),
+                Value::Integer(2, 32),
+                Value::Integer(10, 32)
+            ])));
+
+        let result = interpreter.evaluate(&index(3));
+        check!(result == Err(InterpreterError::OutOfBounds { index: 3, len: 3 }));
+        let null = Expression::Literal(crate::Spanned::new(Literal::Null(pointer), None));
+        let result = interpreter.evaluate(&call(intrinsics.load(Type::Integer(32)), vec![null]));
+        check!(result == Err(InterpreterError::NullPointer));
+    }
+}
@@ -0,0 +1,336 @@
+use super::InterpreterError;
+use crate::{debug_display, Type};
+
+/// Shared, mutable memory cell. Every variable and temporary lives in one
+pub type Cell = std::rc::Rc<std::cell::RefCell<Value>>;
+
+/// Value of any [Type]. Numbers carry their size in bits
+/// and are always kept wrapped to it
+#[derive(Clone)]
+pub enum Value {
+    /// Value of `()`, also produced by expressions of type `!`
+    Unit,
+    /// Boolean
+    Bool(bool),
+    /// Signed integer, sign-extended to 128 bits
+    Integer(i128, u16),
+    /// Unsigned integer
+    Unsigned(u128, u16),
+    /// Float, `f32` values are rounded to single precision
+    Float(f64, u16),
+    /// Pointer to a value, see [Pointer]
+    Pointer(Pointer),
+    /// Pointer to the first element and the number of elements
+    Slice(Pointer, u64),
+    /// Elements of an array
+    Array(Vec<Value>),
+    /// Values of the fields of a struct
+    Struct(Vec<Value>),
+    /// Function pointer, [None] if null
+    Function(Option<crate::ArcLock<crate::expression::Function>>),
+}
+
+impl Value {
+    /// Zero value of a type: `0`, `false`, null or an aggregate of zeros.
+    /// Type parameters have to be substituted first
+    pub fn zero(r#type: &Type) -> Result<Self, InterpreterError> {
+        Ok(match r#type {
+            Type::Never | Type::Unit => Self::Unit,
+            Type::Bool => Self::Bool(false),
+            Type::Integer(bits) => Self::Integer(0, *bits),
+            Type::Unsigned(bits) => Self::Unsigned(0, *bits),
+            Type::Float(bits) => Self::Float(0.0, *bits),
+            Type::Fn(_) => Self::Function(None),
+            Type::Pointer(..) => Self::Pointer(Pointer::null()),
+            Type::Slice(..) => Self::Slice(Pointer::null(), 0),
+            Type::Array(element, len) => Self::Array(vec![Self::zero(element)?; *len as usize]),
+            Type::Struct(r#struct) => Self::Struct(
+                r#struct
+                    .read()
+                    .unwrap()
+                    .fields
+                    .iter()
+                    .map(|(_, r#type)| Self::zero(r#type))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Type::Wildcard | Type::Parameter(_) | Type::Variable(_) | Type::Unresolved(_) => {
+                return Err(InterpreterError::Unsupported(format!(
+                    "a value of type `{}`",
+                    r#type
+                )))
+            }
+        })
+    }
+
+    /// Float of a size in bits, rounded to it
+    pub fn float(value: f64, size: u16) -> Self {
+        match size {
+            32 => Self::Float(value as f32 as f64, size),
+            _ => Self::Float(value, size),
+        }
+    }
+
+    /// Integer or a bool of this type from raw bits, truncated to the size of the type
+    pub fn from_bits(bits: u128, r#type: &Type) -> Result<Self, InterpreterError> {
+        match r#type {
+            Type::Integer(size) => Ok(Self::Integer(wrap_signed(bits as i128, *size), *size)),
+            Type::Unsigned(size) => Ok(Self::Unsigned(wrap_unsigned(bits, *size), *size)),
+            Type::Bool => Ok(Self::Bool(bits != 0)),
+            Type::Float(32) => Ok(Self::Float(f32::from_bits(bits as u32) as f64, 32)),
+            Type::Float(size) => Ok(Self::Float(f64::from_bits(bits as u64), *size)),
+            _ => Err(InterpreterError::Unsupported(format!(
+                "a conversion to `{}`",
+                r#type
+            ))),
+        }
+    }
+
+    /// Raw bits of a scalar. Signed integers are sign-extended
+    pub fn bits(&self) -> Option<u128> {
+        match *self {
+            Self::Bool(value) => Some(value as u128),
+            Self::Integer(value, _) => Some(value as u128),
+            Self::Unsigned(value, _) => Some(value),
+            Self::Float(value, 32) => Some((value as f32).to_bits() as u128),
+            Self::Float(value, _) => Some(value.to_bits() as u128),
+            _ => None,
+        }
+    }
+
+    /// Is this `false`, zero or null?
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Self::Bool(value) => !value,
+            Self::Integer(value, _) => *value == 0,
+            Self::Unsigned(value, _) => *value == 0,
+            Self::Float(value, _) => *value == 0.0,
+            Self::Pointer(pointer) | Self::Slice(pointer, _) => pointer.is_null(),
+            Self::Function(function) => function.is_none(),
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Unit, Self::Unit) => true,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Integer(a, _), Self::Integer(b, _)) => a == b,
+            (Self::Unsigned(a, _), Self::Unsigned(b, _)) => a == b,
+            (Self::Float(a, _), Self::Float(b, _)) => a == b,
+            (Self::Pointer(a), Self::Pointer(b)) => a == b,
+            (Self::Slice(a, a_len), Self::Slice(b, b_len)) => a == b && a_len == b_len,
+            (Self::Array(a), Self::Array(b)) | (Self::Struct(a), Self::Struct(b)) => a == b,
+            (Self::Function(a), Self::Function(b)) => match (a, b) {
+                (Some(a), Some(b)) => std::sync::Arc::ptr_eq(a, b),
+                (a, b) => a.is_none() && b.is_none(),
+            },
+            _ => false,
+        }
+    }
+}
+
+#[debug_display]
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unit => write!(f, "()"),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Integer(value, size) => write!(f, "{}i{}", value, size),
+            Self::Unsigned(value, size) => write!(f, "{}u{}", value, size),
+            Self::Float(value, size) => write!(f, "{}f{}", value, size),
+            Self::Pointer(pointer) => write!(f, "{}", pointer),
+            Self::Slice(pointer, len) => write!(f, "{}[..{}]", pointer, len),
+            Self::Array(elements) => {
+                write!(f, "[")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Self::Struct(fields) => {
+                write!(f, "{{")?;
+                for (index, field) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, " {}", field)?;
+                }
+                write!(f, " }}")
+            }
+            Self::Function(Some(function)) => write!(
+                f,
+                "fn {}",
+                function
+                    .read()
+                    .unwrap()
+                    .name
+                    .as_deref()
+                    .unwrap_or("<unnamed function>")
+            ),
+            Self::Function(None) => write!(f, "null"),
+        }
+    }
+}
+
+/// Pointer to a memory cell, or into a field or an element of the aggregate stored in it
+#[derive(Clone)]
+pub struct Pointer {
+    /// Memory cell, [None] for null pointers
+    pub cell: Option<Cell>,
+    /// Indices of fields and elements, that lead to the pointee
+    pub path: Vec<u64>,
+}
+
+impl Pointer {
+    /// Null pointer
+    pub fn null() -> Self {
+        Self {
+            cell: None,
+            path: Vec::new(),
+        }
+    }
+
+    /// Pointer to a whole cell
+    pub fn new(cell: Cell) -> Self {
+        Self {
+            cell: Some(cell),
+            path: Vec::new(),
+        }
+    }
+
+    /// Allocate a new cell and point to it
+    pub fn allocate(value: Value) -> Self {
+        Self::new(std::rc::Rc::new(std::cell::RefCell::new(value)))
+    }
+
+    /// Is this a null pointer?
+    pub fn is_null(&self) -> bool {
+        self.cell.is_none()
+    }
+
+    /// Pointer to a field or an element of the pointee
+    pub fn child(&self, index: u64) -> Self {
+        let mut pointer = self.clone();
+        pointer.path.push(index);
+        pointer
+    }
+
+    /// Move the pointer by a number of elements. Only
+    /// pointers to elements of an array can be moved
+    pub fn offset(&self, offset: i64) -> Result<Self, InterpreterError> {
+        if offset == 0 {
+            return Ok(self.clone());
+        }
+        let Some((last, parent)) = self.path.split_last() else {
+            return Err(InterpreterError::OutOfBounds { index: 1, len: 1 });
+        };
+        let parent = Self {
+            cell: self.cell.clone(),
+            path: parent.to_vec(),
+        };
+        if let Value::Array(_) = parent.read()? {
+            // Pointers might go out of bounds temporarily,
+            // they are checked once dereferenced
+            Ok(parent.child(last.wrapping_add_signed(offset)))
+        } else {
+            Err(InterpreterError::OutOfBounds { index: 1, len: 1 })
+        }
+    }
+
+    /// Read the pointee
+    pub fn read(&self) -> Result<Value, InterpreterError> {
+        let cell = self.cell.as_ref().ok_or(InterpreterError::NullPointer)?;
+        let value = cell.borrow();
+        let mut value = &*value;
+        for &index in &self.path {
+            value =
+                match value {
+                    Value::Array(values) | Value::Struct(values) => values
+                        .get(index as usize)
+                        .ok_or(InterpreterError::OutOfBounds {
+                            index,
+                            len: values.len() as u64,
+                        })?,
+                    _ => return Err(InterpreterError::OutOfBounds { index, len: 1 }),
+                };
+        }
+        Ok(value.clone())
+    }
+
+    /// Overwrite the pointee
+    pub fn write(&self, new: Value) -> Result<(), InterpreterError> {
+        let cell = self.cell.as_ref().ok_or(InterpreterError::NullPointer)?;
+        let mut value = cell.borrow_mut();
+        let mut value = &mut *value;
+        for &index in &self.path {
+            value = match value {
+                Value::Array(values) | Value::Struct(values) => {
+                    let len = values.len() as u64;
+                    values
+                        .get_mut(index as usize)
+                        .ok_or(InterpreterError::OutOfBounds { index, len })?
+                }
+                _ => return Err(InterpreterError::OutOfBounds { index, len: 1 }),
+            };
+        }
+        *value = new;
+        Ok(())
+    }
+}
+
+impl PartialEq for Pointer {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.cell, &other.cell) {
+            (Some(a), Some(b)) => std::rc::Rc::ptr_eq(a, b) && self.path == other.path,
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+}
+
+impl PartialOrd for Pointer {
+    /// Only pointers into the same cell are ordered
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (&self.cell, &other.cell) {
+            (Some(a), Some(b)) if std::rc::Rc::ptr_eq(a, b) => self.path.partial_cmp(&other.path),
+            (None, None) => Some(std::cmp::Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Pointer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.cell {
+            Some(cell) => {
+                write!(f, "*{:p}", std::rc::Rc::as_ptr(cell))?;
+                for index in &self.path {
+                    write!(f, ".{}", index)?;
+                }
+                Ok(())
+            }
+            None => write!(f, "null"),
+        }
+    }
+}
+
+/// Wrap a signed integer to a size in bits
+pub fn wrap_signed(value: i128, size: u16) -> i128 {
+    if size >= 128 || size == 0 {
+        return value;
+    }
+    let shift = 128 - size as u32;
+    (value << shift) >> shift
+}
+
+/// Wrap an unsigned integer to a size in bits
+pub fn wrap_unsigned(value: u128, size: u16) -> u128 {
+    if size >= 128 || size == 0 {
+        return value;
+    }
+    value & ((1 << size) - 1)
+}
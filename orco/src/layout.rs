@@ -0,0 +1,137 @@
+use crate::Type;
+
+/// Properties of the target machine, that types depend on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Target {
+    /// Size (and alignment) of a pointer in bytes
+    pub pointer_size: u64,
+    /// Sizes of the integers in bits, that the target supports
+    pub integer_sizes: &'static [u16],
+    /// Sizes of the floats in bits, that the target supports
+    pub float_sizes: &'static [u16],
+}
+
+impl Target {
+    /// 64-bit target
+    pub const X86_64: Self = Self {
+        pointer_size: 8,
+        integer_sizes: &[8, 16, 32, 64, 128],
+        float_sizes: &[16, 32, 64, 128],
+    };
+
+    /// Compute the layout of a type on this target.
+    /// Panics if the type is not fully inferred
+    pub fn layout(&self, r#type: &Type) -> Layout {
+        match r#type {
+            Type::Never | Type::Unit => Layout::scalar(0, 1),
+            Type::Bool => Layout::scalar(1, 1),
+            Type::Integer(bits) | Type::Unsigned(bits) | Type::Float(bits) => {
+                let size = (*bits as u64).div_ceil(8).next_power_of_two();
+                Layout::scalar(size, size)
+            }
+            Type::Fn(_) | Type::Pointer(..) => Layout::scalar(self.pointer_size, self.pointer_size),
+            Type::Array(r#type, len) => {
+                let element = self.layout(r#type);
+                Layout::scalar(element.size * len, element.alignment)
+            }
+            // Pointer to the first element and the number of elements
+            Type::Slice(..) => Layout {
+                field_offsets: vec![0, self.pointer_size],
+                ..Layout::scalar(self.pointer_size * 2, self.pointer_size)
+            },
+            Type::Struct(r#struct) => {
+                let r#struct = r#struct.read().unwrap();
+                let mut layout = Layout::scalar(0, 1);
+                for (_, field) in &r#struct.fields {
+                    let field = self.layout(field);
+                    let offset = layout.size.next_multiple_of(field.alignment);
+                    layout.field_offsets.push(offset);
+                    layout.size = offset + field.size;
+                    layout.alignment = layout.alignment.max(field.alignment);
+                }
+                layout.size = layout.size.next_multiple_of(layout.alignment);
+                layout
+            }
+            Type::Wildcard | Type::Variable(_) | Type::Parameter(_) | Type::Unresolved(_) => {
+                panic!("Can't compute the layout of {}, type inference was most likely not done correctly", r#type)
+            }
+        }
+    }
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Self::X86_64
+    }
+}
+
+/// Memory layout of a type
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Layout {
+    /// Size in bytes, always a multiple of the alignment
+    pub size: u64,
+    /// Alignment in bytes
+    pub alignment: u64,
+    /// Offsets of the fields in bytes, empty for types without fields
+    pub field_offsets: Vec<u64>,
+}
+
+impl Layout {
+    /// Layout of a type without fields
+    pub fn scalar(size: u64, alignment: u64) -> Self {
+        Self {
+            size,
+            alignment,
+            field_offsets: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Struct, StructRef};
+    use assert2::*;
+
+    #[test]
+    fn test_struct_layout() {
+        let r#struct = StructRef::new(Struct::new(
+            Some("Foo".to_owned()),
+            vec![
+                ("a".to_owned(), Type::Bool),
+                ("b".to_owned(), Type::Integer(32)),
+                ("c".to_owned(), Type::Unsigned(16)),
+            ],
+        ));
+        let layout = Target::X86_64.layout(&Type::Struct(r#struct.clone()));
+        check!(layout.field_offsets == [0, 4, 8]);
+        check!(layout.size == 12);
+        check!(layout.alignment == 4);
+
+        let outer = Type::Struct(StructRef::new(Struct::new(
+            None,
+            vec![
+                ("foo".to_owned(), Type::Struct(r#struct)),
+                (
+                    "d".to_owned(),
+                    Type::Fn(crate::function_signature![() -> ()]),
+                ),
+            ],
+        )));
+        let layout = Target::X86_64.layout(&outer);
+        check!(layout.field_offsets == [0, 16]);
+        check!(layout.size == 24);
+        check!(layout.alignment == 8);
+    }
+
+    #[test]
+    fn test_array_layout() {
+        let array = Type::Array(Box::new(Type::Integer(16)), 5);
+        check!(Target::X86_64.layout(&array) == Layout::scalar(10, 2));
+
+        let slice = Type::Slice(Box::new(array), crate::types::Mutability::Const);
+        let layout = Target::X86_64.layout(&slice);
+        check!(layout.field_offsets == [0, 8]);
+        check!(layout.size == 16);
+    }
+}
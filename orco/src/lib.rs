@@ -12,15 +12,71 @@ pub use expression::Expression;
 pub mod types;
 pub use types::Type;
 
+/// See [layout::Layout] and [layout::Target]
+pub mod layout;
+
+/// See [Span] and [SourceMap]
+pub mod span;
+pub use span::{SourceMap, Span, Spanned};
+
+/// See [traits::Trait] and [traits::Impl]
+pub mod traits;
+
+/// See [Diagnostic]
+pub mod diagnostics;
+pub use diagnostics::Diagnostic;
+
 /// See [TypeInferenceContext]
 pub mod type_inference;
 pub use type_inference::{TypeInferenceContext, Variable};
 
+/// See [interpreter::Interpreter]
+pub mod interpreter;
+
+/// Compile-time evaluation and constant folding, see [comptime::fold]
+pub mod comptime;
+
+/// Visitors over the IR, see [Visitor] and [VisitorMut]
+pub mod visitor;
+pub use visitor::{Visitor, VisitorMut, Walk};
+
+/// Optimisation passes over the IR, see [passes::PassManager]
+pub mod passes;
+
+/// Well-formedness checks before codegen, see [verify::verify]
+pub mod verify;
+
+/// Textual form of the IR, see [parser::parse_unit]
+pub mod parser;
+
+/// Golden tests of the lowered IR, see [test_symbols] and [test_snapshot]
+pub mod testing;
+pub use testing::{test_snapshot, test_symbols};
+
+/// See [Path]
+pub mod path;
+pub use path::Path;
+
+/// See [Module]
+pub mod module;
+pub use module::Module;
+
+/// Incremental compilation, see [query::Database]
+pub mod query;
+
+/// See [Symbol]
+pub mod symbol;
+pub use symbol::Symbol;
+
 /// Symbol references are one of the key features of OrCo.
 /// They allow symbols to be accessed from anywhere
 pub mod symbol_box;
 pub use symbol_box::{SymbolBox, SymbolRef};
 
+/// Symbol table of a unit, by name. Sorted, so type inference, passes
+/// and backends walk the symbols in the same order on every run
+pub type Symbols = std::collections::BTreeMap<String, Symbol>;
+
 /// `Cow<str>`
 pub type CowStr<'a> = std::borrow::Cow<'a, str>;
 
@@ -0,0 +1,339 @@
+use crate::{Path, Symbol};
+use std::collections::HashMap;
+
+/// Module, a namespace for symbols, nested modules and imports.
+/// The root module is the symbol table of a unit: [crate::parser::parse_unit]
+/// and the C frontend put the items into it and resolve calls through it,
+/// see [crate::TypeInferenceContext::module]. Backends get it flattened, see [Module::flatten]
+#[derive(Default)]
+pub struct Module {
+    /// Symbols, defined in this module
+    pub symbols: crate::Symbols,
+    /// Nested modules
+    pub modules: HashMap<String, Module>,
+    /// Names, brought into this module from other modules, `use a::b as c`
+    pub imports: HashMap<String, Import>,
+}
+
+/// Import of a symbol or a module, see [Module::imports]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Import {
+    /// Imported path, resolved relative to the module, that imports it
+    pub path: Path,
+    /// Public imports are re-exports, other modules can use them too.
+    /// Private ones are only visible in the module and its children
+    pub public: bool,
+    /// Location of the import in the source code
+    pub span: Option<crate::Span>,
+}
+
+impl Import {
+    /// Create a new private import without a location
+    pub fn new(path: Path) -> Self {
+        Self {
+            path,
+            public: false,
+            span: None,
+        }
+    }
+}
+
+impl Module {
+    /// Create an empty module
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Nested module by its path from this module
+    pub fn module(&self, path: &Path) -> Option<&Module> {
+        path.0
+            .iter()
+            .try_fold(self, |module, name| module.modules.get(name))
+    }
+
+    /// Nested module by its path from this module, missing modules are created
+    pub fn module_mut(&mut self, path: &Path) -> &mut Module {
+        path.0.iter().fold(self, |module, name| {
+            module.modules.entry(name.clone()).or_default()
+        })
+    }
+
+    /// Symbol by its path from this module, imports are not followed
+    pub fn get(&self, path: &Path) -> Option<&Symbol> {
+        self.module(&path.parent()?)?.symbols.get(path.name()?)
+    }
+
+    /// Define a symbol, missing modules on the way are created
+    pub fn insert(&mut self, path: &Path, symbol: Symbol) {
+        let (Some(module), Some(name)) = (path.parent(), path.name()) else {
+            panic!("symbol path can't be empty");
+        };
+        self.module_mut(&module)
+            .symbols
+            .insert(name.to_owned(), symbol);
+    }
+
+    /// Resolve a path, used in the `module`, to the path of the symbol or the module
+    /// it refers to. Leading `crate` refers to the root module, `self` to the current one
+    /// and `super` to its parent. Imports are followed
+    pub fn resolve(&self, module: &Path, path: &Path) -> Result<Path, ResolveError> {
+        self.resolve_in(module, path, &mut Vec::new())
+    }
+
+    /// Like [Module::resolve], `imports` are the ones being followed, to detect cycles
+    fn resolve_in(
+        &self,
+        module: &Path,
+        path: &Path,
+        imports: &mut Vec<Path>,
+    ) -> Result<Path, ResolveError> {
+        let mut current = module.clone();
+        let mut segments = path.0.iter().peekable();
+        if segments.next_if(|segment| *segment == "crate").is_some() {
+            current = Path::new();
+        } else {
+            while let Some(segment) =
+                segments.next_if(|segment| *segment == "self" || *segment == "super")
+            {
+                if segment == "super" {
+                    current = current.parent().ok_or(ResolveError::NoParent)?;
+                }
+            }
+        }
+
+        while let Some(name) = segments.next() {
+            let scope = self
+                .module(&current)
+                .ok_or_else(|| ResolveError::NotAModule(current.clone()))?;
+            let found = if scope.symbols.contains_key(name) || scope.modules.contains_key(name) {
+                current.join(name)
+            } else if let Some(import) = scope.imports.get(name) {
+                if !import.public && !module.starts_with(&current) {
+                    return Err(ResolveError::Private {
+                        name: name.clone(),
+                        module: current,
+                    });
+                }
+                let key = current.join(name);
+                if imports.contains(&key) {
+                    return Err(ResolveError::Cycle(key));
+                }
+                imports.push(key);
+                let target = self.resolve_in(&current, &import.path, imports);
+                imports.pop();
+                target?
+            } else {
+                return Err(ResolveError::NotFound {
+                    name: name.clone(),
+                    module: current,
+                });
+            };
+            if segments.peek().is_some() && self.module(&found).is_none() {
+                return Err(ResolveError::NotAModule(found));
+            }
+            current = found;
+        }
+        Ok(current)
+    }
+
+    /// Resolve a path to a symbol, see [Module::resolve]
+    pub fn lookup(&self, module: &Path, path: &Path) -> Result<&Symbol, ResolveError> {
+        let path = self.resolve(module, path)?;
+        self.get(&path).ok_or(ResolveError::NotASymbol(path))
+    }
+
+    /// All symbols of this module and the nested ones, keyed by their paths
+    pub fn symbol_table(&self) -> std::collections::BTreeMap<Path, &Symbol> {
+        let mut table = std::collections::BTreeMap::new();
+        let mut modules = vec![(Path::new(), self)];
+        while let Some((path, module)) = modules.pop() {
+            for (name, symbol) in &module.symbols {
+                table.insert(path.join(name), symbol);
+            }
+            for (name, module) in &module.modules {
+                modules.push((path.join(name), module));
+            }
+        }
+        table
+    }
+
+    /// Turn the module tree into a unit, that backends build.
+    /// Symbols are named by their paths, `a::b::c`, named functions are renamed too
+    pub fn flatten(self) -> crate::Symbols {
+        let mut unit = crate::Symbols::new();
+        let mut modules = vec![(Path::new(), self)];
+        while let Some((path, module)) = modules.pop() {
+            for (name, symbol) in module.symbols {
+                let name = path.join(name).to_string();
+                if let Symbol::Function(function) = &symbol {
+                    let mut function = function.write().unwrap();
+                    if function.name.is_some() {
+                        function.name = Some(name.clone());
+                    }
+                }
+                unit.insert(name, symbol);
+            }
+            for (name, module) in module.modules {
+                modules.push((path.join(name), module));
+            }
+        }
+        unit
+    }
+}
+
+/// Errors of path resolution, see [Module::resolve]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolveError {
+    /// There is nothing with this name in the module
+    NotFound {
+        /// Name, that wasn't found
+        name: String,
+        /// Module, that was searched
+        module: Path,
+    },
+    /// Import is private to another module
+    Private {
+        /// Name of the import
+        name: String,
+        /// Module, that imports it
+        module: Path,
+    },
+    /// Path goes through something, that isn't a module
+    NotAModule(Path),
+    /// Path refers to a module, where a symbol was expected
+    NotASymbol(Path),
+    /// `super` was used in the root module
+    NoParent,
+    /// Imports refer to each other
+    Cycle(Path),
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound { name, module } => {
+                write!(f, "cannot find `{}` in `{}`", name, module)
+            }
+            Self::Private { name, module } => {
+                write!(f, "import `{}` is private to `{}`", name, module)
+            }
+            Self::NotAModule(path) => write!(f, "`{}` is not a module", path),
+            Self::NotASymbol(path) => write!(f, "`{}` is a module, not a symbol", path),
+            Self::NoParent => write!(f, "there are no modules above the root one"),
+            Self::Cycle(path) => write!(f, "import `{}` refers to itself", path),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+impl From<ResolveError> for crate::Diagnostic {
+    fn from(error: ResolveError) -> Self {
+        crate::Diagnostic::error("E0123", error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    fn path(path: &str) -> Path {
+        path.parse().unwrap()
+    }
+
+    fn symbol(value: u128) -> Symbol {
+        Symbol::Constant(crate::Expression::Literal(crate::Spanned::new(
+            crate::expression::Literal::Integer(value, crate::Type::Integer(32)),
+            None,
+        )))
+    }
+
+    /// `a { b { x }, y, use b::x as z, pub use b::x as w }, c { use super::a::y }`
+    fn tree() -> Module {
+        let mut root = Module::new();
+        root.insert(&path("a::b::x"), symbol(1));
+        root.insert(&path("a::y"), symbol(2));
+        let a = root.module_mut(&path("a"));
+        a.imports.insert("z".to_owned(), Import::new(path("b::x")));
+        a.imports.insert(
+            "w".to_owned(),
+            Import {
+                public: true,
+                ..Import::new(path("self::b::x"))
+            },
+        );
+        root.module_mut(&path("c"))
+            .imports
+            .insert("y".to_owned(), Import::new(path("super::a::y")));
+        root
+    }
+
+    #[test]
+    fn test_resolve() {
+        let root = tree();
+        check!(root.resolve(&Path::new(), &path("a::b::x")) == Ok(path("a::b::x")));
+        check!(root.resolve(&path("a::b"), &path("super::y")) == Ok(path("a::y")));
+        check!(root.resolve(&path("a::b"), &path("crate::a::y")) == Ok(path("a::y")));
+        check!(root.resolve(&path("a::b"), &path("self::x")) == Ok(path("a::b::x")));
+        check!(root.resolve(&path("a"), &path("b")) == Ok(path("a::b")));
+        check!(root.resolve(&path("c"), &path("y")) == Ok(path("a::y")));
+        let_assert!(
+            Ok(Symbol::Constant(crate::Expression::Literal(literal))) =
+                root.lookup(&path("c"), &path("y"))
+        );
+        check!(literal.r#type() == &crate::Type::Integer(32));
+
+        check!(
+            root.resolve(&Path::new(), &path("a::q"))
+                == Err(ResolveError::NotFound {
+                    name: "q".to_owned(),
+                    module: path("a"),
+                })
+        );
+        check!(root.resolve(&Path::new(), &path("super")) == Err(ResolveError::NoParent));
+        check!(
+            root.resolve(&Path::new(), &path("a::y::x"))
+                == Err(ResolveError::NotAModule(path("a::y")))
+        );
+        check!(
+            root.lookup(&Path::new(), &path("a::b")).err()
+                == Some(ResolveError::NotASymbol(path("a::b")))
+        );
+    }
+
+    #[test]
+    fn test_imports() {
+        let mut root = tree();
+        check!(root.resolve(&path("a"), &path("z")) == Ok(path("a::b::x")));
+        check!(root.resolve(&path("a::b"), &path("super::z")) == Ok(path("a::b::x")));
+        check!(
+            root.resolve(&path("c"), &path("super::a::z"))
+                == Err(ResolveError::Private {
+                    name: "z".to_owned(),
+                    module: path("a"),
+                })
+        );
+        check!(root.resolve(&path("c"), &path("crate::a::w")) == Ok(path("a::b::x")));
+
+        let c = root.module_mut(&path("c"));
+        c.imports.insert("p".to_owned(), Import::new(path("q")));
+        c.imports.insert("q".to_owned(), Import::new(path("p")));
+        check!(root.resolve(&path("c"), &path("p")) == Err(ResolveError::Cycle(path("c::p"))));
+    }
+
+    #[test]
+    fn test_flatten() {
+        let root = tree();
+        check!(
+            root.symbol_table()
+                .keys()
+                .map(Path::to_string)
+                .collect::<Vec<_>>()
+                == ["a::b::x", "a::y"]
+        );
+        let mut unit = root.flatten();
+        check!(unit.len() == 2);
+        check!(unit.remove("a::b::x").is_some());
+    }
+}
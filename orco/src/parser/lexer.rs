@@ -0,0 +1,109 @@
+use super::ParseError;
+
+/// Token of the textual IR
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    /// Identifier or a keyword, `_` included
+    Ident(String),
+    /// Number literal with an optional sign and type suffix, f.e. `-1.5f64`
+    Number(String),
+    /// Quoted name of an unresolved type, `'name'`
+    Quoted(String),
+    /// Punctuation, f.e. `::` or `->`
+    Punct(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ident(ident) => write!(f, "`{}`", ident),
+            Self::Number(number) => write!(f, "`{}`", number),
+            Self::Quoted(name) => write!(f, "`'{}'`", name),
+            Self::Punct(punct) => write!(f, "`{}`", punct),
+        }
+    }
+}
+
+/// Longer punctuation goes first, so that `::` is not split into two `:`
+const PUNCTUATION: &[&str] = &[
+    "::", "->", "(", ")", "{", "}", "[", "]", "<", ">", ",", ";", ":", ".", "*", "!", "?", "=",
+];
+
+/// Split the source into tokens with their byte ranges
+pub fn tokenize(source: &str) -> Result<Vec<(Token, std::ops::Range<usize>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        let rest = &source[start..];
+        let word = |chars: &mut std::iter::Peekable<std::str::CharIndices>| {
+            let mut end = start;
+            while let Some(&(index, c)) = chars.peek() {
+                if !(c.is_alphanumeric()
+                    || c == '_'
+                    || c == '.' && is_number(&source[start..index]))
+                {
+                    break;
+                }
+                chars.next();
+                end = index + c.len_utf8();
+                // Exponent sign, `1e-7`
+                if matches!(c, 'e' | 'E') && is_number(&source[start..index]) {
+                    if let Some(&(index, sign @ ('-' | '+'))) = chars.peek() {
+                        chars.next();
+                        end = index + sign.len_utf8();
+                    }
+                }
+            }
+            end
+        };
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit() || c == 'i') {
+            chars.next();
+            let end = word(&mut chars);
+            tokens.push((Token::Number(source[start..end].to_owned()), start..end));
+        } else if c.is_alphanumeric() || c == '_' {
+            let end = word(&mut chars);
+            let text = &source[start..end];
+            let token = if is_number(text) {
+                Token::Number(text.to_owned())
+            } else {
+                Token::Ident(text.to_owned())
+            };
+            tokens.push((token, start..end));
+        } else if c == '\'' {
+            let Some(len) = rest[1..].find('\'') else {
+                return Err(ParseError::new(
+                    "unterminated type name",
+                    start..source.len(),
+                ));
+            };
+            let end = start + len + 2;
+            tokens.push((Token::Quoted(rest[1..len + 1].to_owned()), start..end));
+            while chars.peek().is_some_and(|&(index, _)| index < end) {
+                chars.next();
+            }
+        } else if let Some(punct) = PUNCTUATION.iter().find(|punct| rest.starts_with(**punct)) {
+            for _ in 0..punct.len() {
+                chars.next();
+            }
+            tokens.push((Token::Punct(punct), start..start + punct.len()));
+        } else {
+            return Err(ParseError::new(
+                format!("unexpected character `{}`", c),
+                start..start + c.len_utf8(),
+            ));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Does this word start a number? Floats can also be `inf` and `NaN`
+fn is_number(word: &str) -> bool {
+    let word = word.strip_prefix('-').unwrap_or(word);
+    word.starts_with(|c: char| c.is_ascii_digit())
+        || ["inf", "NaN"].iter().any(|special| {
+            word.strip_prefix(special)
+                .is_some_and(|suffix| suffix.is_empty() || super::sized(suffix).is_some())
+        })
+}
@@ -0,0 +1,1119 @@
+/// Tokens of the textual IR, see [lexer::tokenize]
+pub mod lexer;
+use lexer::Token;
+
+use crate::expression::function::FunctionBody;
+use crate::expression::{Call, Comptime, FieldAccess, Function, Index, Literal, TraitCall};
+use crate::types::{CallingConvention, FunctionSignature, Mutability, Struct, StructRef};
+use crate::{Expression, Type, Walk};
+
+/// Syntax error in the textual IR
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// What went wrong
+    pub message: String,
+    /// Byte range of the offending text
+    pub range: std::ops::Range<usize>,
+}
+
+impl ParseError {
+    /// Create a new error at a byte range
+    pub fn new(message: impl ToString, range: std::ops::Range<usize>) -> Self {
+        Self {
+            message: message.to_string(),
+            range,
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse a unit: `const a::b = expression;` items, `use a::c = super::b;` imports
+/// and `struct Name { .. }` declarations, the way [unit_to_string] prints them.
+/// Items are put into a [crate::Module] by their paths, and the paths of the calls
+/// are resolved from the module of the item, see [crate::Module::resolve].
+/// Printed units call functions by their full paths, so paths that aren't found
+/// there are resolved from the root module too. Calls to named functions point to
+/// the [crate::Symbol::Function] they resolve to, the unit is [crate::Module::flatten]ed.
+/// Structs are declared in [crate::TypeInferenceContext::types]
+pub fn parse_unit(
+    ctx: &mut crate::TypeInferenceContext,
+    source: &str,
+) -> Result<crate::Symbols, ParseError> {
+    let mut parser = Parser::new(ctx, source)?;
+    while parser.peek().is_some() {
+        if parser.eat_keyword("struct") {
+            parser.struct_declaration()?;
+            parser.eat(";");
+            continue;
+        }
+        let start = parser.range().start;
+        let public = parser.eat_keyword("pub");
+        if public || parser.peek() == Some(&Token::Ident("use".to_owned())) {
+            if !parser.eat_keyword("use") {
+                return Err(parser.unexpected("`use`"));
+            }
+            parser.import(public, start)?;
+            continue;
+        }
+        if !parser.eat_keyword("const") {
+            return Err(parser.unexpected("`const`, `use` or `struct`"));
+        }
+        let path = parser.item_path()?;
+        parser.expect("=")?;
+        parser.variables.clear();
+        parser.module = path.parent().unwrap_or_default();
+        let symbol = match parser.expression()? {
+            Expression::Function(function) => {
+                crate::Symbol::Function(std::sync::Arc::new(std::sync::RwLock::new(function)))
+            }
+            expression => crate::Symbol::Constant(expression),
+        };
+        parser.expect(";")?;
+        parser.root.insert(&path, symbol);
+    }
+    let links = parser.resolve_calls()?;
+    parser.finish()?;
+    let mut symbols = std::mem::take(&mut parser.root).flatten();
+    for symbol in symbols.values_mut() {
+        symbol.walk_mut(&mut Linker(&links));
+    }
+    Ok(symbols)
+}
+
+/// Points the calls from the placeholders to the functions they resolved to,
+/// see [Parser::resolve_calls]. Functions, that are called in place, are not
+/// symbols, so the calls inside of them are linked too
+struct Linker<'a>(
+    &'a std::collections::HashMap<*const std::sync::RwLock<Function>, crate::ArcLock<Function>>,
+);
+
+impl crate::VisitorMut for Linker<'_> {
+    fn visit_call(&mut self, call: &mut Call) {
+        match self.0.get(&std::sync::Arc::as_ptr(&call.function)) {
+            Some(function) => call.function = function.clone(),
+            None => call.function.write().unwrap().walk_mut(self),
+        }
+        call.walk_mut(self);
+    }
+}
+
+/// Top-level items of a unit with their byte ranges, without parsing them.
+/// Constants are named by their paths, imports are named `use a::b`
+/// and structs are named `struct Name`, like in [crate::TypeInferenceContext::types]
+pub fn items(source: &str) -> Result<Vec<(String, std::ops::Range<usize>)>, ParseError> {
+    let tokens = lexer::tokenize(source)?;
+    let mut tokens = tokens.into_iter().peekable();
+    let mut items = Vec::new();
+    while let Some((token, range)) = tokens.next() {
+        let mut name = match token {
+            Token::Ident(keyword) if keyword == "struct" => "struct ".to_owned(),
+            Token::Ident(keyword) if keyword == "const" => String::new(),
+            Token::Ident(keyword) if keyword == "use" => "use ".to_owned(),
+            Token::Ident(keyword)
+                if keyword == "pub"
+                    && tokens
+                        .next_if(|(token, _)| *token == Token::Ident("use".to_owned()))
+                        .is_some() =>
+            {
+                "use ".to_owned()
+            }
+            token => {
+                return Err(ParseError::new(
+                    format!("expected `const`, `use` or `struct`, found {}", token),
+                    range,
+                ))
+            }
+        };
+        let start = range.start;
+        let mut end = range.end;
+        while let Some((Token::Ident(_) | Token::Punct("::"), _)) = tokens.peek() {
+            let (token, range) = tokens.next().unwrap();
+            name.push_str(match &token {
+                Token::Ident(ident) => ident,
+                _ => "::",
+            });
+            end = range.end;
+        }
+        if name.is_empty() || name.ends_with(' ') {
+            return Err(ParseError::new("expected a name", end..end));
+        }
+
+        // Items end with a `;` outside of brackets, struct declarations may omit it
+        let mut depth = 0usize;
+        for (token, range) in tokens.by_ref() {
+            end = range.end;
+            match token {
+                Token::Punct("(" | "{" | "[") => depth += 1,
+                Token::Punct(")" | "]") => depth = depth.saturating_sub(1),
+                Token::Punct("}") => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 && name.starts_with("struct ") {
+                        break;
+                    }
+                }
+                Token::Punct(";") if depth == 0 => break,
+                _ => (),
+            }
+        }
+        if name.starts_with("struct ")
+            && tokens
+                .peek()
+                .is_some_and(|(token, _)| *token == Token::Punct(";"))
+        {
+            end = tokens.next().unwrap().1.end;
+        }
+        items.push((name, start..end));
+    }
+    Ok(items)
+}
+
+/// Parse a single expression. It can't call named functions, there are no items to call
+pub fn parse_expression(
+    ctx: &mut crate::TypeInferenceContext,
+    source: &str,
+) -> Result<Expression, ParseError> {
+    let mut parser = Parser::new(ctx, source)?;
+    let expression = parser.expression()?;
+    if parser.peek().is_some() {
+        return Err(parser.unexpected("end of input"));
+    }
+    parser.resolve_calls()?;
+    parser.finish()?;
+    Ok(expression)
+}
+
+/// Parse a type. Named structs are looked up in [crate::TypeInferenceContext::types]
+pub fn parse_type(ctx: &mut crate::TypeInferenceContext, source: &str) -> Result<Type, ParseError> {
+    let mut parser = Parser::new(ctx, source)?;
+    let r#type = parser.r#type()?;
+    if parser.peek().is_some() {
+        return Err(parser.unexpected("end of input"));
+    }
+    parser.finish()?;
+    Ok(r#type)
+}
+
+/// Print a unit in the form [parse_unit] reads: named structs
+/// of the context first, then the symbols, sorted by name
+pub fn unit_to_string(ctx: &crate::TypeInferenceContext, symbols: &crate::Symbols) -> String {
+    let mut structs = ctx
+        .types
+        .values()
+        .filter_map(|r#type| match r#type {
+            Type::Struct(r#struct) => Some(r#struct.read().unwrap().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    structs.sort();
+
+    let mut unit = String::new();
+    for r#struct in structs {
+        unit.push_str(&format!("{};\n", r#struct));
+    }
+    for (name, symbol) in symbols {
+        unit.push_str(&format!("const {} = {};\n", name, symbol));
+    }
+    unit
+}
+
+/// Size of a number type, `i32`, `u8` or `f64`
+fn sized(word: &str) -> Option<Type> {
+    let size = |bits: &str| {
+        bits.starts_with(|c: char| c.is_ascii_digit())
+            .then(|| bits.parse().ok())
+            .flatten()
+    };
+    match word.split_at_checked(1)? {
+        ("i", bits) => size(bits).map(Type::Integer),
+        ("u", bits) => size(bits).map(Type::Unsigned),
+        ("f", bits) => size(bits).map(Type::Float),
+        _ => None,
+    }
+}
+
+struct Parser<'a> {
+    ctx: &'a mut crate::TypeInferenceContext,
+    tokens: Vec<(Token, std::ops::Range<usize>)>,
+    position: usize,
+    len: usize,
+    /// Variables of the function that is being parsed, by name
+    variables: std::collections::HashMap<String, crate::ArcLock<crate::Variable>>,
+    /// Module tree of the unit, items are inserted by their paths
+    root: crate::Module,
+    /// Module of the item, that is being parsed
+    module: crate::Path,
+    /// Paths of the called functions, by the module they are called from,
+    /// with the placeholder the calls point to until they are resolved
+    /// and the location of the first call, see [Parser::resolve_calls]
+    calls: std::collections::HashMap<
+        (crate::Path, crate::Path),
+        (crate::ArcLock<Function>, std::ops::Range<usize>),
+    >,
+    /// Structs, that were referred to before they were declared
+    pending_structs: std::collections::HashMap<String, std::ops::Range<usize>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(ctx: &'a mut crate::TypeInferenceContext, source: &str) -> Result<Self, ParseError> {
+        Ok(Self {
+            ctx,
+            tokens: lexer::tokenize(source)?,
+            position: 0,
+            len: source.len(),
+            variables: std::collections::HashMap::new(),
+            root: crate::Module::new(),
+            module: crate::Path::new(),
+            calls: std::collections::HashMap::new(),
+            pending_structs: std::collections::HashMap::new(),
+        })
+    }
+
+    /// `a::b = path;`, after `use`. The import is named by the last segment
+    /// of the item path and is put into the module before it
+    fn import(&mut self, public: bool, start: usize) -> Result<(), ParseError> {
+        let item = self.item_path()?;
+        self.expect("=")?;
+        let path = self.item_path()?;
+        self.expect(";")?;
+        let (Some(module), Some(name)) = (item.parent(), item.name()) else {
+            unreachable!("item paths are not empty");
+        };
+        let import = crate::module::Import {
+            path,
+            public,
+            span: self.span(start),
+        };
+        self.root
+            .module_mut(&module)
+            .imports
+            .insert(name.to_owned(), import);
+        Ok(())
+    }
+
+    /// Path of an item, `a::b::c`
+    fn item_path(&mut self) -> Result<crate::Path, ParseError> {
+        let (path, range) = self.path()?;
+        path.parse()
+            .map_err(|_| ParseError::new("invalid path", range))
+    }
+
+    /// Resolve the paths of the called functions in the module tree.
+    /// Returns the functions, that the placeholders should be replaced with
+    fn resolve_calls(
+        &self,
+    ) -> Result<
+        std::collections::HashMap<*const std::sync::RwLock<Function>, crate::ArcLock<Function>>,
+        ParseError,
+    > {
+        let mut calls = self.calls.iter().collect::<Vec<_>>();
+        calls.sort_by_key(|(_, (_, range))| range.start);
+        let mut links = std::collections::HashMap::new();
+        for ((module, path), (placeholder, range)) in calls {
+            let symbol = self
+                .root
+                .lookup(module, path)
+                .or_else(|error| match error {
+                    crate::module::ResolveError::NotFound { .. } => {
+                        self.root.lookup(&crate::Path::new(), path)
+                    }
+                    error => Err(error),
+                })
+                .map_err(|error| match error {
+                    crate::module::ResolveError::NotFound { .. } => {
+                        ParseError::new(format!("cannot find function `{}`", path), range.clone())
+                    }
+                    error => ParseError::new(error, range.clone()),
+                })?;
+            let crate::Symbol::Function(function) = symbol else {
+                return Err(ParseError::new(
+                    format!("`{}` is not a function", path),
+                    range.clone(),
+                ));
+            };
+            links.insert(std::sync::Arc::as_ptr(placeholder), function.clone());
+        }
+        Ok(links)
+    }
+
+    /// Report functions and structs, that were used but never defined.
+    /// Placeholders of missing structs are removed once the parser is dropped
+    fn finish(&self) -> Result<(), ParseError> {
+        if let Some((name, range)) = self
+            .pending_structs
+            .iter()
+            .min_by_key(|(_, range)| range.start)
+        {
+            return Err(ParseError::new(
+                format!("cannot find struct `{}`", name),
+                range.clone(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.position + n).map(|(token, _)| token)
+    }
+
+    /// Byte range of the next token
+    fn range(&self) -> std::ops::Range<usize> {
+        match self.tokens.get(self.position) {
+            Some((_, range)) => range.clone(),
+            None => self.len..self.len,
+        }
+    }
+
+    /// Span from a byte offset to the end of the last consumed token
+    fn span(&self, start: usize) -> Option<crate::Span> {
+        let end = match self.position.checked_sub(1) {
+            Some(last) => self.tokens[last].1.end,
+            None => start,
+        };
+        self.ctx.span(start..end)
+    }
+
+    fn next(&mut self) -> Result<(Token, std::ops::Range<usize>), ParseError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| ParseError::new("unexpected end of input", self.range()))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        let found = match self.peek() {
+            Some(token) => token.to_string(),
+            None => "end of input".to_owned(),
+        };
+        ParseError::new(
+            format!("expected {}, found {}", expected, found),
+            self.range(),
+        )
+    }
+
+    /// Consume a punctuation token if it's next
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(next)) if *next == punct) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    /// Consume a keyword if it's next
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(next)) if next == keyword) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), ParseError> {
+        if !self.eat(punct) {
+            return Err(self.unexpected(&format!("`{}`", punct)));
+        }
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<(String, std::ops::Range<usize>), ParseError> {
+        match self.peek() {
+            Some(Token::Ident(_)) => match self.next()? {
+                (Token::Ident(ident), range) => Ok((ident, range)),
+                _ => unreachable!(),
+            },
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    /// Symbol name, that might be a path, `a::b::c`
+    fn path(&mut self) -> Result<(String, std::ops::Range<usize>), ParseError> {
+        let (ident, range) = self.ident()?;
+        self.path_rest(ident, range)
+    }
+
+    /// Rest of the path, after its first segment
+    fn path_rest(
+        &mut self,
+        mut path: String,
+        mut range: std::ops::Range<usize>,
+    ) -> Result<(String, std::ops::Range<usize>), ParseError> {
+        while self.peek() == Some(&Token::Punct("::"))
+            && matches!(self.peek_nth(1), Some(Token::Ident(_)))
+        {
+            self.position += 1;
+            let (name, name_range) = self.ident()?;
+            path = format!("{}::{}", path, name);
+            range.end = name_range.end;
+        }
+        Ok((path, range))
+    }
+
+    /// Parse comma-separated items until the closing punctuation
+    fn list<T>(
+        &mut self,
+        close: &str,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut items = Vec::new();
+        while !self.eat(close) {
+            items.push(item(self)?);
+            if !self.eat(",") {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// `struct Name { field: type, .. }`, after the `struct` keyword
+    fn struct_declaration(&mut self) -> Result<(), ParseError> {
+        let (name, range) = self.ident()?;
+        self.expect("{")?;
+        let fields = self.fields()?;
+        let key = format!("struct {}", name);
+        if self.pending_structs.remove(&name).is_none() && self.ctx.types.contains_key(&key) {
+            return Err(ParseError::new(
+                format!("struct `{}` is already declared", name),
+                range,
+            ));
+        }
+        match self.ctx.types.get(&key) {
+            Some(Type::Struct(r#struct)) => r#struct.write().unwrap().fields = fields,
+            _ => {
+                let r#struct = StructRef::new(Struct::new(Some(name), fields));
+                self.ctx.types.insert(key, Type::Struct(r#struct));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fields of a struct, after the opening brace
+    fn fields(&mut self) -> Result<Vec<(String, Type)>, ParseError> {
+        self.list("}", |parser| {
+            let (name, _) = parser.ident()?;
+            parser.expect(":")?;
+            Ok((name, parser.r#type()?))
+        })
+    }
+
+    fn r#type(&mut self) -> Result<Type, ParseError> {
+        let (token, range) = self.next()?;
+        Ok(match token {
+            Token::Punct("!") => Type::Never,
+            Token::Punct("(") => {
+                self.expect(")")?;
+                Type::Unit
+            }
+            Token::Punct("*") => {
+                let mutability = if self.eat_keyword("mut") {
+                    Mutability::Mut
+                } else if self.eat_keyword("const") {
+                    Mutability::Const
+                } else {
+                    return Err(self.unexpected("`mut` or `const`"));
+                };
+                // `*mut [T]` is a slice, `*mut [T; 4]` is a pointer to an array
+                if self.eat("[") {
+                    let element = self.r#type()?;
+                    if self.eat(";") {
+                        let len = self.len()?;
+                        self.expect("]")?;
+                        Type::Pointer(Box::new(Type::Array(Box::new(element), len)), mutability)
+                    } else {
+                        self.expect("]")?;
+                        Type::Slice(Box::new(element), mutability)
+                    }
+                } else {
+                    Type::Pointer(Box::new(self.r#type()?), mutability)
+                }
+            }
+            Token::Punct("[") => {
+                let element = self.r#type()?;
+                self.expect(";")?;
+                let len = self.len()?;
+                self.expect("]")?;
+                Type::Array(Box::new(element), len)
+            }
+            Token::Quoted(name) => Type::Unresolved(name),
+            Token::Ident(word) => match word.as_str() {
+                "_" => Type::Wildcard,
+                "bool" => Type::Bool,
+                "fn" => Type::Fn(self.signature()?),
+                "struct" if self.eat("{") => {
+                    Type::Struct(StructRef::new(Struct::new(None, self.fields()?)))
+                }
+                "struct" => {
+                    let (name, range) = self.ident()?;
+                    let key = format!("struct {}", name);
+                    if let Some(r#type) = self.ctx.types.get(&key) {
+                        return Ok(r#type.clone());
+                    }
+                    // Declared later in the unit
+                    let r#type =
+                        Type::Struct(StructRef::new(Struct::new(Some(name.clone()), Vec::new())));
+                    self.ctx.types.insert(key, r#type.clone());
+                    self.pending_structs.insert(name, range);
+                    r#type
+                }
+                word => sized(word).unwrap_or_else(|| Type::Parameter(word.to_owned())),
+            },
+            token => {
+                return Err(ParseError::new(
+                    format!("expected a type, found {}", token),
+                    range,
+                ))
+            }
+        })
+    }
+
+    /// Array length
+    fn len(&mut self) -> Result<u64, ParseError> {
+        match self.next()? {
+            (Token::Number(number), range) => number
+                .parse()
+                .map_err(|_| ParseError::new("invalid array length", range)),
+            (token, range) => Err(ParseError::new(
+                format!("expected an array length, found {}", token),
+                range,
+            )),
+        }
+    }
+
+    /// `<T>(name: type, _: type) -> type calling_convention`
+    fn signature(&mut self) -> Result<FunctionSignature, ParseError> {
+        let type_parameters = if self.eat("<") {
+            self.list(">", |parser| Ok(parser.ident()?.0))?
+        } else {
+            Vec::new()
+        };
+        self.expect("(")?;
+        let parameters = self.list(")", |parser| {
+            let (name, _) = parser.ident()?;
+            parser.expect(":")?;
+            Ok(((name != "_").then_some(name), parser.r#type()?))
+        })?;
+        self.expect("->")?;
+        let return_type = self.r#type()?;
+        let (calling_convention, range) = self.ident()?;
+        let calling_convention = calling_convention
+            .parse::<CallingConvention>()
+            .map_err(|_| ParseError::new("unknown calling convention", range))?;
+        Ok(FunctionSignature {
+            type_parameters,
+            ..FunctionSignature::new(parameters, return_type, calling_convention)
+        })
+    }
+
+    fn expression(&mut self) -> Result<Expression, ParseError> {
+        let start = self.range().start;
+        let mut expression = self.primary()?;
+        loop {
+            if self.eat(".") {
+                let (name, _) = self.ident()?;
+                expression = Expression::Field(FieldAccess {
+                    span: self.span(start),
+                    ..FieldAccess::new(expression, name)
+                });
+            } else if self.eat("[") {
+                let index = self.expression()?;
+                self.expect("]")?;
+                let checked = self.eat("?");
+                expression = Expression::Index(Index {
+                    checked,
+                    span: self.span(start),
+                    ..Index::new(expression, index)
+                });
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression, ParseError> {
+        let start = self.range().start;
+        let (token, range) = self.next()?;
+        let literal = |parser: &Self, literal| {
+            Ok(Expression::Literal(crate::Spanned::new(
+                literal,
+                parser.span(start),
+            )))
+        };
+        let word = match token {
+            Token::Number(number) => return literal(self, number_literal(&number, range)?),
+            // Intrinsics and other unnamed functions are called in place
+            Token::Punct("(") => {
+                let function = match self.expression()? {
+                    Expression::Function(function) => function,
+                    _ => return Err(ParseError::new("only functions can be called", range)),
+                };
+                self.expect(")")?;
+                let function = std::sync::Arc::new(std::sync::RwLock::new(function));
+                return self.call(function, start);
+            }
+            Token::Punct("<") => {
+                return Err(ParseError::new("error expressions can't be parsed", range))
+            }
+            Token::Ident(word) => word,
+            token => {
+                return Err(ParseError::new(
+                    format!("expected an expression, found {}", token),
+                    range,
+                ))
+            }
+        };
+        match word.as_str() {
+            "true" => literal(self, Literal::Bool(true)),
+            "false" => literal(self, Literal::Bool(false)),
+            "null" => {
+                let r#type = if self.eat_keyword("as") {
+                    self.r#type()?
+                } else {
+                    Type::Wildcard
+                };
+                literal(self, Literal::Null(r#type))
+            }
+            "fn" => Ok(Expression::Function(self.function(start)?)),
+            "comptime" => {
+                self.expect("{")?;
+                let body = self.body()?;
+                Ok(Expression::Comptime(Comptime {
+                    span: self.span(start),
+                    ..Comptime::new(body)
+                }))
+            }
+            "unnamed"
+                if self.peek() == Some(&Token::Ident("variable".to_owned()))
+                    && self.peek_nth(1) == Some(&Token::Punct(":")) =>
+            {
+                self.position += 2;
+                self.variable(None, start)
+            }
+            _ if self.eat(":") => self.variable(Some(word), start),
+            _ if self.peek() == Some(&Token::Punct("::")) && self.find_trait(&word).is_some() => {
+                self.position += 1;
+                self.trait_call(word, range, start)
+            }
+            _ if matches!(self.peek(), Some(Token::Punct("(" | "::"))) => {
+                let (word, range) = self.path_rest(word, range)?;
+                let path = word
+                    .parse()
+                    .map_err(|_| ParseError::new("invalid path", range.clone()))?;
+                let function = self
+                    .calls
+                    .entry((self.module.clone(), path))
+                    .or_insert_with(|| {
+                        // Replaced once the path is resolved, see [Parser::resolve_calls]
+                        let function = Function::new(
+                            FunctionSignature::new(
+                                Vec::new(),
+                                Type::Wildcard,
+                                CallingConvention::default(),
+                            ),
+                            Some(word),
+                            Vec::new(),
+                        );
+                        (std::sync::Arc::new(std::sync::RwLock::new(function)), range)
+                    })
+                    .0
+                    .clone();
+                self.call(function, start)
+            }
+            _ => Err(self.unexpected("`:` or a call")),
+        }
+    }
+
+    /// `Trait::method(..)`, after the `::`
+    fn trait_call(
+        &mut self,
+        name: String,
+        range: std::ops::Range<usize>,
+        start: usize,
+    ) -> Result<Expression, ParseError> {
+        let (method, _) = self.ident()?;
+        let r#trait = self
+            .find_trait(&name)
+            .ok_or_else(|| ParseError::new(format!("cannot find trait `{}`", name), range))?;
+        self.expect("(")?;
+        let args = self.list(")", Self::expression)?;
+        Ok(Expression::TraitCall(TraitCall {
+            span: self.span(start),
+            ..TraitCall::new(r#trait, method, args)
+        }))
+    }
+
+    /// Trait or an operator trait by its name
+    fn find_trait(&self, name: &str) -> Option<crate::traits::TraitRef> {
+        self.ctx
+            .traits
+            .get(name)
+            .or_else(|| {
+                self.ctx
+                    .operators
+                    .values()
+                    .find(|r#trait| r#trait.read().unwrap().name == name)
+            })
+            .cloned()
+    }
+
+    /// Type arguments and arguments of a call, after the called function
+    fn call(
+        &mut self,
+        function: crate::ArcLock<Function>,
+        start: usize,
+    ) -> Result<Expression, ParseError> {
+        let type_arguments = if self.eat("::") {
+            self.expect("<")?;
+            self.list(">", Self::r#type)?
+        } else {
+            Vec::new()
+        };
+        self.expect("(")?;
+        let args = self.list(")", Self::expression)?;
+        Ok(Expression::Call(Call {
+            type_arguments,
+            span: self.span(start),
+            ..Call::new(function, args)
+        }))
+    }
+
+    /// `name: type`, after the colon. Variables are told apart by name
+    fn variable(&mut self, name: Option<String>, start: usize) -> Result<Expression, ParseError> {
+        let r#type = self.r#type()?;
+        let span = self.span(start);
+        let key = name.clone().unwrap_or_default();
+        let variable = self
+            .variables
+            .entry(key)
+            .or_insert_with(|| {
+                std::sync::Arc::new(std::sync::RwLock::new(crate::Variable {
+                    span,
+                    ..crate::Variable::new(name, r#type)
+                }))
+            })
+            .clone();
+        Ok(Expression::Variable(crate::Spanned::new(variable, span)))
+    }
+
+    /// `fn name<T>(..) -> type calling_convention { .. }` or `= intrinsic`, after `fn`
+    fn function(&mut self, start: usize) -> Result<Function, ParseError> {
+        let name = match self.peek() {
+            Some(Token::Ident(_)) => Some(self.path()?.0),
+            _ => None,
+        };
+        let signature = self.signature()?;
+
+        if self.eat("=") {
+            let intrinsic_start = self.range().start;
+            let (mut intrinsic, _) = self.ident()?;
+            if intrinsic == "implicit" {
+                intrinsic.push_str(&format!(" {}", self.ident()?.0));
+            }
+            // Casts and loop control take an argument, `cast sext` or `break 1`
+            if matches!(
+                intrinsic.as_str(),
+                "implicit cast" | "cast" | "break" | "continue"
+            ) {
+                if let Some(Token::Ident(word) | Token::Number(word)) = self.peek() {
+                    intrinsic.push_str(&format!(" {}", word));
+                    self.position += 1;
+                }
+            }
+            let Ok(intrinsic) = intrinsic.parse() else {
+                return Err(ParseError::new(
+                    format!("unknown intrinsic `{}`", intrinsic),
+                    intrinsic_start..self.span_end(),
+                ));
+            };
+            return Ok(Function {
+                name,
+                span: self.span(start),
+                ..Function::intrinsic(signature, intrinsic)
+            });
+        }
+
+        // Parameters are only visible inside of the function
+        let outer = self.variables.clone();
+        let parameters = signature
+            .parameters
+            .iter()
+            .map(|(name, r#type)| {
+                let variable = std::sync::Arc::new(std::sync::RwLock::new(crate::Variable::new(
+                    name.clone(),
+                    r#type.clone(),
+                )));
+                if let Some(name) = name {
+                    self.variables.insert(name.clone(), variable.clone());
+                }
+                variable
+            })
+            .collect();
+        self.expect("{")?;
+        let body = self.body();
+        self.variables = outer;
+        Ok(Function {
+            signature,
+            name,
+            parameters,
+            body: FunctionBody::Block(body?),
+            span: self.span(start),
+        })
+    }
+
+    /// End of the last consumed token
+    fn span_end(&self) -> usize {
+        self.tokens[self.position - 1].1.end
+    }
+
+    /// `expression; ..}`, after the opening brace
+    fn body(&mut self) -> Result<Vec<Expression>, ParseError> {
+        let mut body = Vec::new();
+        while !self.eat("}") {
+            body.push(self.expression()?);
+            self.expect(";")?;
+        }
+        Ok(body)
+    }
+}
+
+impl Drop for Parser<'_> {
+    fn drop(&mut self) {
+        for name in self.pending_structs.keys() {
+            self.ctx.types.remove(&format!("struct {}", name));
+        }
+    }
+}
+
+/// Number literal with an optional type suffix, f.e. `42`, `42u8` or `1.5f32`
+fn number_literal(number: &str, range: std::ops::Range<usize>) -> Result<Literal, ParseError> {
+    let digits = number.strip_prefix('-').unwrap_or(number);
+    let special = ["inf", "NaN"]
+        .iter()
+        .any(|special| digits.starts_with(special));
+    let split = match special {
+        true => number.len() - digits.len() + 3,
+        false => number.find(['i', 'u', 'f']).unwrap_or(number.len()),
+    };
+    let (value, suffix) = number.split_at(split);
+    let r#type = match suffix {
+        "" => Type::Wildcard,
+        suffix => sized(suffix).ok_or_else(|| {
+            ParseError::new(format!("invalid suffix `{}`", suffix), range.clone())
+        })?,
+    };
+    let float = special || value.contains(['.', 'e', 'E']) || matches!(r#type, Type::Float(_));
+    if float {
+        return value
+            .parse()
+            .map(|value| Literal::Float(value, r#type))
+            .map_err(|_| ParseError::new("invalid float literal", range));
+    }
+    value
+        .parse()
+        .map(|value| Literal::Integer(value, r#type))
+        .map_err(|_| ParseError::new("invalid integer literal", range))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    const UNIT: &str = "\
+struct Point { x: i32, y: f64 };
+const factorial = fn factorial(n: i32) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (cond: bool, then: fn () -> i32 transparent, else: fn () -> i32 transparent) -> i32 transparent = branch)((fn (lhs: i32, rhs: i32) -> bool transparent = le)(n: i32, 1i32), fn () -> i32 transparent {
+        1i32;
+    }, fn () -> i32 transparent {
+        (fn (lhs: i32, rhs: i32) -> i32 transparent = mul)(n: i32, factorial((fn (lhs: i32, rhs: i32) -> i32 transparent = sub)(n: i32, 1i32)));
+    }));
+};
+const origin = fn origin(point: *mut struct Point) -> f64 fastest {
+    (fn (place: f64, value: f64) -> f64 transparent = assign)(y: f64, (fn (value: i32) -> f64 transparent = cast itof)((fn (pointer: *const struct Point) -> struct Point transparent = load)(point: *mut struct Point).x));
+    y: f64;
+};
+";
+
+    #[test]
+    fn test_round_trip() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let mut symbols = parse_unit(&mut ctx, UNIT).unwrap();
+        check!(unit_to_string(&ctx, &symbols) == UNIT);
+
+        ctx.infer_symbols(symbols.values_mut());
+        check!(!ctx.diagnostics.has_errors());
+        let result = crate::interpreter::Interpreter::new().run(
+            &symbols,
+            "factorial",
+            vec![crate::interpreter::Value::Integer(5, 32)],
+        );
+        check!(result == Ok(crate::interpreter::Value::Integer(120, 32)));
+    }
+
+    #[test]
+    fn test_calls() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let symbols = parse_unit(&mut ctx, UNIT).unwrap();
+        let_assert!(crate::Symbol::Function(factorial) = &symbols["factorial"]);
+
+        // return(branch(.., .., fn { mul(n, factorial(..)) }))
+        let function = factorial.read().unwrap();
+        let_assert!(FunctionBody::Block(body) = &function.body);
+        let_assert!(Expression::Call(r#return) = &body[0]);
+        let_assert!(Expression::Call(branch) = &r#return.args[0]);
+        let_assert!(Expression::Function(r#else) = &branch.args[2]);
+        let_assert!(FunctionBody::Block(body) = &r#else.body);
+        let_assert!(Expression::Call(mul) = &body[0]);
+        let_assert!(Expression::Call(call) = &mul.args[1]);
+        check!(std::sync::Arc::ptr_eq(&call.function, factorial));
+    }
+
+    #[test]
+    fn test_types() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        for r#type in [
+            "_",
+            "!",
+            "()",
+            "*const [u8]",
+            "*mut [f32; 4]",
+            "[*const i64; 2]",
+            "fn <T>(_: T, value: *mut T) -> bool sys_v",
+            "struct { a: i32, b: [u8; 3] }",
+            "'size_t'",
+        ] {
+            check!(parse_type(&mut ctx, r#type).unwrap().to_string() == r#type);
+        }
+        let_assert!(Err(error) = parse_type(&mut ctx, "struct Missing"));
+        check!(error.message == "cannot find struct `Missing`");
+    }
+
+    #[test]
+    fn test_literals() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        for literal in [
+            "42",
+            "42u8",
+            "-1.5f32",
+            "1e-7f64",
+            "inff64",
+            "true",
+            "null as *mut i32",
+        ] {
+            check!(parse_expression(&mut ctx, literal).unwrap().to_string() == literal);
+        }
+        let_assert!(Err(error) = parse_expression(&mut ctx, "foo(1)"));
+        check!(error.message == "cannot find function `foo`");
+        check!(error.range == (0..3));
+    }
+
+    #[test]
+    fn test_items() {
+        let_assert!(Ok(found) = items(UNIT));
+        check!(
+            found
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                == ["struct Point", "factorial", "origin"]
+        );
+        check!(UNIT[found[0].1.clone()] == *"struct Point { x: i32, y: f64 };");
+        check!(UNIT[found[2].1.clone()].ends_with("y: f64;\n};"));
+        let_assert!(Ok(found) = items("pub use a::b = c; use d = a::b;"));
+        check!(found == [("use a::b".to_owned(), 0..17), ("use d".to_owned(), 18..31)]);
+        let_assert!(Err(error) = items("const a = 1i32; fn"));
+        check!(error.range == (16..18));
+    }
+
+    #[test]
+    fn test_paths() {
+        const UNIT: &str = "\
+const a::b::one = fn a::b::one() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)(1i32);
+};
+const main = fn main() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)(a::b::one());
+};
+";
+        let mut ctx = crate::TypeInferenceContext::new();
+        let symbols = parse_unit(&mut ctx, UNIT).unwrap();
+        check!(unit_to_string(&ctx, &symbols) == UNIT);
+
+        // Modules are flattened into the same unit
+        let mut root = crate::Module::new();
+        for (name, symbol) in symbols {
+            root.insert(&name.parse().unwrap(), symbol);
+        }
+        check!(
+            root.resolve(&"a".parse().unwrap(), &"b::one".parse().unwrap())
+                == Ok("a::b::one".parse().unwrap())
+        );
+        let mut unit = root.flatten();
+        ctx.infer_symbols(unit.values_mut());
+        check!(!ctx.diagnostics.has_errors());
+        let result = crate::interpreter::Interpreter::new().run(&unit, "main", Vec::new());
+        check!(result == Ok(crate::interpreter::Value::Integer(1, 32)));
+    }
+
+    #[test]
+    fn test_modules() {
+        const UNIT: &str = "\
+const a::b::one = fn a::b::one() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)(1i32);
+};
+pub use a::uno = self::b::one;
+use a::hidden = b::one;
+use c::one = super::a::uno;
+const c::main = fn c::main() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = add)(one(), crate::a::b::one()));
+};
+";
+        let mut ctx = crate::TypeInferenceContext::new();
+        let mut symbols = parse_unit(&mut ctx, UNIT).unwrap();
+        check!(symbols.keys().collect::<Vec<_>>() == ["a::b::one", "c::main"]);
+        let_assert!(crate::Symbol::Function(one) = &symbols["a::b::one"]);
+        let_assert!(crate::Symbol::Function(main) = &symbols["c::main"]);
+        {
+            let main = main.read().unwrap();
+            let_assert!(FunctionBody::Block(body) = &main.body);
+            let_assert!(Expression::Call(r#return) = &body[0]);
+            let_assert!(Expression::Call(add) = &r#return.args[0]);
+            for arg in &add.args {
+                let_assert!(Expression::Call(call) = arg);
+                check!(std::sync::Arc::ptr_eq(&call.function, one));
+            }
+        }
+
+        // Printed units call functions by their full paths
+        let text = unit_to_string(&ctx, &symbols);
+        let parsed = parse_unit(&mut ctx, &text).unwrap();
+        check!(unit_to_string(&ctx, &parsed) == text);
+        ctx.infer_symbols(symbols.values_mut());
+        check!(!ctx.diagnostics.has_errors());
+        let result = crate::interpreter::Interpreter::new().run(&symbols, "c::main", Vec::new());
+        check!(result == Ok(crate::interpreter::Value::Integer(2, 32)));
+
+        let_assert!(Err(error) = parse_unit(&mut ctx, &UNIT.replace("(one()", "(a::hidden()")));
+        check!(error.message == "import `hidden` is private to `a`");
+        let_assert!(
+            Err(error) = parse_unit(&mut ctx, &UNIT.replace("(one()", "(super::super::one()"))
+        );
+        check!(error.message == "there are no modules above the root one");
+        let unit = "const a = 1i32;\nconst b = fn b() -> () fastest {\n    a();\n};";
+        let_assert!(Err(error) = parse_unit(&mut ctx, unit));
+        check!(error.message == "`a` is not a function");
+    }
+}
@@ -0,0 +1,23 @@
+use super::*;
+
+/// Replaces constant arithmetic, casts and calls to pure functions
+/// with their values, see [crate::comptime::fold]
+pub struct ConstantFolding;
+
+impl Pass for ConstantFolding {
+    fn name(&self) -> &'static str {
+        "constant-folding"
+    }
+
+    fn run(
+        &mut self,
+        _ctx: &mut crate::TypeInferenceContext,
+        symbols: &mut crate::Symbols,
+    ) -> bool {
+        let mut changed = false;
+        for symbol in symbols.values_mut() {
+            changed |= crate::comptime::fold_symbol(symbol);
+        }
+        changed
+    }
+}
@@ -0,0 +1,81 @@
+use super::*;
+use crate::expression::{function::FunctionBody, Comptime, Function};
+use crate::{VisitorMut, Walk};
+
+/// Removes the code after calls that never return, see [diverges]
+pub struct DeadCode;
+
+impl Pass for DeadCode {
+    fn name(&self) -> &'static str {
+        "dead-code"
+    }
+
+    fn run(
+        &mut self,
+        _ctx: &mut crate::TypeInferenceContext,
+        symbols: &mut crate::Symbols,
+    ) -> bool {
+        let mut remover = Remover { changed: false };
+        for symbol in symbols.values_mut() {
+            symbol.walk_mut(&mut remover);
+        }
+        remover.changed
+    }
+}
+
+struct Remover {
+    changed: bool,
+}
+
+impl Remover {
+    fn block(&mut self, body: &mut Vec<Expression>) {
+        if let Some(index) = body.iter().position(diverges) {
+            if index + 1 < body.len() {
+                body.truncate(index + 1);
+                self.changed = true;
+            }
+        }
+    }
+}
+
+impl VisitorMut for Remover {
+    fn visit_function(&mut self, function: &mut Function) {
+        if let FunctionBody::Block(body) = &mut function.body {
+            self.block(body);
+        }
+        function.walk_mut(self);
+    }
+
+    fn visit_comptime(&mut self, comptime: &mut Comptime) {
+        self.block(&mut comptime.body);
+        comptime.walk_mut(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    #[test]
+    fn test_nested() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let mut symbols = crate::parser::parse_unit(
+            &mut ctx,
+            "\
+const f = fn f(x: bool) -> () fastest {
+    (fn (cond: bool, then: fn () -> () transparent, else: fn () -> () transparent) -> () transparent = branch)(x: bool, fn () -> () transparent {
+        (fn () -> ! transparent = break 0)();
+        (fn (value: ()) -> ! transparent = return)((fn () -> () transparent {
+        })());
+    }, fn () -> () transparent {
+    });
+};
+",
+        )
+        .unwrap();
+        check!(DeadCode.run(&mut ctx, &mut symbols));
+        check!(!symbols["f"].to_string().contains("return"));
+        check!(!DeadCode.run(&mut ctx, &mut symbols));
+    }
+}
@@ -0,0 +1,130 @@
+use super::*;
+use crate::expression::{function::FunctionBody, Comptime, Function};
+use crate::type_inference::intrinsics::Intrinsic;
+use crate::types::CallingConvention;
+use crate::{VisitorMut, Walk};
+
+/// Removes branches with both arms empty, keeping the condition,
+/// calls to empty transparent functions and the statements,
+/// that have no effect, like variables and literals
+pub struct EmptyBranches;
+
+impl Pass for EmptyBranches {
+    fn name(&self) -> &'static str {
+        "empty-branches"
+    }
+
+    fn run(
+        &mut self,
+        _ctx: &mut crate::TypeInferenceContext,
+        symbols: &mut crate::Symbols,
+    ) -> bool {
+        let mut remover = Remover { changed: false };
+        for symbol in symbols.values_mut() {
+            symbol.walk_mut(&mut remover);
+        }
+        remover.changed
+    }
+}
+
+/// Is this a transparent function with an empty body?
+fn empty(expression: &Expression) -> bool {
+    match expression {
+        Expression::Function(function) => {
+            function.signature.calling_convention == CallingConvention::Transparent
+                && matches!(&function.body, FunctionBody::Block(body) if body.is_empty())
+        }
+        _ => false,
+    }
+}
+
+struct Remover {
+    changed: bool,
+}
+
+impl Remover {
+    /// Simplify a statement, returns false if it can be removed
+    fn statement(&mut self, statement: &mut Expression) -> bool {
+        let Expression::Call(call) = statement else {
+            return !matches!(statement, Expression::Literal(_) | Expression::Variable(_));
+        };
+        // Recursive calls to the function, that is being rewritten, are kept
+        let Ok(function) = call.function.try_read() else {
+            return true;
+        };
+        match &function.body {
+            FunctionBody::Intrinsic(Intrinsic::Branch) if call.args.iter().skip(1).all(empty) => {
+                drop(function);
+                let cond = call.args.swap_remove(0);
+                *statement = cond;
+                self.changed = true;
+                self.statement(statement)
+            }
+            FunctionBody::Block(body)
+                if body.is_empty()
+                    && call.args.is_empty()
+                    && function.signature.calling_convention == CallingConvention::Transparent =>
+            {
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Last expression is the value of the block, so it's kept
+    fn block(&mut self, body: &mut Vec<Expression>) {
+        let Some(last) = body.len().checked_sub(1) else {
+            return;
+        };
+        let mut index = 0;
+        body.retain_mut(|statement| {
+            index += 1;
+            index > last || self.statement(statement)
+        });
+        self.changed |= body.len() != last + 1;
+    }
+}
+
+impl VisitorMut for Remover {
+    fn visit_function(&mut self, function: &mut Function) {
+        function.walk_mut(self);
+        if let FunctionBody::Block(body) = &mut function.body {
+            self.block(body);
+        }
+    }
+
+    fn visit_comptime(&mut self, comptime: &mut Comptime) {
+        comptime.walk_mut(self);
+        self.block(&mut comptime.body);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    #[test]
+    fn test_empty_branches() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let mut symbols = crate::parser::parse_unit(
+            &mut ctx,
+            "\
+const f = fn f(x: bool, y: i32) -> i32 fastest {
+    (fn (cond: bool, then: fn () -> () transparent, else: fn () -> () transparent) -> () transparent = branch)(x: bool, fn () -> () transparent {
+    }, fn () -> () transparent {
+    });
+    (fn () -> () transparent {
+    })();
+    y: i32;
+};
+",
+        )
+        .unwrap();
+        check!(EmptyBranches.run(&mut ctx, &mut symbols));
+        check!(
+            symbols["f"].to_string() == "fn f(x: bool, y: i32) -> i32 fastest {\n    y: i32;\n}"
+        );
+        check!(!EmptyBranches.run(&mut ctx, &mut symbols));
+    }
+}
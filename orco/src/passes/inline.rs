@@ -0,0 +1,445 @@
+use super::*;
+use crate::expression::{function::FunctionBody, Call, Comptime, FieldAccess, Function, Index};
+use crate::type_inference::intrinsics::{Intrinsic, Intrinsics};
+use crate::types::{CallingConvention, FunctionSignature};
+use crate::{ArcLock, Type, Visitor, VisitorMut, Walk};
+use std::collections::HashMap;
+
+/// Inlines calls to [CallingConvention::Inline] functions and to
+/// [CallingConvention::Fastest] functions, that are cheap enough, see [Inliner::max_cost].
+///
+/// The called function is replaced with its transparent copy,
+/// that binds the arguments to the parameters. Locals of the copy are renamed,
+/// `return` breaks out of a loop around the body instead.
+/// Transparent functions are inline already, so they are left as is
+pub struct Inliner {
+    /// Biggest [cost] of a `fastest` function, that is still inlined
+    pub max_cost: usize,
+    /// Number of inlined calls, used to make the names of the locals unique
+    pub inlined: usize,
+}
+
+impl Default for Inliner {
+    fn default() -> Self {
+        Self {
+            max_cost: 24,
+            inlined: 0,
+        }
+    }
+}
+
+impl Pass for Inliner {
+    fn name(&self) -> &'static str {
+        "inline"
+    }
+
+    fn run(&mut self, ctx: &mut crate::TypeInferenceContext, symbols: &mut crate::Symbols) -> bool {
+        let inlined = self.inlined;
+        let mut rewriter = Rewriter {
+            inliner: self,
+            intrinsics: &ctx.intrinsics,
+        };
+        for symbol in symbols.values_mut() {
+            symbol.walk_mut(&mut rewriter);
+        }
+        self.inlined != inlined
+    }
+}
+
+impl Inliner {
+    /// Should a call to this function be inlined?
+    /// Generic and recursive functions are never inlined, neither are functions,
+    /// that fall off the end without returning a value
+    pub fn should_inline(&self, function: &Function) -> bool {
+        let FunctionBody::Block(body) = &function.body else {
+            return false;
+        };
+        let inline = match function.signature.calling_convention {
+            CallingConvention::Inline => true,
+            CallingConvention::Fastest => cost(function) <= self.max_cost,
+            _ => false,
+        };
+        inline
+            && function.signature.type_parameters.is_empty()
+            && (*function.signature.return_type == Type::Unit || body.last().is_some_and(diverges))
+            && !function
+                .name
+                .as_ref()
+                .is_some_and(|name| calls(function, name))
+    }
+
+    /// Make a transparent copy of a function, see [Inliner]
+    pub fn inline(&mut self, intrinsics: &Intrinsics, function: &Function) -> Function {
+        let FunctionBody::Block(body) = &function.body else {
+            panic!("intrinsics can't be inlined");
+        };
+        self.inlined += 1;
+        let return_type = function.signature.return_type.as_ref().clone();
+        let mut copier = Copier {
+            intrinsics,
+            suffix: format!("_inline{}", self.inlined),
+            variables: HashMap::new(),
+            return_type: Some(return_type.clone()),
+            depth: 0,
+            returns: 0,
+        };
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| copier.variable(parameter))
+            .collect();
+        let names = function
+            .signature
+            .parameters
+            .iter()
+            .map(|(name, r#type)| {
+                let name = name
+                    .as_ref()
+                    .map(|name| format!("{}{}", name, copier.suffix));
+                (name, r#type.clone())
+            })
+            .collect();
+        let mut body = body
+            .iter()
+            .map(|expression| copier.copy(expression))
+            .collect::<Vec<_>>();
+
+        let block = |body: Vec<Expression>| {
+            Expression::Function(Function::new(block_signature(), None, body))
+        };
+        let last_returns = body.last().is_some_and(|last| match last {
+            Expression::Call(call) => call.function.try_read().is_ok_and(|function| {
+                matches!(function.body, FunctionBody::Intrinsic(Intrinsic::Break(0)))
+            }),
+            _ => false,
+        });
+        if copier.returns == 1 && last_returns && return_type != Type::Unit {
+            // Single `return` at the end is just the value of the body
+            if let Some(Expression::Call(mut call)) = body.pop() {
+                body.push(call.args.pop().unwrap());
+            }
+        } else if copier.returns > 0 {
+            if !body.last().is_some_and(diverges) {
+                body.push(Expression::Call(Call::new(
+                    intrinsics.r#break(0, Type::Unit),
+                    Vec::new(),
+                )));
+            }
+            body = vec![Expression::Call(Call::new(
+                intrinsics.r#loop(return_type.clone()),
+                vec![block(body), block(Vec::new())],
+            ))];
+        }
+
+        Function {
+            parameters,
+            span: function.span,
+            ..Function::new(
+                FunctionSignature::new(names, return_type, CallingConvention::Transparent),
+                None,
+                body,
+            )
+        }
+    }
+}
+
+/// Number of expressions in the body of a function
+pub fn cost(function: &Function) -> usize {
+    struct Cost(usize);
+    impl Visitor for Cost {
+        fn visit_expression(&mut self, expression: &Expression) {
+            self.0 += 1;
+            expression.walk(self);
+        }
+    }
+
+    let mut cost = Cost(0);
+    function.walk(&mut cost);
+    cost.0
+}
+
+/// Does the body of the function call a function with this name?
+fn calls(function: &Function, name: &str) -> bool {
+    struct Calls<'a>(&'a str, bool);
+    impl Visitor for Calls<'_> {
+        fn visit_call(&mut self, call: &Call) {
+            // Function, that is being rewritten, is locked, it can't be this one
+            self.1 |= call
+                .function
+                .try_read()
+                .is_ok_and(|function| function.name.as_deref() == Some(self.0));
+            call.walk(self);
+        }
+    }
+
+    let mut calls = Calls(name, false);
+    function.walk(&mut calls);
+    calls.1
+}
+
+fn block_signature() -> FunctionSignature {
+    FunctionSignature::new(Vec::new(), Type::Unit, CallingConvention::Transparent)
+}
+
+struct Rewriter<'a> {
+    inliner: &'a mut Inliner,
+    intrinsics: &'a Intrinsics,
+}
+
+impl VisitorMut for Rewriter<'_> {
+    fn visit_call(&mut self, call: &mut Call) {
+        call.walk_mut(self);
+        // Recursive call to the function, that is being rewritten
+        let Ok(function) = call.function.try_read() else {
+            return;
+        };
+        if function.signature.calling_convention == CallingConvention::Transparent {
+            drop(function);
+            // Already inline, but the calls in the body might not be
+            call.function.write().unwrap().walk_mut(self);
+        } else if self.inliner.should_inline(&function) {
+            let inlined = self.inliner.inline(self.intrinsics, &function);
+            drop(function);
+            call.function = std::sync::Arc::new(std::sync::RwLock::new(inlined));
+            call.type_arguments.clear();
+        }
+    }
+}
+
+/// Deep copy of a function body with fresh variables
+struct Copier<'a> {
+    intrinsics: &'a Intrinsics,
+    suffix: String,
+    variables: HashMap<*const std::sync::RwLock<crate::Variable>, ArcLock<crate::Variable>>,
+    /// Return type of the inlined function, [None] inside of nested functions,
+    /// where `return` returns from the nested function
+    return_type: Option<Type>,
+    /// Number of loops around the expression, that is being copied
+    depth: usize,
+    /// Number of rewritten `return`s
+    returns: usize,
+}
+
+impl Copier<'_> {
+    fn variable(&mut self, variable: &ArcLock<crate::Variable>) -> ArcLock<crate::Variable> {
+        self.variables
+            .entry(std::sync::Arc::as_ptr(variable))
+            .or_insert_with(|| {
+                let variable = variable.read().unwrap();
+                std::sync::Arc::new(std::sync::RwLock::new(crate::Variable {
+                    name: variable
+                        .name
+                        .as_ref()
+                        .map(|name| format!("{}{}", name, self.suffix)),
+                    r#type: variable.r#type.clone(),
+                    span: variable.span,
+                }))
+            })
+            .clone()
+    }
+
+    fn function(&mut self, function: &Function) -> Function {
+        let return_type = self.return_type.take();
+        let depth = self.depth;
+        if function.signature.calling_convention == CallingConvention::Transparent {
+            self.return_type = return_type.clone();
+        }
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| self.variable(parameter))
+            .collect();
+        let body = match &function.body {
+            FunctionBody::Block(body) => FunctionBody::Block(
+                body.iter()
+                    .map(|expression| self.copy(expression))
+                    .collect(),
+            ),
+            FunctionBody::Intrinsic(intrinsic) => FunctionBody::Intrinsic(*intrinsic),
+        };
+        self.return_type = return_type;
+        self.depth = depth;
+        Function {
+            signature: function.signature.clone(),
+            name: function.name.clone(),
+            parameters,
+            body,
+            span: function.span,
+        }
+    }
+
+    fn call(&mut self, call: &Call) -> Expression {
+        // Function, that is being rewritten, is called like any other
+        let Ok(function) = call.function.try_read() else {
+            return Expression::Call(Call {
+                function: call.function.clone(),
+                args: call.args.iter().map(|arg| self.copy(arg)).collect(),
+                type_arguments: call.type_arguments.clone(),
+                span: call.span,
+            });
+        };
+        let callee = match &function.body {
+            FunctionBody::Intrinsic(Intrinsic::Return) if self.return_type.is_some() => {
+                return self.r#return(call);
+            }
+            FunctionBody::Intrinsic(Intrinsic::Loop) => {
+                self.depth += 1;
+                let args = call.args.iter().map(|arg| self.copy(arg)).collect();
+                self.depth -= 1;
+                return Expression::Call(Call {
+                    function: call.function.clone(),
+                    args,
+                    type_arguments: call.type_arguments.clone(),
+                    span: call.span,
+                });
+            }
+            FunctionBody::Block(_)
+                if function.signature.calling_convention == CallingConvention::Transparent =>
+            {
+                std::sync::Arc::new(std::sync::RwLock::new(self.function(&function)))
+            }
+            _ => call.function.clone(),
+        };
+        Expression::Call(Call {
+            function: callee,
+            args: call.args.iter().map(|arg| self.copy(arg)).collect(),
+            type_arguments: call.type_arguments.clone(),
+            span: call.span,
+        })
+    }
+
+    /// `return value` becomes `break value` out of the loop around the body
+    fn r#return(&mut self, call: &Call) -> Expression {
+        self.returns += 1;
+        let return_type = self.return_type.clone().unwrap();
+        let mut args = call
+            .args
+            .iter()
+            .map(|arg| self.copy(arg))
+            .collect::<Vec<_>>();
+        let r#break = self.intrinsics.r#break(self.depth, return_type.clone());
+        if return_type != Type::Unit {
+            return Expression::Call(Call {
+                span: call.span,
+                ..Call::new(r#break, args)
+            });
+        }
+        // Unit breaks take no value, but it might still have side effects
+        args.push(Expression::Call(Call::new(r#break, Vec::new())));
+        let signature =
+            FunctionSignature::new(Vec::new(), Type::Never, CallingConvention::Transparent);
+        Expression::Call(Call {
+            span: call.span,
+            ..Call::new(
+                std::sync::Arc::new(std::sync::RwLock::new(Function::new(signature, None, args))),
+                Vec::new(),
+            )
+        })
+    }
+
+    fn copy(&mut self, expression: &Expression) -> Expression {
+        match expression {
+            Expression::Literal(literal) => Expression::Literal(literal.clone()),
+            Expression::Variable(variable) => {
+                Expression::Variable(crate::Spanned::new(self.variable(variable), variable.span))
+            }
+            Expression::Function(function) => Expression::Function(self.function(function)),
+            Expression::Call(call) => self.call(call),
+            Expression::TraitCall(call) => Expression::TraitCall(crate::expression::TraitCall {
+                r#trait: call.r#trait.clone(),
+                method: call.method.clone(),
+                args: call.args.iter().map(|arg| self.copy(arg)).collect(),
+                self_type: call.self_type.clone(),
+                span: call.span,
+            }),
+            Expression::Field(field) => Expression::Field(FieldAccess {
+                value: Box::new(self.copy(&field.value)),
+                name: field.name.clone(),
+                field: field.field.clone(),
+                span: field.span,
+            }),
+            Expression::Index(index) => Expression::Index(Index {
+                value: Box::new(self.copy(&index.value)),
+                index: Box::new(self.copy(&index.index)),
+                index_type: index.index_type.clone(),
+                checked: index.checked,
+                container: index.container.clone(),
+                span: index.span,
+            }),
+            Expression::Comptime(comptime) => Expression::Comptime(Comptime {
+                body: comptime
+                    .body
+                    .iter()
+                    .map(|expression| self.copy(expression))
+                    .collect(),
+                r#type: comptime.r#type.clone(),
+                span: comptime.span,
+            }),
+            Expression::Error(id) => Expression::Error(*id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{Interpreter, Value};
+    use assert2::*;
+
+    const UNIT: &str = "\
+const clamp = fn clamp(x: i32) -> i32 inline {
+    (fn (cond: bool, then: fn () -> () transparent, else: fn () -> () transparent) -> () transparent = branch)((fn (lhs: i32, rhs: i32) -> bool transparent = lt)(x: i32, 0i32), fn () -> () transparent {
+        (fn (value: i32) -> ! transparent = return)(0i32);
+    }, fn () -> () transparent {
+    });
+    (fn (value: i32) -> ! transparent = return)(x: i32);
+};
+const double = fn double(x: i32) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = add)(x: i32, x: i32));
+};
+const main = fn main(x: i32) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = add)(clamp(x: i32), double(x: i32)));
+};
+";
+
+    fn run(symbols: &crate::Symbols, x: i128) -> Value {
+        Interpreter::new()
+            .run(symbols, "main", vec![Value::Integer(x, 32)])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_inline() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let mut symbols = crate::parser::parse_unit(&mut ctx, UNIT).unwrap();
+        let mut inliner = Inliner::default();
+        check!(inliner.run(&mut ctx, &mut symbols));
+        check!(inliner.inlined == 2);
+
+        let main = symbols["main"].to_string();
+        check!(!main.contains("clamp("));
+        check!(!main.contains("double("));
+        check!(main.contains("x_inline1: i32"));
+        check!(main.contains("x_inline2: i32"));
+        check!(main.contains("= break)(0i32)"));
+        check!(main.matches("= return)").count() == 1);
+
+        check!(run(&symbols, -5) == Value::Integer(-10, 32));
+        check!(run(&symbols, 5) == Value::Integer(15, 32));
+    }
+
+    #[test]
+    fn test_cost_model() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let symbols = crate::parser::parse_unit(&mut ctx, UNIT).unwrap();
+        let_assert!(crate::Symbol::Function(double) = &symbols["double"]);
+        let double = &*double.read().unwrap();
+        check!(cost(double) == 4);
+        check!(Inliner::default().should_inline(double));
+        let strict = Inliner {
+            max_cost: 3,
+            ..Inliner::default()
+        };
+        check!(!strict.should_inline(double));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::Expression;
+
+/// See [ConstantFolding]
+pub mod constant_folding;
+pub use constant_folding::ConstantFolding;
+/// See [DeadCode]
+pub mod dead_code;
+pub use dead_code::DeadCode;
+/// See [EmptyBranches]
+pub mod empty_branches;
+pub use empty_branches::EmptyBranches;
+/// See [Inliner]
+pub mod inline;
+pub use inline::Inliner;
+
+/// Transformation of the IR, run after type inference
+pub trait Pass {
+    /// Name of the pass, used to configure the pipeline and in dumps
+    fn name(&self) -> &'static str;
+
+    /// Run the pass over the symbols of a unit, returns true if anything changed
+    fn run(&mut self, ctx: &mut crate::TypeInferenceContext, symbols: &mut crate::Symbols) -> bool;
+}
+
+/// Create a pass by its name, see [Pass::name]
+pub fn pass(name: &str) -> Option<Box<dyn Pass>> {
+    Some(match name {
+        "constant-folding" => Box::new(ConstantFolding),
+        "dead-code" => Box::new(DeadCode),
+        "empty-branches" => Box::new(EmptyBranches),
+        "inline" => Box::new(Inliner::default()),
+        "verify" => Box::new(crate::verify::Verify::default()),
+        _ => return None,
+    })
+}
+
+/// Names of the passes in the default pipeline, in order
+pub const DEFAULT_PIPELINE: &[&str] =
+    &["inline", "constant-folding", "dead-code", "empty-branches"];
+
+/// Runs passes in order, measuring the time they take
+/// and dumping the IR after them if asked to
+#[derive(Default)]
+pub struct PassManager {
+    /// Passes to run, in order
+    pub passes: Vec<Box<dyn Pass>>,
+    /// Names of the passes to dump the unit after, `all` dumps after every pass
+    pub dump_after: Vec<String>,
+    /// Time each pass took during the last [PassManager::run]
+    pub timings: Vec<(&'static str, std::time::Duration)>,
+    /// Units, printed after the passes from [PassManager::dump_after]
+    pub dumps: Vec<(&'static str, String)>,
+}
+
+impl PassManager {
+    /// Create a pass manager without any passes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a pass manager, running passes from [DEFAULT_PIPELINE]
+    pub fn default_pipeline() -> Self {
+        Self::from_names(DEFAULT_PIPELINE.iter().copied()).unwrap()
+    }
+
+    /// Create a pass manager from pass names, see [pass].
+    /// Returns the unknown name on error
+    pub fn from_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut manager = Self::new();
+        for name in names {
+            manager
+                .passes
+                .push(pass(name).ok_or_else(|| name.to_owned())?);
+        }
+        Ok(manager)
+    }
+
+    /// Add a pass to the end of the pipeline
+    pub fn add(&mut self, pass: impl Pass + 'static) -> &mut Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// Run all passes once, in order. Returns true if any of them changed the unit
+    pub fn run(
+        &mut self,
+        ctx: &mut crate::TypeInferenceContext,
+        symbols: &mut crate::Symbols,
+    ) -> bool {
+        self.timings.clear();
+        self.dumps.clear();
+        let mut changed = false;
+        for pass in &mut self.passes {
+            let name = pass.name();
+            let start = std::time::Instant::now();
+            changed |= pass.run(ctx, symbols);
+            self.timings.push((name, start.elapsed()));
+            if self
+                .dump_after
+                .iter()
+                .any(|dump| dump == name || dump == "all")
+            {
+                self.dumps
+                    .push((name, crate::parser::unit_to_string(ctx, symbols)));
+            }
+        }
+        changed
+    }
+
+    /// Human-readable table of [PassManager::timings]
+    pub fn timings_report(&self) -> String {
+        let width = self
+            .timings
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        let mut report = String::new();
+        for (name, duration) in &self.timings {
+            report.push_str(&format!("{:width$}  {:?}\n", name, duration));
+        }
+        let total = self
+            .timings
+            .iter()
+            .map(|(_, duration)| *duration)
+            .sum::<std::time::Duration>();
+        report.push_str(&format!("{:width$}  {:?}\n", "total", total));
+        report
+    }
+}
+
+/// Is this expression a call, that never returns, f.e. `return` or `break`?
+/// Recursive calls to the function, that is being rewritten, are assumed to return
+pub fn diverges(expression: &Expression) -> bool {
+    match expression {
+        Expression::Call(call) => call
+            .function
+            .try_read()
+            .is_ok_and(|function| *function.signature.return_type == crate::Type::Never),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    const UNIT: &str = "\
+const main = fn main() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = mul)(6i32, 7i32));
+    (fn (value: i32) -> ! transparent = return)(0i32);
+};
+";
+
+    #[test]
+    fn test_pipeline() {
+        let_assert!(Err(name) = PassManager::from_names(["dead-code", "inline-everything"]));
+        check!(name == "inline-everything");
+
+        let mut ctx = crate::TypeInferenceContext::new();
+        let mut symbols = crate::parser::parse_unit(&mut ctx, UNIT).unwrap();
+        let mut manager = PassManager::default_pipeline();
+        manager.dump_after = vec!["constant-folding".to_owned()];
+        check!(manager.run(&mut ctx, &mut symbols));
+        check!(manager.timings.len() == DEFAULT_PIPELINE.len());
+        check!(manager
+            .timings_report()
+            .lines()
+            .last()
+            .unwrap()
+            .starts_with("total"));
+
+        let_assert!([("constant-folding", dump)] = &manager.dumps[..]);
+        check!(dump.contains("return)(42i32);\n    (fn"));
+        check!(
+            crate::parser::unit_to_string(&ctx, &symbols)
+                == "\
+const main = fn main() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)(42i32);
+};
+"
+        );
+        check!(!manager.run(&mut ctx, &mut symbols));
+    }
+}
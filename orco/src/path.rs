@@ -0,0 +1,80 @@
+/// Path to a symbol or a module, `a::b::c`.
+/// Paths, that are resolved, might start with `crate`, `self` or `super`,
+/// see [crate::Module::resolve]. Empty path is the root module
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Path(pub Vec<String>);
+
+impl Path {
+    /// Path of the root module
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Path to an item of this module
+    pub fn join(&self, name: impl Into<String>) -> Self {
+        let mut path = self.clone();
+        path.0.push(name.into());
+        path
+    }
+
+    /// Path of the module, that contains this item. [None] for the root module
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.0.split_last()?;
+        Some(Self(parent.to_vec()))
+    }
+
+    /// Last segment of the path. [None] for the root module
+    pub fn name(&self) -> Option<&str> {
+        self.0.last().map(String::as_str)
+    }
+
+    /// Is this path the root module?
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Is this path inside of the module (or the module itself)?
+    pub fn starts_with(&self, module: &Path) -> bool {
+        self.0.starts_with(&module.0)
+    }
+}
+
+impl std::fmt::Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_root() {
+            return write!(f, "crate");
+        }
+        write!(f, "{}", self.0.join("::"))
+    }
+}
+
+impl std::str::FromStr for Path {
+    type Err = ();
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let segments = path.split("::").map(str::to_owned).collect::<Vec<_>>();
+        if segments.iter().any(String::is_empty) {
+            return Err(());
+        }
+        Ok(Self(segments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    #[test]
+    fn test_path() {
+        let_assert!(Ok(path) = "a::b::c".parse::<Path>());
+        check!(path.to_string() == "a::b::c");
+        check!(path.name() == Some("c"));
+        check!(path.parent() == Some(Path::new().join("a").join("b")));
+        check!(path.starts_with(&"a".parse().unwrap()));
+        check!(Path::new().to_string() == "crate");
+        check!(Path::new().parent().is_none());
+        check!("a::::b".parse::<Path>().is_err());
+        check!("".parse::<Path>().is_err());
+    }
+}
@@ -0,0 +1,358 @@
+use super::{Database, Memo, Query, Report, Value};
+use crate::diagnostics::Severity;
+use std::io::{Error, ErrorKind, Result};
+
+/// First line of a cache file, bumped when the format changes
+const HEADER: &str = "orco query cache 2\n";
+
+impl Database {
+    /// Write the sources and the memoized results to a file,
+    /// so that the next run can reuse them, see [Database::load]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let mut writer = Writer(HEADER.to_owned());
+        writer.number(self.revision);
+        let mut files = self.files.iter().collect::<Vec<_>>();
+        files.sort_by_key(|(name, _)| *name);
+        writer.number(files.len() as _);
+        for (name, (source, changed_at)) in files {
+            writer.string(name);
+            writer.string(source);
+            writer.number(*changed_at);
+        }
+        match &self.settings {
+            Some((settings, changed_at)) => {
+                writer.number(1);
+                writer.string(settings);
+                writer.number(*changed_at);
+            }
+            None => writer.number(0),
+        }
+        let mut memos = self.memos.iter().collect::<Vec<_>>();
+        memos.sort_by_key(|(query, _)| *query);
+        writer.number(memos.len() as _);
+        for (query, memo) in memos {
+            writer.query(query);
+            writer.value(&memo.value);
+            writer.number(memo.changed_at);
+            writer.number(memo.verified_at);
+            writer.number(memo.dependencies.len() as _);
+            for dependency in &memo.dependencies {
+                writer.query(dependency);
+            }
+        }
+        std::fs::write(path, writer.0)
+    }
+
+    /// Read a database, written by [Database::save]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let Some(text) = text.strip_prefix(HEADER) else {
+            return Err(invalid("unknown cache format"));
+        };
+        let mut reader = Reader(text);
+        let mut db = Database::new();
+        db.revision = reader.number()?;
+        for _ in 0..reader.number()? {
+            let name = reader.string()?;
+            let source = reader.string()?;
+            db.files.insert(name, (source, reader.number()?));
+        }
+        db.settings = match reader.number()? {
+            0 => None,
+            _ => Some((reader.string()?, reader.number()?)),
+        };
+        for _ in 0..reader.number()? {
+            let query = reader.query()?;
+            let value = reader.value()?;
+            let changed_at = reader.number()?;
+            let verified_at = reader.number()?;
+            let dependencies = (0..reader.number()?)
+                .map(|_| reader.query())
+                .collect::<Result<_>>()?;
+            db.memos.insert(
+                query,
+                Memo {
+                    value,
+                    changed_at,
+                    verified_at,
+                    dependencies,
+                },
+            );
+        }
+        if !reader.0.is_empty() {
+            return Err(invalid("trailing data"));
+        }
+        Ok(db)
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid query cache: {}", message),
+    )
+}
+
+/// Diagnostic codes are static, so the loaded ones are leaked, each one once
+fn intern(code: String) -> &'static str {
+    static CODES: std::sync::Mutex<Vec<&'static str>> = std::sync::Mutex::new(Vec::new());
+    let mut codes = CODES.lock().unwrap();
+    if let Some(interned) = codes.iter().find(|interned| **interned == code) {
+        return interned;
+    }
+    let interned = Box::leak(code.into_boxed_str());
+    codes.push(interned);
+    interned
+}
+
+/// Every string is written as `length:text`, so that it can contain anything.
+/// Generated code is written as a hex string
+struct Writer(String);
+
+impl Writer {
+    fn string(&mut self, string: &str) {
+        self.0.push_str(&format!("{}:{}\n", string.len(), string));
+    }
+
+    fn number(&mut self, number: u64) {
+        self.string(&number.to_string());
+    }
+
+    fn query(&mut self, query: &Query) {
+        let (kind, file, name) = match query {
+            Query::Settings => return self.string("settings"),
+            Query::Source(file) => ("source", file, None),
+            Query::Items(file) => ("items", file, None),
+            Query::Names(file) => ("names", file, None),
+            Query::Item(file, name) => ("item", file, Some(name)),
+            Query::Lower(file, name) => ("lower", file, Some(name)),
+            Query::Infer(file, name) => ("infer", file, Some(name)),
+            Query::Codegen(file, name) => ("codegen", file, Some(name)),
+        };
+        self.string(kind);
+        self.string(file);
+        if let Some(name) = name {
+            self.string(name);
+        }
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Text(text) => {
+                self.string("text");
+                self.string(text);
+            }
+            Value::Items(items, reports) => {
+                self.string("items");
+                self.number(items.len() as _);
+                for (name, range) in items {
+                    self.string(name);
+                    self.number(range.start as _);
+                    self.number(range.end as _);
+                }
+                self.reports(reports);
+            }
+            Value::Names(names) => {
+                self.string("names");
+                self.number(names.len() as _);
+                for name in names {
+                    self.string(name);
+                }
+            }
+            Value::Unit(unit, offset) => {
+                self.string("unit");
+                self.string(unit);
+                self.number(*offset as _);
+            }
+            Value::Symbol(ir, reports) => {
+                self.string("symbol");
+                self.string(ir);
+                self.reports(reports);
+            }
+            Value::Code(code) => {
+                self.string("code");
+                let hex = code.iter().map(|byte| format!("{:02x}", byte));
+                self.string(&hex.collect::<String>());
+            }
+        }
+    }
+
+    fn reports(&mut self, reports: &[Report]) {
+        self.number(reports.len() as _);
+        for report in reports {
+            self.string(&report.severity.to_string());
+            self.string(report.code.unwrap_or_default());
+            self.string(&report.message);
+            self.number(report.labels.len() as _);
+            for (range, message) in &report.labels {
+                self.number(range.start as _);
+                self.number(range.end as _);
+                match message {
+                    Some(message) => {
+                        self.number(1);
+                        self.string(message);
+                    }
+                    None => self.number(0),
+                }
+            }
+            self.number(report.notes.len() as _);
+            for note in &report.notes {
+                self.string(note);
+            }
+        }
+    }
+}
+
+struct Reader<'a>(&'a str);
+
+impl Reader<'_> {
+    fn string(&mut self) -> Result<String> {
+        let (length, rest) = self
+            .0
+            .split_once(':')
+            .ok_or_else(|| invalid("expected a string"))?;
+        let length = length
+            .parse::<usize>()
+            .map_err(|_| invalid("invalid string length"))?;
+        let (string, rest) = rest
+            .get(..length)
+            .zip(rest.get(length..))
+            .ok_or_else(|| invalid("string is too long"))?;
+        self.0 = rest
+            .strip_prefix('\n')
+            .ok_or_else(|| invalid("expected a new line"))?;
+        Ok(string.to_owned())
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T> {
+        self.string()?
+            .parse()
+            .map_err(|_| invalid("expected a number"))
+    }
+
+    fn query(&mut self) -> Result<Query> {
+        let kind = self.string()?;
+        if kind == "settings" {
+            return Ok(Query::Settings);
+        }
+        let file = self.string()?;
+        Ok(match kind.as_str() {
+            "source" => Query::Source(file),
+            "items" => Query::Items(file),
+            "names" => Query::Names(file),
+            "item" => Query::Item(file, self.string()?),
+            "lower" => Query::Lower(file, self.string()?),
+            "infer" => Query::Infer(file, self.string()?),
+            "codegen" => Query::Codegen(file, self.string()?),
+            _ => return Err(invalid("unknown query")),
+        })
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        Ok(match self.string()?.as_str() {
+            "text" => Value::Text(self.string()?),
+            "items" => {
+                let items = (0..self.number()?)
+                    .map(|_| Ok((self.string()?, self.number()?..self.number()?)))
+                    .collect::<Result<_>>()?;
+                Value::Items(items, self.reports()?)
+            }
+            "names" => Value::Names(
+                (0..self.number()?)
+                    .map(|_| self.string())
+                    .collect::<Result<_>>()?,
+            ),
+            "unit" => Value::Unit(self.string()?, self.number()?),
+            "symbol" => Value::Symbol(self.string()?, self.reports()?),
+            "code" => {
+                let hex = self.string()?;
+                let code = (0..hex.len())
+                    .step_by(2)
+                    .map(|index| {
+                        hex.get(index..index + 2)
+                            .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                            .ok_or_else(|| invalid("invalid code"))
+                    })
+                    .collect::<Result<_>>()?;
+                Value::Code(code)
+            }
+            _ => return Err(invalid("unknown value")),
+        })
+    }
+
+    fn reports(&mut self) -> Result<Vec<Report>> {
+        (0..self.number()?)
+            .map(|_| {
+                let severity = match self.string()?.as_str() {
+                    "error" => Severity::Error,
+                    "warning" => Severity::Warning,
+                    "note" => Severity::Note,
+                    "help" => Severity::Help,
+                    _ => return Err(invalid("unknown severity")),
+                };
+                let code = Some(self.string()?)
+                    .filter(|code| !code.is_empty())
+                    .map(intern);
+                let message = self.string()?;
+                let labels = (0..self.number()?)
+                    .map(|_| {
+                        let range = self.number()?..self.number()?;
+                        let message = match self.number()? {
+                            0 => None,
+                            _ => Some(self.string()?),
+                        };
+                        Ok((range, message))
+                    })
+                    .collect::<Result<_>>()?;
+                let notes = (0..self.number()?)
+                    .map(|_| self.string())
+                    .collect::<Result<_>>()?;
+                Ok(Report {
+                    severity,
+                    code,
+                    message,
+                    labels,
+                    notes,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    #[test]
+    fn test_cache() {
+        let source = super::super::tests::UNIT.replace("twice(21i32)", "twice(true)");
+        let mut db = Database::new();
+        db.set_source("unit.orco", source.clone());
+        db.set_backend(super::super::tests::Listing("a"));
+        let unit = db.unit("unit.orco");
+        let code = db.code("unit.orco");
+        let mut ctx = crate::TypeInferenceContext::new();
+        let id = ctx.source_map.add_file("unit.orco", source.clone());
+        let diagnostics = db.diagnostics("unit.orco", id);
+
+        let path = std::env::temp_dir().join(format!("orco-query-{}.cache", std::process::id()));
+        db.save(&path).unwrap();
+        let mut loaded = Database::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Same source, nothing is computed again
+        loaded.set_source("unit.orco", source);
+        loaded.set_backend(super::super::tests::Listing("a"));
+        check!(loaded.revision() == db.revision());
+        check!(loaded.unit("unit.orco") == unit);
+        check!(loaded.code("unit.orco") == code);
+        check!(loaded.diagnostics("unit.orco", id) == diagnostics);
+        check!(loaded.executed.is_empty());
+
+        std::fs::write(&path, "not a cache").unwrap();
+        let_assert!(Err(error) = Database::load(&path));
+        std::fs::remove_file(&path).unwrap();
+        check!(error.kind() == ErrorKind::InvalidData);
+    }
+}
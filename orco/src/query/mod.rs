@@ -0,0 +1,762 @@
+use crate::diagnostics::{Diagnostic, Label, Severity};
+use crate::parser::ParseError;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
+
+/// On-disk cache of a [Database], see [Database::save]
+pub mod cache;
+
+/// Revision of the inputs, bumped on every change, see [Database::set_source]
+pub type Revision = u64;
+
+/// Queries of the [Database]. Files are referred to by their names
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Query {
+    /// Source of a file, the input of all the other queries
+    Source(String),
+    /// Top-level items of a file with their byte ranges, see [crate::parser::items]
+    Items(String),
+    /// Names of the items of a file. Unlike [Query::Items],
+    /// it doesn't change when the code is edited inside of an item
+    Names(String),
+    /// Text of an item, by file and item name
+    Item(String, String),
+    /// Unit, that an item is built from: the item itself and every item
+    /// it refers to, directly or not, in the source order, see [Value::Unit]
+    Lower(String, String),
+    /// Item with types inferred, see [Value::Symbol]
+    Infer(String, String),
+    /// Settings of the [Backend], an input like [Query::Source]
+    Settings,
+    /// Code of a function, generated by the [Backend], see [Value::Code]
+    Codegen(String, String),
+}
+
+/// Result of a [Query]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    /// Source code or textual IR
+    Text(String),
+    /// See [Query::Items]. Syntax errors point into the file
+    Items(Vec<(String, Range<usize>)>, Vec<Report>),
+    /// See [Query::Names]
+    Names(Vec<String>),
+    /// Source of a unit and the offset of the item, it was lowered for, in it
+    Unit(String, usize),
+    /// Item, printed as textual IR, empty if it can't be compiled.
+    /// Diagnostics point into the text of the item
+    Symbol(String, Vec<Report>),
+    /// Output of [Backend::codegen], empty if there is no backend
+    /// or if the item is not a function without errors
+    Code(Vec<u8>),
+}
+
+/// Language of the files with some extension, see [Database::add_frontend].
+/// Files of other languages hold the textual IR
+pub trait Frontend {
+    /// Top-level items with their byte ranges, see [Query::Items]
+    fn items(&self, source: &str) -> Result<Vec<(String, Range<usize>)>, ParseError>;
+
+    /// Names of the items, that the text of an item might refer to
+    fn mentions(&self, text: &str, names: &BTreeSet<String>) -> BTreeSet<String>;
+
+    /// Build the symbols of a unit, see [Query::Lower]. Diagnostics go into the context
+    fn build(
+        &self,
+        ctx: &mut crate::TypeInferenceContext,
+        unit: &str,
+    ) -> Result<crate::Symbols, ParseError>;
+}
+
+/// Textual IR, see [crate::parser]
+struct Ir;
+
+impl Frontend for Ir {
+    fn items(&self, source: &str) -> Result<Vec<(String, Range<usize>)>, ParseError> {
+        crate::parser::items(source)
+    }
+
+    fn mentions(&self, text: &str, names: &BTreeSet<String>) -> BTreeSet<String> {
+        mentions(text, names)
+    }
+
+    fn build(
+        &self,
+        ctx: &mut crate::TypeInferenceContext,
+        unit: &str,
+    ) -> Result<crate::Symbols, ParseError> {
+        crate::parser::parse_unit(ctx, unit)
+    }
+}
+
+/// Code generator of a [Database], see [Database::set_backend]
+pub trait Backend {
+    /// Everything, that the generated code depends on besides the IR,
+    /// like the passes to run. Code is generated again when they change
+    fn settings(&self) -> String;
+
+    /// Generate the code of a function. `symbols` are the function and the items
+    /// it refers to, with the types inferred. The output is cached as is
+    fn codegen(
+        &self,
+        ctx: &mut crate::TypeInferenceContext,
+        symbols: &mut crate::Symbols,
+        name: &str,
+    ) -> Vec<u8>;
+}
+
+/// [Diagnostic], detached from a [crate::SourceMap], so that it can be cached.
+/// Labels are byte ranges relative to the text, the query was run on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// See [Diagnostic::severity]
+    pub severity: Severity,
+    /// See [Diagnostic::code]
+    pub code: Option<&'static str>,
+    /// See [Diagnostic::message]
+    pub message: String,
+    /// Byte ranges with their messages
+    pub labels: Vec<(Range<usize>, Option<String>)>,
+    /// See [Diagnostic::notes]
+    pub notes: Vec<String>,
+}
+
+impl Report {
+    /// Detach a diagnostic, labels of other files are dropped
+    pub fn new(diagnostic: &Diagnostic, file: Option<crate::span::FileId>) -> Self {
+        Self {
+            severity: diagnostic.severity,
+            code: diagnostic.code,
+            message: diagnostic.message.clone(),
+            labels: diagnostic
+                .labels
+                .iter()
+                .filter(|label| Some(label.span.file) == file)
+                .map(|label| (label.span.range(), label.message.clone()))
+                .collect(),
+            notes: diagnostic.notes.clone(),
+        }
+    }
+
+    /// Attach the report to a file, moving its labels by `offset` bytes
+    pub fn diagnostic(&self, file: crate::span::FileId, offset: usize) -> Diagnostic {
+        Diagnostic {
+            severity: self.severity,
+            code: self.code,
+            message: self.message.clone(),
+            labels: self
+                .labels
+                .iter()
+                .map(|(range, message)| Label {
+                    span: crate::Span::new(file, range.start + offset..range.end + offset),
+                    message: message.clone(),
+                })
+                .collect(),
+            notes: self.notes.clone(),
+        }
+    }
+
+    /// Keep the labels inside of an item, making them relative to it
+    fn relative(mut self, item: &Range<usize>) -> Self {
+        self.labels
+            .retain(|(range, _)| item.start <= range.start && range.end <= item.end);
+        for (range, _) in &mut self.labels {
+            *range = range.start - item.start..range.end - item.start;
+        }
+        self
+    }
+}
+
+struct Memo {
+    value: Value,
+    /// Revision, in which the value last changed
+    changed_at: Revision,
+    /// Revision, in which the value was last known to be up to date
+    verified_at: Revision,
+    /// Queries, that were used to compute the value, in order
+    dependencies: Vec<Query>,
+}
+
+/// Demand-driven, incremental compilation of the textual IR.
+/// Query results are memoized, after an input changes only the queries,
+/// that depend on it, are recomputed. If a recomputed value is the same
+/// as before, queries that depend on it are not recomputed either
+#[derive(Default)]
+pub struct Database {
+    revision: Revision,
+    /// Sources of the files and the revisions they changed in
+    files: HashMap<String, (String, Revision)>,
+    /// [Backend::settings] and the revision they changed in, [None] before the backend is set
+    settings: Option<(String, Revision)>,
+    /// Frontends by the file extension, see [Database::add_frontend]
+    frontends: HashMap<String, Box<dyn Frontend>>,
+    /// See [Database::set_backend]
+    backend: Option<Box<dyn Backend>>,
+    memos: HashMap<Query, Memo>,
+    /// Dependencies of the queries, that are being computed, innermost last
+    stack: Vec<Vec<Query>>,
+    /// Queries, that were computed instead of being reused, in order
+    pub executed: Vec<Query>,
+}
+
+impl Database {
+    /// Create an empty database
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current revision
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    /// Set the source of a file. Starts a new revision, if it has changed
+    pub fn set_source(&mut self, file: &str, source: String) {
+        if self.files.get(file).is_some_and(|(old, _)| *old == source) {
+            return;
+        }
+        self.revision += 1;
+        self.files.insert(file.to_owned(), (source, self.revision));
+    }
+
+    /// Use a frontend for the files with an extension, like `c`.
+    /// Should be done before any query is run, results of other frontends are not invalidated
+    pub fn add_frontend(&mut self, extension: &str, frontend: impl Frontend + 'static) {
+        self.frontends
+            .insert(extension.to_owned(), Box::new(frontend));
+    }
+
+    /// Set the code generator. Starts a new revision, if its settings have changed
+    pub fn set_backend(&mut self, backend: impl Backend + 'static) {
+        let settings = backend.settings();
+        self.backend = Some(Box::new(backend));
+        if self
+            .settings
+            .as_ref()
+            .is_none_or(|(current, _)| *current != settings)
+        {
+            self.revision += 1;
+            self.settings = Some((settings, self.revision));
+        }
+    }
+
+    fn frontend(&self, file: &str) -> &dyn Frontend {
+        std::path::Path::new(file)
+            .extension()
+            .and_then(|extension| self.frontends.get(extension.to_str()?))
+            .map_or(&Ir, Box::as_ref)
+    }
+
+    /// Result of a query, computed or reused
+    pub fn get(&mut self, query: Query) -> Value {
+        if let Some(dependencies) = self.stack.last_mut() {
+            dependencies.push(query.clone());
+        }
+        if let Query::Source(file) = &query {
+            let source = self.files.get(file).map(|(source, _)| source.clone());
+            return Value::Text(source.unwrap_or_default());
+        }
+        if query == Query::Settings {
+            let settings = self.settings.as_ref().map(|(settings, _)| settings.clone());
+            return Value::Text(settings.unwrap_or_default());
+        }
+        self.update(&query);
+        self.memos[&query].value.clone()
+    }
+
+    /// Bring the memo of the query up to date, returns the revision its value changed in
+    fn update(&mut self, query: &Query) -> Revision {
+        if let Query::Source(file) = query {
+            return self
+                .files
+                .get(file)
+                .map_or(0, |(_, changed_at)| *changed_at);
+        }
+        if *query == Query::Settings {
+            return self
+                .settings
+                .as_ref()
+                .map_or(0, |(_, changed_at)| *changed_at);
+        }
+        if let Some(memo) = self.memos.get(query) {
+            if memo.verified_at == self.revision {
+                return memo.changed_at;
+            }
+            let verified_at = memo.verified_at;
+            let dependencies = memo.dependencies.clone();
+            if dependencies
+                .iter()
+                .all(|dependency| self.update(dependency) <= verified_at)
+            {
+                let memo = self.memos.get_mut(query).unwrap();
+                memo.verified_at = self.revision;
+                return memo.changed_at;
+            }
+        }
+
+        self.stack.push(Vec::new());
+        let value = self.execute(query);
+        let mut dependencies = self.stack.pop().unwrap();
+        let mut seen = std::collections::HashSet::new();
+        dependencies.retain(|dependency| seen.insert(dependency.clone()));
+        self.executed.push(query.clone());
+
+        let changed_at = match self.memos.get(query) {
+            Some(memo) if memo.value == value => memo.changed_at,
+            _ => self.revision,
+        };
+        self.memos.insert(
+            query.clone(),
+            Memo {
+                value,
+                changed_at,
+                verified_at: self.revision,
+                dependencies,
+            },
+        );
+        changed_at
+    }
+
+    fn execute(&mut self, query: &Query) -> Value {
+        match query {
+            Query::Source(_) | Query::Settings => unreachable!(),
+            Query::Items(file) => {
+                let source = self.text(Query::Source(file.clone()));
+                match self.frontend(file).items(&source) {
+                    Ok(items) => Value::Items(items, Vec::new()),
+                    Err(error) => Value::Items(Vec::new(), vec![syntax_error(error)]),
+                }
+            }
+            Query::Names(file) => {
+                let Value::Items(items, _) = self.get(Query::Items(file.clone())) else {
+                    unreachable!()
+                };
+                Value::Names(items.into_iter().map(|(name, _)| name).collect())
+            }
+            Query::Item(file, name) => {
+                let source = self.text(Query::Source(file.clone()));
+                let Value::Items(items, _) = self.get(Query::Items(file.clone())) else {
+                    unreachable!()
+                };
+                let text = items
+                    .into_iter()
+                    .find(|(item, _)| item == name)
+                    .map(|(_, range)| source[range].to_owned());
+                Value::Text(text.unwrap_or_default())
+            }
+            Query::Lower(file, name) => self.lower(file, name),
+            Query::Infer(file, name) => self.infer(file, name),
+            Query::Codegen(file, name) => self.codegen(file, name),
+        }
+    }
+
+    fn text(&mut self, query: Query) -> String {
+        match self.get(query) {
+            Value::Text(text) => text,
+            _ => unreachable!(),
+        }
+    }
+
+    /// See [Query::Lower]
+    fn lower(&mut self, file: &str, name: &str) -> Value {
+        let Value::Names(names) = self.get(Query::Names(file.to_owned())) else {
+            unreachable!()
+        };
+        let known = names.iter().cloned().collect::<BTreeSet<_>>();
+        let item = self.text(Query::Item(file.to_owned(), name.to_owned()));
+        let mut pending = self.frontend(file).mentions(&item, &known);
+        let mut texts = BTreeMap::from([(name.to_owned(), item)]);
+        while let Some(mention) = pending.pop_first() {
+            if texts.contains_key(&mention) {
+                continue;
+            }
+            let text = self.text(Query::Item(file.to_owned(), mention.clone()));
+            pending.extend(self.frontend(file).mentions(&text, &known));
+            texts.insert(mention, text);
+        }
+
+        // Some languages need items to be declared before they are used
+        let mut unit = String::new();
+        let mut offset = 0;
+        for included in &names {
+            if let Some(text) = texts.remove(included) {
+                if included == name {
+                    offset = unit.len();
+                }
+                unit.push_str(&text);
+                unit.push('\n');
+            }
+        }
+        Value::Unit(unit, offset)
+    }
+
+    /// See [Query::Infer]
+    fn infer(&mut self, file: &str, name: &str) -> Value {
+        let Value::Unit(unit, offset) = self.get(Query::Lower(file.to_owned(), name.to_owned()))
+        else {
+            unreachable!()
+        };
+        let item = self.text(Query::Item(file.to_owned(), name.to_owned()));
+        let mut ctx = crate::TypeInferenceContext::new();
+        let id = ctx.source_map.add_file(file, unit.clone());
+        ctx.current_file = Some(id);
+
+        // Diagnostics of the other items are reported by their own queries
+        let item = offset..offset + item.len();
+        let inside = |report: &Report| {
+            report
+                .labels
+                .first()
+                .is_none_or(|(range, _)| item.start <= range.start && range.end <= item.end)
+        };
+        let mut symbols = match self.frontend(file).build(&mut ctx, &unit) {
+            Ok(symbols) => symbols,
+            Err(error) => {
+                let report = syntax_error(error);
+                let reports = if inside(&report) {
+                    vec![report.relative(&item)]
+                } else {
+                    Vec::new()
+                };
+                return Value::Symbol(String::new(), reports);
+            }
+        };
+        ctx.infer_symbols(symbols.values_mut());
+        let reports = ctx
+            .diagnostics
+            .iter()
+            .map(|diagnostic| Report::new(diagnostic, Some(id)))
+            .filter(inside)
+            .map(|report| report.relative(&item))
+            .collect();
+
+        let ir = match name.strip_prefix("struct ") {
+            Some(_) => match ctx.types.get(name) {
+                Some(crate::Type::Struct(r#struct)) => {
+                    format!("{};\n", r#struct.read().unwrap())
+                }
+                _ => String::new(),
+            },
+            None => match symbols.get(name) {
+                Some(symbol) => format!("const {} = {};\n", name, symbol),
+                None => String::new(),
+            },
+        };
+        Value::Symbol(ir, reports)
+    }
+
+    /// See [Query::Codegen]
+    fn codegen(&mut self, file: &str, name: &str) -> Value {
+        self.get(Query::Settings);
+        let Value::Symbol(ir, reports) = self.get(Query::Infer(file.to_owned(), name.to_owned()))
+        else {
+            unreachable!()
+        };
+        if ir.is_empty()
+            || self.backend.is_none()
+            || reports
+                .iter()
+                .any(|report| report.severity == Severity::Error)
+        {
+            return Value::Code(Vec::new());
+        }
+
+        // Inferred items are textual IR, no matter the frontend
+        let Value::Names(names) = self.get(Query::Names(file.to_owned())) else {
+            unreachable!()
+        };
+        let names = names.into_iter().collect::<BTreeSet<_>>();
+        let mut pending = mentions(&ir, &names);
+        let mut unit = ir;
+        let mut included = BTreeSet::from([name.to_owned()]);
+        while let Some(item) = pending.pop_first() {
+            if !included.insert(item.clone()) {
+                continue;
+            }
+            if let Value::Symbol(ir, _) = self.get(Query::Infer(file.to_owned(), item)) {
+                pending.extend(mentions(&ir, &names));
+                unit.push_str(&ir);
+            }
+        }
+
+        let mut ctx = crate::TypeInferenceContext::new();
+        let Ok(mut symbols) = crate::parser::parse_unit(&mut ctx, &unit) else {
+            return Value::Code(Vec::new());
+        };
+        if !matches!(symbols.get(name), Some(crate::Symbol::Function(_))) {
+            return Value::Code(Vec::new());
+        }
+        let backend = self.backend.as_ref().unwrap();
+        Value::Code(backend.codegen(&mut ctx, &mut symbols, name))
+    }
+
+    /// Textual IR of a file with all the types inferred, items are in the source order
+    pub fn unit(&mut self, file: &str) -> String {
+        let Value::Names(names) = self.get(Query::Names(file.to_owned())) else {
+            unreachable!()
+        };
+        let mut unit = String::new();
+        for name in names {
+            if let Value::Symbol(ir, _) = self.get(Query::Infer(file.to_owned(), name)) {
+                unit.push_str(&ir);
+            }
+        }
+        unit
+    }
+
+    /// Code of the functions of a file, see [Query::Codegen], in the source order
+    pub fn code(&mut self, file: &str) -> Vec<(String, Vec<u8>)> {
+        let Value::Names(names) = self.get(Query::Names(file.to_owned())) else {
+            unreachable!()
+        };
+        let mut code = Vec::new();
+        for name in names {
+            if let Value::Code(bytes) = self.get(Query::Codegen(file.to_owned(), name.clone())) {
+                if !bytes.is_empty() {
+                    code.push((name, bytes));
+                }
+            }
+        }
+        code
+    }
+
+    /// Diagnostics of a file, attached to it in a [crate::SourceMap]
+    pub fn diagnostics(&mut self, file: &str, id: crate::span::FileId) -> Vec<Diagnostic> {
+        let Value::Items(items, reports) = self.get(Query::Items(file.to_owned())) else {
+            unreachable!()
+        };
+        let mut diagnostics = reports
+            .iter()
+            .map(|report| report.diagnostic(id, 0))
+            .collect::<Vec<_>>();
+        for (name, range) in items {
+            if let Value::Symbol(_, reports) = self.get(Query::Infer(file.to_owned(), name)) {
+                diagnostics.extend(
+                    reports
+                        .iter()
+                        .map(|report| report.diagnostic(id, range.start)),
+                );
+            }
+        }
+        diagnostics
+    }
+}
+
+fn syntax_error(error: ParseError) -> Report {
+    Report {
+        severity: Severity::Error,
+        code: Some("E0000"),
+        message: error.message,
+        labels: vec![(error.range, Some("syntax error".to_owned()))],
+        notes: Vec::new(),
+    }
+}
+
+/// Names of the items, that the text refers to: paths and `struct Name`s
+fn mentions(text: &str, names: &BTreeSet<String>) -> BTreeSet<String> {
+    use crate::parser::lexer::Token;
+
+    let tokens = crate::parser::lexer::tokenize(text).unwrap_or_default();
+    let mut mentions = BTreeSet::new();
+    let mut path = String::new();
+    let mut previous = None;
+    for (token, _) in tokens {
+        match &token {
+            Token::Ident(ident) if previous == Some(Token::Ident("struct".to_owned())) => {
+                mentions.insert(format!("struct {}", ident));
+                path = ident.clone();
+            }
+            Token::Ident(ident) if previous == Some(Token::Punct("::")) => {
+                path.push_str(ident);
+            }
+            Token::Ident(ident) => path = ident.clone(),
+            Token::Punct("::") => path.push_str("::"),
+            _ => path.clear(),
+        }
+        mentions.extend(names.iter().filter(|name| refers_to(&path, name)).cloned());
+        previous = Some(token);
+    }
+    mentions.retain(|mention| names.contains(mention));
+    mentions
+}
+
+/// Might the path refer to the item? Paths are resolved from the module
+/// they are used in, see [crate::Module::resolve], so the item path only
+/// has to end with it. Imports are named `use a::b`, see [crate::parser::items]
+fn refers_to(path: &str, name: &str) -> bool {
+    let name = name.strip_prefix("use ").unwrap_or(name);
+    let path = path
+        .split("::")
+        .skip_while(|segment| matches!(*segment, "crate" | "self" | "super"))
+        .collect::<Vec<_>>();
+    !path.is_empty() && name.split("::").collect::<Vec<_>>().ends_with(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    pub(super) const UNIT: &str = "\
+struct Pair { a: i32, b: i32 };
+const sum = fn sum(pair: *const struct Pair) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = add)((fn (pointer: *const struct Pair) -> struct Pair transparent = load)(pair: *const struct Pair).a, (fn (pointer: *const struct Pair) -> struct Pair transparent = load)(pair: *const struct Pair).b));
+};
+const twice = fn twice(x: i32) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = mul)(x: i32, 2i32));
+};
+const main = fn main() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)(twice(21i32));
+};
+";
+
+    /// Names of the items, that were inferred since the last call
+    fn inferred(db: &mut Database) -> Vec<String> {
+        db.executed
+            .drain(..)
+            .filter_map(|query| match query {
+                Query::Infer(_, name) => Some(name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_incremental() {
+        let mut db = Database::new();
+        db.set_source("unit.orco", UNIT.to_owned());
+        check!(db.unit("unit.orco") == UNIT);
+        let mut ctx = crate::TypeInferenceContext::new();
+        let id = ctx.source_map.add_file("unit.orco", UNIT);
+        check!(db.diagnostics("unit.orco", id).is_empty());
+        check!(inferred(&mut db) == ["struct Pair", "sum", "twice", "main"]);
+
+        // Nothing changed
+        db.set_source("unit.orco", UNIT.to_owned());
+        db.unit("unit.orco");
+        check!(db.executed.is_empty());
+
+        // Only `twice` and `main`, which calls it, are inferred again
+        let edited = UNIT.replace("2i32));", "3i32));");
+        db.set_source("unit.orco", edited.clone());
+        check!(db.unit("unit.orco") == edited);
+        check!(inferred(&mut db) == ["twice", "main"]);
+
+        // Moving code around doesn't invalidate anything
+        let moved = format!("\n\n{}", edited);
+        db.set_source("unit.orco", moved);
+        db.unit("unit.orco");
+        check!(inferred(&mut db).is_empty());
+    }
+
+    /// Names of the functions, that code was generated for since the last call
+    fn generated(db: &mut Database) -> Vec<String> {
+        db.executed
+            .drain(..)
+            .filter_map(|query| match query {
+                Query::Codegen(_, name) => Some(name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Generates the settings, the name and the symbols, that the function was built with
+    pub(super) struct Listing(pub(super) &'static str);
+
+    impl Backend for Listing {
+        fn settings(&self) -> String {
+            self.0.to_owned()
+        }
+
+        fn codegen(
+            &self,
+            _ctx: &mut crate::TypeInferenceContext,
+            symbols: &mut crate::Symbols,
+            name: &str,
+        ) -> Vec<u8> {
+            let symbols = symbols.keys().cloned().collect::<Vec<_>>();
+            format!("{} {}: {}", self.0, name, symbols.join(", ")).into_bytes()
+        }
+    }
+
+    #[test]
+    fn test_codegen() {
+        let mut db = Database::new();
+        db.set_source("unit.orco", UNIT.to_owned());
+        check!(db.code("unit.orco").is_empty());
+
+        db.set_backend(Listing("a"));
+        let code = db
+            .code("unit.orco")
+            .into_iter()
+            .map(|(name, code)| (name, String::from_utf8(code).unwrap()))
+            .collect::<Vec<_>>();
+        check!(
+            code == [
+                ("sum".to_owned(), "a sum: sum".to_owned()),
+                ("twice".to_owned(), "a twice: twice".to_owned()),
+                ("main".to_owned(), "a main: main, twice".to_owned()),
+            ]
+        );
+        generated(&mut db);
+
+        // Only the edited function and the one, that calls it
+        db.set_source("unit.orco", UNIT.replace("2i32));", "3i32));"));
+        db.code("unit.orco");
+        check!(generated(&mut db) == ["twice", "main"]);
+
+        db.set_backend(Listing("a"));
+        db.code("unit.orco");
+        check!(generated(&mut db).is_empty());
+
+        db.set_backend(Listing("b"));
+        db.code("unit.orco");
+        check!(generated(&mut db) == ["struct Pair", "sum", "twice", "main"]);
+
+        // Errors are reported by the inference, there is no code to generate
+        db.set_source("unit.orco", UNIT.replace("twice(21i32)", "twice(true)"));
+        check!(db.code("unit.orco").len() == 2);
+    }
+
+    #[test]
+    fn test_modules() {
+        const UNIT: &str = "\
+const c::main = fn c::main() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)(one());
+};
+use c::one = super::a::b::one;
+const a::b::one = fn a::b::one() -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)(1i32);
+};
+";
+        let mut db = Database::new();
+        db.set_source("unit.orco", UNIT.to_owned());
+        let mut ctx = crate::TypeInferenceContext::new();
+        let id = ctx.source_map.add_file("unit.orco", UNIT);
+        check!(db.diagnostics("unit.orco", id).is_empty());
+        check!(db.unit("unit.orco").contains("(a::b::one())"));
+        inferred(&mut db);
+
+        // `c::main` calls `a::b::one` through the import
+        db.set_source("unit.orco", UNIT.replace("1i32", "2i32"));
+        db.unit("unit.orco");
+        check!(inferred(&mut db) == ["c::main", "use c::one", "a::b::one"]);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let source = UNIT.replace("twice(21i32)", "twice(true)");
+        let mut db = Database::new();
+        db.set_source("unit.orco", source.clone());
+        let mut ctx = crate::TypeInferenceContext::new();
+        let id = ctx.source_map.add_file("unit.orco", source.clone());
+        let diagnostics = db.diagnostics("unit.orco", id);
+        check!(diagnostics.len() == 1);
+        let_assert!([label] = &diagnostics[0].labels[..]);
+        check!(ctx.source_map.snippet(label.span) == "true");
+
+        db.set_source("unit.orco", "const x = ;".to_owned());
+        let_assert!([error] = &db.diagnostics("unit.orco", id)[..]);
+        check!(error.code == Some("E0000"));
+    }
+}
@@ -0,0 +1,188 @@
+/// Id of a file in a [SourceMap]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(std::num::NonZeroU32);
+
+impl FileId {
+    fn index(self) -> usize {
+        self.0.get() as usize - 1
+    }
+}
+
+/// Location in the source code: a file and a byte range in it.
+/// Just 12 bytes and `Copy`, `Option<Span>` is the same size
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    /// File this span points into
+    pub file: FileId,
+    /// Start byte offset
+    pub start: u32,
+    /// End byte offset (exclusive)
+    pub end: u32,
+}
+
+impl Span {
+    /// Create a new span from a file and a byte range
+    pub fn new(file: FileId, range: std::ops::Range<usize>) -> Self {
+        Self {
+            file,
+            start: range.start as _,
+            end: range.end as _,
+        }
+    }
+
+    /// Byte range of this span
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start as usize..self.end as usize
+    }
+
+    /// Smallest span, containing both spans. Spans must be in the same file
+    pub fn to(self, other: Span) -> Span {
+        debug_assert_eq!(self.file, other.file);
+        Span {
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// Wrapper to attach a [Span] to enums and foreign types.
+/// Prefer adding a `span` field to structs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Spanned<T> {
+    /// Inner value
+    pub inner: T,
+    /// Where it came from
+    pub span: Option<Span>,
+}
+
+impl<T> Spanned<T> {
+    /// Attach a span to a value
+    pub fn new(inner: T, span: Option<Span>) -> Self {
+        Self { inner, span }
+    }
+}
+
+impl<T> std::ops::Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T> std::ops::DerefMut for Spanned<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+/// A source file, owned by the [SourceMap]
+pub struct SourceFile {
+    /// File name or path
+    pub name: String,
+    /// File contents
+    pub contents: String,
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(name: String, contents: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(contents.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Self {
+            name,
+            contents,
+            line_starts,
+        }
+    }
+
+    /// Zero-based line index of a byte offset
+    pub fn line_index(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+
+    /// Text of a line by it's zero-based index, without the line break
+    pub fn line(&self, index: usize) -> &str {
+        let start = self.line_starts[index];
+        let end = self
+            .line_starts
+            .get(index + 1)
+            .map_or(self.contents.len(), |&end| end - 1);
+        self.contents[start..end].trim_end_matches('\r')
+    }
+
+    /// One-based line and column (in characters) of a byte offset
+    pub fn location(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.contents.len());
+        let line = self.line_index(offset);
+        let column = self.contents[self.line_starts[line]..offset]
+            .chars()
+            .count();
+        (line + 1, column + 1)
+    }
+}
+
+/// Owns the contents of all the source files, so that spans can stay small
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    /// Create an empty source map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file to the source map
+    pub fn add_file(&mut self, name: impl Into<String>, contents: impl Into<String>) -> FileId {
+        self.files
+            .push(SourceFile::new(name.into(), contents.into()));
+        FileId(std::num::NonZeroU32::new(self.files.len() as _).unwrap())
+    }
+
+    /// Get a file by it's id
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.index()]
+    }
+
+    /// Iterate over all files and their ids
+    pub fn files(&self) -> impl Iterator<Item = (FileId, &SourceFile)> {
+        self.files.iter().enumerate().map(|(index, file)| {
+            (
+                FileId(std::num::NonZeroU32::new(index as u32 + 1).unwrap()),
+                file,
+            )
+        })
+    }
+
+    /// Source code a span points to
+    pub fn snippet(&self, span: Span) -> &str {
+        &self.file(span.file).contents[span.range()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    #[test]
+    fn test_size() {
+        check!(std::mem::size_of::<Span>() == 12);
+        check!(std::mem::size_of::<Option<Span>>() == 12);
+    }
+
+    #[test]
+    fn test_location() {
+        let mut source_map = SourceMap::new();
+        let file = source_map.add_file("main.c", "int main() {\n    return 42;\n}\n");
+        let span = Span::new(file, 24..26);
+        check!(source_map.snippet(span) == "42");
+        check!(source_map.file(file).location(24) == (2, 12));
+        check!(source_map.file(file).line(1) == "    return 42;");
+        check!(source_map.file(file).line(2) == "}");
+    }
+}
@@ -0,0 +1,68 @@
+use crate::expression::Function;
+use crate::{ArcLock, Expression};
+
+/// Top-level item of a unit, see [crate::Symbols]
+pub enum Symbol {
+    /// Function, calls to it point to the same lock
+    Function(ArcLock<Function>),
+    /// Any other item, f.e. a constant
+    Constant(Expression),
+}
+
+impl Symbol {
+    /// Location of this symbol in the source code
+    pub fn span(&self) -> Option<crate::Span> {
+        match self {
+            Self::Function(function) => function.read().unwrap().span,
+            Self::Constant(expression) => expression.span(),
+        }
+    }
+
+    /// Get the type of this symbol, once types are inferred
+    pub fn r#type(&self) -> crate::Type {
+        match self {
+            Self::Function(function) => crate::Type::Fn(function.read().unwrap().signature.clone()),
+            Self::Constant(expression) => expression.r#type(),
+        }
+    }
+}
+
+/// Functions become [Symbol::Function]s, everything else is a [Symbol::Constant]
+impl From<Expression> for Symbol {
+    fn from(expression: Expression) -> Self {
+        match expression {
+            Expression::Function(function) => {
+                Self::Function(std::sync::Arc::new(std::sync::RwLock::new(function)))
+            }
+            expression => Self::Constant(expression),
+        }
+    }
+}
+
+/// Visits the function or the expression, the way [Expression] dispatches.
+/// Functions are locked while they are visited, so a call to the function from
+/// inside of it can't be locked for writing, and a mutable visit can't lock it at all
+impl crate::Walk for Symbol {
+    fn walk<V: crate::Visitor + ?Sized>(&self, visitor: &mut V) {
+        match self {
+            Self::Function(function) => visitor.visit_function(&function.read().unwrap()),
+            Self::Constant(expression) => visitor.visit_expression(expression),
+        }
+    }
+
+    fn walk_mut<V: crate::VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        match self {
+            Self::Function(function) => visitor.visit_function(&mut function.write().unwrap()),
+            Self::Constant(expression) => visitor.visit_expression(expression),
+        }
+    }
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Function(function) => function.read().unwrap().fmt(f),
+            Self::Constant(expression) => expression.fmt(f),
+        }
+    }
+}
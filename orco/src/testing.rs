@@ -0,0 +1,162 @@
+/// Set this environment variable to write the printed IR
+/// into the snapshot files instead of comparing it, see [test_snapshot]
+pub const UPDATE_SNAPSHOTS: &str = "ORCO_UPDATE_SNAPSHOTS";
+
+/// Compare lowered symbols with the expected IR, one text per symbol,
+/// in the order of symbol names. Whitespace is normalised, see [normalize].
+/// Panics with a line diff if they don't match
+#[track_caller]
+pub fn test_symbols(symbols: &crate::Symbols, expected: &[&str]) {
+    let found = symbols
+        .values()
+        .map(|symbol| normalize(&symbol.to_string()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let expected = expected
+        .iter()
+        .map(|expected| normalize(expected))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if let Err(message) = compare(&expected, &found) {
+        panic!("{}", message);
+    }
+}
+
+/// Compare the whole unit, printed by [crate::parser::unit_to_string],
+/// with a golden file. If [UPDATE_SNAPSHOTS] is set, the file is
+/// written instead, so that it can be reviewed in the diff
+#[track_caller]
+pub fn test_snapshot(
+    ctx: &crate::TypeInferenceContext,
+    symbols: &crate::Symbols,
+    path: impl AsRef<std::path::Path>,
+) {
+    let found = crate::parser::unit_to_string(ctx, symbols);
+    let update = std::env::var_os(UPDATE_SNAPSHOTS).is_some_and(|value| value != "0");
+    if let Err(message) = snapshot(path.as_ref(), &found, update) {
+        panic!("{}", message);
+    }
+}
+
+/// Remove the common indentation, trailing whitespace and blank lines
+/// around the text, so that the expected IR can be written inline
+pub fn normalize(text: &str) -> String {
+    let text = unindent::unindent(text);
+    let lines = text.lines().map(str::trim_end).collect::<Vec<_>>();
+    let start = lines.iter().position(|line| !line.is_empty());
+    let end = lines.iter().rposition(|line| !line.is_empty());
+    match (start, end) {
+        (Some(start), Some(end)) => lines[start..=end].join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Line diff of two texts, removed lines start with `-`, added ones with `+`
+pub fn line_diff(expected: &str, found: &str) -> String {
+    let mut output = String::new();
+    for line in diff::lines(expected, found) {
+        let (sign, line) = match line {
+            diff::Result::Left(line) => ('-', line),
+            diff::Result::Right(line) => ('+', line),
+            diff::Result::Both(line, _) => (' ', line),
+        };
+        output.push_str(&format!("{} {}\n", sign, line));
+    }
+    output
+}
+
+fn compare(expected: &str, found: &str) -> Result<(), String> {
+    if expected == found {
+        return Ok(());
+    }
+    Err(format!(
+        "IR doesn't match the expected one (-expected +found):\n{}",
+        line_diff(expected, found)
+    ))
+}
+
+fn snapshot(path: &std::path::Path, found: &str, update: bool) -> Result<(), String> {
+    if update {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|err| format!("can't create {}: {}", parent.display(), err))?;
+        }
+        return std::fs::write(path, found)
+            .map_err(|err| format!("can't write {}: {}", path.display(), err));
+    }
+    let expected = std::fs::read_to_string(path).map_err(|err| {
+        format!(
+            "can't read snapshot {}: {}, run with {}=1 to create it",
+            path.display(),
+            err,
+            UPDATE_SNAPSHOTS
+        )
+    })?;
+    compare(&normalize(&expected), &normalize(found)).map_err(|message| {
+        format!(
+            "{}: {}\nrun with {}=1 to update the snapshot",
+            path.display(),
+            message,
+            UPDATE_SNAPSHOTS
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    #[test]
+    fn test_normalize() {
+        let text = "
+
+            fn main() -> i32 fastest {
+                0i32;
+            }
+
+        ";
+        check!(normalize(text) == "fn main() -> i32 fastest {\n    0i32;\n}");
+        check!(normalize("  \n\n").is_empty());
+    }
+
+    #[test]
+    fn test_compare() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let symbols = crate::parser::parse_unit(
+            &mut ctx,
+            "const b = fn b() -> () fastest {\n};\nconst a = fn a() -> i32 fastest {\n    1i32;\n};",
+        )
+        .unwrap();
+        test_symbols(
+            &symbols,
+            &[
+                "
+                    fn a() -> i32 fastest {
+                        1i32;
+                    }
+                ",
+                "fn b() -> () fastest {\n}",
+            ],
+        );
+
+        let_assert!(Err(message) = compare("a\nb\nc", "a\nd\nc"));
+        check!(message.ends_with("  a\n- b\n+ d\n  c\n"));
+    }
+
+    #[test]
+    fn test_update() {
+        let path = std::env::temp_dir()
+            .join(format!("orco-snapshot-{}", std::process::id()))
+            .join("unit.orco");
+        let unit = "const a = 1i32;\n";
+        let_assert!(Err(message) = snapshot(&path, unit, false));
+        check!(message.contains(UPDATE_SNAPSHOTS));
+
+        check!(snapshot(&path, unit, true) == Ok(()));
+        check!(snapshot(&path, unit, false) == Ok(()));
+        let_assert!(Err(message) = snapshot(&path, "const a = 2i32;\n", false));
+        check!(message.contains("- const a = 1i32;\n+ const a = 2i32;"));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
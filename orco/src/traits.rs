@@ -0,0 +1,277 @@
+use crate::{
+    type_inference::intrinsics::Intrinsic, types::FunctionSignature, ArcLock, Type,
+    TypeInferenceContext,
+};
+
+/// Trait declaration, a set of method signatures.
+/// Method signatures refer to the implementing type as `Self`, see [Trait::self_type]
+#[derive(Clone)]
+pub struct Trait {
+    /// Trait name
+    pub name: String,
+    /// Method names and signatures
+    pub methods: Vec<(String, FunctionSignature)>,
+    /// Operator traits are implemented for primitive types by an intrinsic
+    pub intrinsic: Option<Intrinsic>,
+    /// Location of the declaration in the source code
+    pub span: Option<crate::Span>,
+}
+
+impl Trait {
+    /// Create a new trait without a location
+    pub fn new(name: String, methods: Vec<(String, FunctionSignature)>) -> Self {
+        Self {
+            name,
+            methods,
+            intrinsic: None,
+            span: None,
+        }
+    }
+
+    /// Trait for an arithmetic, bitwise or comparison operator.
+    /// It has a single method, named like the intrinsic
+    pub fn operator(intrinsic: Intrinsic) -> Self {
+        let return_type = if intrinsic.is_comparison() {
+            Type::Bool
+        } else {
+            Self::self_type()
+        };
+        let signature = match intrinsic {
+            Intrinsic::Neg | Intrinsic::Not => {
+                crate::function_signature![(value: {Self::self_type()}) -> {return_type}]
+            }
+            _ => {
+                crate::function_signature![(lhs: {Self::self_type()}, rhs: {Self::self_type()}) -> {return_type}]
+            }
+        };
+        let name = intrinsic.to_string();
+        let mut chars = name.chars();
+        let capitalized = chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default();
+        Self {
+            intrinsic: Some(intrinsic),
+            ..Self::new(capitalized, vec![(name, signature)])
+        }
+    }
+
+    /// Type, that refers to the implementing type in method signatures
+    pub fn self_type() -> Type {
+        Type::Parameter("Self".to_owned())
+    }
+
+    /// Signature of a method
+    pub fn method(&self, name: &str) -> Option<&FunctionSignature> {
+        self.methods
+            .iter()
+            .find(|(method, _)| method == name)
+            .map(|(_, signature)| signature)
+    }
+}
+
+impl std::fmt::Display for Trait {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "trait {} {{", self.name)?;
+        for (name, signature) in &self.methods {
+            writeln!(f, "    fn {}{};", name, signature)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Shared reference to a [Trait]. Two references
+/// are equal only if they point to the same declaration
+#[derive(Clone)]
+pub struct TraitRef(pub ArcLock<Trait>);
+
+impl TraitRef {
+    /// Wrap a trait declaration
+    pub fn new(r#trait: Trait) -> Self {
+        Self(std::sync::Arc::new(std::sync::RwLock::new(r#trait)))
+    }
+}
+
+impl std::ops::Deref for TraitRef {
+    type Target = ArcLock<Trait>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl PartialEq for TraitRef {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for TraitRef {}
+
+/// Implementation of a trait for a type, binds functions to the methods
+pub struct Impl {
+    /// Implemented trait
+    pub r#trait: TraitRef,
+    /// Type, that implements the trait
+    pub self_type: Type,
+    /// Method names and functions, that implement them
+    pub methods: Vec<(String, ArcLock<crate::expression::Function>)>,
+    /// Location of the impl block in the source code
+    pub span: Option<crate::Span>,
+}
+
+impl Impl {
+    /// Create a new impl without a location
+    pub fn new(
+        r#trait: TraitRef,
+        self_type: Type,
+        methods: Vec<(String, ArcLock<crate::expression::Function>)>,
+    ) -> Self {
+        Self {
+            r#trait,
+            self_type,
+            methods,
+            span: None,
+        }
+    }
+}
+
+impl TypeInferenceContext {
+    /// Register an impl. Reports missing methods and
+    /// methods with signatures, that don't match the trait
+    pub fn implement(&mut self, r#impl: Impl) {
+        let r#trait = r#impl.r#trait.read().unwrap();
+        let arguments = [("Self".to_owned(), r#impl.self_type.clone())].into();
+        for (name, signature) in &r#trait.methods {
+            let Some((_, function)) = r#impl.methods.iter().find(|(method, _)| method == name)
+            else {
+                self.diagnostics.report(
+                    crate::Diagnostic::error(
+                        "E0110",
+                        format!(
+                            "method `{}` of trait `{}` is not implemented",
+                            name, r#trait.name
+                        ),
+                    )
+                    .with_label(r#impl.span, "missing method"),
+                );
+                continue;
+            };
+            let expected = signature.substitute(&arguments);
+            let found = &function.read().unwrap().signature;
+            if expected
+                .parameters
+                .iter()
+                .map(|(_, r#type)| r#type)
+                .ne(found.parameters.iter().map(|(_, r#type)| r#type))
+                || expected.return_type != found.return_type
+            {
+                self.diagnostics.report(
+                    crate::Diagnostic::error(
+                        "E0111",
+                        format!(
+                            "method `{}` has an incompatible signature for trait `{}`",
+                            name, r#trait.name
+                        ),
+                    )
+                    .with_label(r#impl.span, "in this impl")
+                    .with_note(format!("expected `{}`, found `{}`", expected, found)),
+                );
+            }
+        }
+        drop(r#trait);
+        self.impls.push(r#impl);
+    }
+
+    /// Find the function, that implements a method of a trait for a type.
+    /// Operator traits resolve to intrinsics for primitive types
+    pub fn find_method(
+        &self,
+        r#trait: &TraitRef,
+        method: &str,
+        self_type: &Type,
+    ) -> Option<ArcLock<crate::expression::Function>> {
+        let intrinsic = r#trait.read().unwrap().intrinsic;
+        if let Some(intrinsic) = intrinsic {
+            // Only primitive types are supported by intrinsics
+            if intrinsic.supports(self_type) {
+                return Some(match intrinsic {
+                    Intrinsic::Neg | Intrinsic::Not => {
+                        self.intrinsics.unary(intrinsic, self_type.clone())
+                    }
+                    _ => self.intrinsics.binary(intrinsic, self_type.clone()),
+                });
+            }
+        }
+        self.impls
+            .iter()
+            .find(|r#impl| r#impl.r#trait == *r#trait && r#impl.self_type == *self_type)
+            .and_then(|r#impl| {
+                r#impl
+                    .methods
+                    .iter()
+                    .find(|(name, _)| name == method)
+                    .map(|(_, function)| function.clone())
+            })
+    }
+
+    /// Trait of an operator intrinsic, see [Trait::operator]
+    pub fn operator(&self, intrinsic: Intrinsic) -> TraitRef {
+        self.operators
+            .get(&intrinsic)
+            .expect("Only operators have traits")
+            .clone()
+    }
+}
+
+/// Traits of all the operator intrinsics
+pub fn operators() -> std::collections::HashMap<Intrinsic, TraitRef> {
+    use Intrinsic::*;
+    [
+        Add, Sub, Mul, Div, Rem, Neg, And, Or, Xor, Not, Shl, Shr, Eq, Ne, Lt, Le, Gt, Ge,
+    ]
+    .into_iter()
+    .map(|intrinsic| (intrinsic, TraitRef::new(Trait::operator(intrinsic))))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::Function;
+    use crate::types::{Struct, StructRef};
+    use assert2::*;
+
+    #[test]
+    fn test_find_method() {
+        let mut ctx = TypeInferenceContext::new();
+        let add = ctx.operator(Intrinsic::Add);
+        check!(add.read().unwrap().name == "Add");
+
+        let function = ctx.find_method(&add, "add", &Type::Integer(32)).unwrap();
+        check!(let crate::expression::function::FunctionBody::Intrinsic(Intrinsic::Add) = function.read().unwrap().body);
+        check!(ctx.find_method(&add, "add", &Type::Bool).is_none());
+
+        let vector = Type::Struct(StructRef::new(Struct::new(
+            Some("Vector".to_owned()),
+            vec![("x".to_owned(), Type::Float(32))],
+        )));
+        check!(ctx.find_method(&add, "add", &vector).is_none());
+        let function = std::sync::Arc::new(std::sync::RwLock::new(Function::new(
+            crate::function_signature![(lhs: {vector.clone()}, rhs: {vector.clone()}) -> {vector.clone()}],
+            Some("vector_add".to_owned()),
+            Vec::new(),
+        )));
+        ctx.implement(Impl::new(
+            add.clone(),
+            vector.clone(),
+            vec![("add".to_owned(), function.clone())],
+        ));
+        check!(!ctx.diagnostics.has_errors());
+        let found = ctx.find_method(&add, "add", &vector).unwrap();
+        check!(std::sync::Arc::ptr_eq(&found, &function));
+
+        ctx.implement(Impl::new(ctx.operator(Intrinsic::Sub), vector, Vec::new()));
+        check!(ctx.diagnostics.has_errors());
+    }
+}
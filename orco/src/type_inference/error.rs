@@ -0,0 +1,60 @@
+use super::TypeVariable;
+use crate::Type;
+
+/// Errors that can occur while inferring types
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeError {
+    /// Two types had to be equal, but they are not
+    Mismatch {
+        /// Type that was expected
+        expected: Type,
+        /// Type that was found instead
+        found: Type,
+    },
+    /// Function was called with a wrong number of arguments
+    ArgumentCount {
+        /// Number of parameters in the function signature
+        expected: usize,
+        /// Number of arguments passed
+        found: usize,
+    },
+    /// Type variable would have to contain itself
+    InfiniteType(TypeVariable, Type),
+    /// Not enough information to infer a type
+    Unsolved(TypeVariable),
+}
+
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mismatch { expected, found } => {
+                write!(f, "type mismatch: expected {}, found {}", expected, found)
+            }
+            Self::ArgumentCount { expected, found } => write!(
+                f,
+                "function takes {} arguments, but {} were supplied",
+                expected, found
+            ),
+            Self::InfiniteType(variable, r#type) => {
+                write!(f, "infinite type: {} = {}", variable, r#type)
+            }
+            Self::Unsolved(variable) => {
+                write!(f, "type annotations needed, can't infer {}", variable)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+impl From<TypeError> for crate::Diagnostic {
+    fn from(error: TypeError) -> Self {
+        let code = match error {
+            TypeError::Mismatch { .. } => "E0100",
+            TypeError::ArgumentCount { .. } => "E0101",
+            TypeError::InfiniteType(..) => "E0102",
+            TypeError::Unsolved(_) => "E0103",
+        };
+        crate::Diagnostic::error(code, error)
+    }
+}
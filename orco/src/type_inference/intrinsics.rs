@@ -1,10 +1,394 @@
-use crate::{expression::Function, types::FunctionSignature};
+use crate::{
+    expression::Function,
+    types::{FunctionSignature, Mutability},
+};
 type IntrinsicFunction = crate::ArcLock<Function>;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Intrinsic {
     Return,
     Branch,
+    /// Loop forever, until broken out of
+    Loop,
+    /// Break out of an enclosing loop, `0` is the innermost one
+    Break(usize),
+    /// Jump to the `next` body of an enclosing loop, `0` is the innermost one
+    Continue(usize),
+    /// Assign a value to a variable, returns the value
+    Assign,
+    /// Construct a struct from the values of it's fields
+    Construct,
+
+    /// Pointer to a variable, a field or a value behind another pointer
+    AddressOf,
+    /// Read a value behind a pointer
+    Load,
+    /// Write a value behind a mutable pointer
+    Store,
+    /// Offset a pointer by a number of elements
+    Offset,
+
+    /// Construct an array from the values of it's elements
+    Array,
+    /// Make a slice from a pointer to the first element and a length
+    Slice,
+    /// Number of elements in a slice
+    Length,
+
+    /// Convert a value to another type, see [Cast]
+    Cast(Cast),
+    /// Conversion, that was inserted by a frontend rather than written in the source code
+    ImplicitCast(Cast),
+
+    /// `lhs + rhs`
+    Add,
+    /// `lhs - rhs`
+    Sub,
+    /// `lhs * rhs`
+    Mul,
+    /// `lhs / rhs`
+    Div,
+    /// `lhs % rhs`
+    Rem,
+    /// `-value`
+    Neg,
+
+    /// Bitwise (or logical for bools) `lhs & rhs`
+    And,
+    /// Bitwise (or logical for bools) `lhs | rhs`
+    Or,
+    /// Bitwise (or logical for bools) `lhs ^ rhs`
+    Xor,
+    /// Bitwise (or logical for bools) `!value`
+    Not,
+    /// `lhs << rhs`
+    Shl,
+    /// `lhs >> rhs`, arithmetic for signed integers, logical for unsigned
+    Shr,
+
+    /// `lhs == rhs`
+    Eq,
+    /// `lhs != rhs`
+    Ne,
+    /// `lhs < rhs`
+    Lt,
+    /// `lhs <= rhs`
+    Le,
+    /// `lhs > rhs`
+    Gt,
+    /// `lhs >= rhs`
+    Ge,
+}
+
+impl Intrinsic {
+    /// Is this a comparison operator, returning `bool`?
+    pub fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge
+        )
+    }
+
+    /// Is this an arithmetic, bitwise or comparison operator?
+    pub fn is_operator(self) -> bool {
+        !matches!(
+            self,
+            Self::Return
+                | Self::Branch
+                | Self::Loop
+                | Self::Break(_)
+                | Self::Continue(_)
+                | Self::Assign
+                | Self::Construct
+                | Self::AddressOf
+                | Self::Load
+                | Self::Store
+                | Self::Offset
+                | Self::Array
+                | Self::Slice
+                | Self::Length
+                | Self::Cast(_)
+                | Self::ImplicitCast(_)
+        )
+    }
+
+    /// Kind of the conversion, if this is a cast
+    pub fn cast(self) -> Option<Cast> {
+        match self {
+            Self::Cast(cast) | Self::ImplicitCast(cast) => Some(cast),
+            _ => None,
+        }
+    }
+
+    /// Can this operator be applied to values of this type?
+    /// Types of other intrinsics are checked by their signatures
+    pub fn supports(self, r#type: &crate::Type) -> bool {
+        use crate::Type;
+        if !self.is_operator() {
+            return true;
+        }
+        let (integer, unsigned, float, bool) = match r#type {
+            Type::Integer(_) => (true, false, false, false),
+            Type::Unsigned(_) => (false, true, false, false),
+            Type::Float(_) => (false, false, true, false),
+            Type::Bool => (false, false, false, true),
+            Type::Pointer(..) => return self.is_comparison(),
+            Type::Wildcard | Type::Never => return true,
+            _ => return false,
+        };
+        match self {
+            Self::Add | Self::Sub | Self::Mul | Self::Div => integer || unsigned || float,
+            Self::Rem | Self::Shl | Self::Shr => integer || unsigned,
+            Self::Neg => integer || float,
+            Self::And | Self::Or | Self::Xor | Self::Not => integer || unsigned || bool,
+            Self::Eq | Self::Ne => true,
+            Self::Lt | Self::Le | Self::Gt | Self::Ge => integer || unsigned || float,
+            _ => true,
+        }
+    }
+}
+
+impl std::fmt::Display for Intrinsic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Return => write!(f, "return"),
+            Self::Branch => write!(f, "branch"),
+            Self::Loop => write!(f, "loop"),
+            Self::Break(0) => write!(f, "break"),
+            Self::Break(depth) => write!(f, "break {}", depth),
+            Self::Continue(0) => write!(f, "continue"),
+            Self::Continue(depth) => write!(f, "continue {}", depth),
+            Self::Assign => write!(f, "assign"),
+            Self::Construct => write!(f, "construct"),
+            Self::AddressOf => write!(f, "address_of"),
+            Self::Load => write!(f, "load"),
+            Self::Store => write!(f, "store"),
+            Self::Offset => write!(f, "offset"),
+            Self::Array => write!(f, "array"),
+            Self::Slice => write!(f, "slice"),
+            Self::Length => write!(f, "length"),
+            Self::Cast(cast) => write!(f, "cast {}", cast),
+            Self::ImplicitCast(cast) => write!(f, "implicit cast {}", cast),
+            Self::Add => write!(f, "add"),
+            Self::Sub => write!(f, "sub"),
+            Self::Mul => write!(f, "mul"),
+            Self::Div => write!(f, "div"),
+            Self::Rem => write!(f, "rem"),
+            Self::Neg => write!(f, "neg"),
+            Self::And => write!(f, "and"),
+            Self::Or => write!(f, "or"),
+            Self::Xor => write!(f, "xor"),
+            Self::Not => write!(f, "not"),
+            Self::Shl => write!(f, "shl"),
+            Self::Shr => write!(f, "shr"),
+            Self::Eq => write!(f, "eq"),
+            Self::Ne => write!(f, "ne"),
+            Self::Lt => write!(f, "lt"),
+            Self::Le => write!(f, "le"),
+            Self::Gt => write!(f, "gt"),
+            Self::Ge => write!(f, "ge"),
+        }
+    }
+}
+
+/// Parses the [Display](std::fmt::Display) form, f.e. `add`, `break 1` or `cast sext`
+impl std::str::FromStr for Intrinsic {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let depth = |depth: &str| depth.trim().parse().map_err(|_| ());
+        if let Some(cast) = s.strip_prefix("implicit cast ") {
+            return Ok(Self::ImplicitCast(cast.parse()?));
+        }
+        if let Some(cast) = s.strip_prefix("cast ") {
+            return Ok(Self::Cast(cast.parse()?));
+        }
+        if let Some(depth) = s.strip_prefix("break ").map(depth) {
+            return Ok(Self::Break(depth?));
+        }
+        if let Some(depth) = s.strip_prefix("continue ").map(depth) {
+            return Ok(Self::Continue(depth?));
+        }
+        Ok(match s {
+            "return" => Self::Return,
+            "branch" => Self::Branch,
+            "loop" => Self::Loop,
+            "break" => Self::Break(0),
+            "continue" => Self::Continue(0),
+            "assign" => Self::Assign,
+            "construct" => Self::Construct,
+            "address_of" => Self::AddressOf,
+            "load" => Self::Load,
+            "store" => Self::Store,
+            "offset" => Self::Offset,
+            "array" => Self::Array,
+            "slice" => Self::Slice,
+            "length" => Self::Length,
+            "add" => Self::Add,
+            "sub" => Self::Sub,
+            "mul" => Self::Mul,
+            "div" => Self::Div,
+            "rem" => Self::Rem,
+            "neg" => Self::Neg,
+            "and" => Self::And,
+            "or" => Self::Or,
+            "xor" => Self::Xor,
+            "not" => Self::Not,
+            "shl" => Self::Shl,
+            "shr" => Self::Shr,
+            "eq" => Self::Eq,
+            "ne" => Self::Ne,
+            "lt" => Self::Lt,
+            "le" => Self::Le,
+            "gt" => Self::Gt,
+            "ge" => Self::Ge,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// Kinds of conversions between primitive types
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Cast {
+    /// Whatever conversion C would do, resolved to one of the others
+    /// by type inference, see [Cast::between]
+    Convert,
+    /// Sign-extend an integer to a wider one
+    SignExtend,
+    /// Zero-extend an integer to a wider one
+    ZeroExtend,
+    /// Drop the high bits of an integer
+    Truncate,
+    /// Integer to the nearest float, signedness is taken from the integer type
+    IntToFloat,
+    /// Float to integer, rounding towards zero.
+    /// Out of range values and NaN are undefined behavior
+    FloatToInt,
+    /// Float to integer, rounding towards zero.
+    /// Out of range values are clamped, NaN becomes zero
+    FloatToIntSaturating,
+    /// Float to a float of another size
+    FloatResize,
+    /// Reinterpret the bits as a type of the same size
+    Bitcast,
+    /// `value != 0`
+    ToBool,
+    /// `true` is one, `false` is zero
+    FromBool,
+}
+
+impl Cast {
+    /// Natural conversion between two types, [None] if there is none
+    pub fn between(from: &crate::Type, to: &crate::Type) -> Option<Self> {
+        use crate::Type;
+        Some(match (from, to) {
+            (Type::Integer(from_bits), Type::Integer(to_bits) | Type::Unsigned(to_bits))
+            | (Type::Unsigned(from_bits), Type::Integer(to_bits) | Type::Unsigned(to_bits)) => {
+                match from_bits.cmp(to_bits) {
+                    std::cmp::Ordering::Less if matches!(from, Type::Integer(_)) => {
+                        Self::SignExtend
+                    }
+                    std::cmp::Ordering::Less => Self::ZeroExtend,
+                    std::cmp::Ordering::Equal => Self::Bitcast,
+                    std::cmp::Ordering::Greater => Self::Truncate,
+                }
+            }
+            (Type::Integer(_) | Type::Unsigned(_), Type::Float(_)) => Self::IntToFloat,
+            (Type::Float(_), Type::Integer(_) | Type::Unsigned(_)) => Self::FloatToInt,
+            (Type::Float(_), Type::Float(_)) => Self::FloatResize,
+            (Type::Bool, Type::Bool) | (Type::Pointer(..), Type::Pointer(..)) => Self::Bitcast,
+            (Type::Bool, Type::Integer(_) | Type::Unsigned(_)) => Self::FromBool,
+            (
+                Type::Integer(_) | Type::Unsigned(_) | Type::Float(_) | Type::Pointer(..),
+                Type::Bool,
+            ) => Self::ToBool,
+            _ => return None,
+        })
+    }
+
+    /// Can a value of type `from` be converted to `to` this way?
+    pub fn supports(self, from: &crate::Type, to: &crate::Type) -> bool {
+        use crate::Type;
+        let integer_bits = |r#type: &Type| match r#type {
+            Type::Integer(bits) | Type::Unsigned(bits) => Some(*bits),
+            _ => None,
+        };
+        let float_bits = |r#type: &Type| match r#type {
+            Type::Float(bits) => Some(*bits),
+            _ => None,
+        };
+        match (from, to) {
+            (Type::Wildcard | Type::Never, _) | (_, Type::Wildcard | Type::Never) => return true,
+            _ => (),
+        }
+        match self {
+            Self::Convert => Self::between(from, to).is_some(),
+            Self::SignExtend | Self::ZeroExtend => {
+                matches!((integer_bits(from), integer_bits(to)), (Some(from), Some(to)) if from <= to)
+            }
+            Self::Truncate => {
+                matches!((integer_bits(from), integer_bits(to)), (Some(from), Some(to)) if from >= to)
+            }
+            Self::IntToFloat => integer_bits(from).is_some() && float_bits(to).is_some(),
+            Self::FloatToInt | Self::FloatToIntSaturating => {
+                float_bits(from).is_some() && integer_bits(to).is_some()
+            }
+            Self::FloatResize => float_bits(from).is_some() && float_bits(to).is_some(),
+            Self::Bitcast => match (from, to) {
+                (Type::Pointer(..), Type::Pointer(..)) | (Type::Bool, Type::Bool) => true,
+                _ => {
+                    let bits = |r#type| integer_bits(r#type).or(float_bits(r#type));
+                    bits(from).is_some() && bits(from) == bits(to)
+                }
+            },
+            Self::ToBool => {
+                matches!(to, Type::Bool)
+                    && (integer_bits(from).is_some()
+                        || float_bits(from).is_some()
+                        || matches!(from, Type::Pointer(..)))
+            }
+            Self::FromBool => matches!(from, Type::Bool) && integer_bits(to).is_some(),
+        }
+    }
+}
+
+impl std::fmt::Display for Cast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Convert => write!(f, "convert"),
+            Self::SignExtend => write!(f, "sext"),
+            Self::ZeroExtend => write!(f, "zext"),
+            Self::Truncate => write!(f, "trunc"),
+            Self::IntToFloat => write!(f, "itof"),
+            Self::FloatToInt => write!(f, "ftoi"),
+            Self::FloatToIntSaturating => write!(f, "ftoi_sat"),
+            Self::FloatResize => write!(f, "fresize"),
+            Self::Bitcast => write!(f, "bitcast"),
+            Self::ToBool => write!(f, "to_bool"),
+            Self::FromBool => write!(f, "from_bool"),
+        }
+    }
+}
+
+impl std::str::FromStr for Cast {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "convert" => Ok(Self::Convert),
+            "sext" => Ok(Self::SignExtend),
+            "zext" => Ok(Self::ZeroExtend),
+            "trunc" => Ok(Self::Truncate),
+            "itof" => Ok(Self::IntToFloat),
+            "ftoi" => Ok(Self::FloatToInt),
+            "ftoi_sat" => Ok(Self::FloatToIntSaturating),
+            "fresize" => Ok(Self::FloatResize),
+            "bitcast" => Ok(Self::Bitcast),
+            "to_bool" => Ok(Self::ToBool),
+            "from_bool" => Ok(Self::FromBool),
+            _ => Err(()),
+        }
+    }
 }
 
 fn make_intrinsic(signature: FunctionSignature, intrinsic: Intrinsic) -> IntrinsicFunction {
@@ -41,6 +425,201 @@ impl Intrinsics {
             Intrinsic::Branch,
         )
     }
+
+    /// Loop, runs `body` and then `next` until broken out of.
+    /// [Intrinsic::Continue] skips the rest of `body`, but still runs `next`
+    /// Signature: `fn orco::intrinsics::loop<T>(body: fn () -> (), next: fn () -> ()) -> T`
+    pub fn r#loop(&self, r#type: crate::Type) -> IntrinsicFunction {
+        make_intrinsic(
+            crate::function_signature![(body: (fn () -> () transparent), next: (fn () -> () transparent)) -> {r#type} transparent],
+            Intrinsic::Loop,
+        )
+    }
+
+    /// Break out of a loop `depth` levels up, yielding a value from it.
+    /// Takes no value if the loop returns `()`
+    /// Signature: `fn orco::intrinsics::break<T>(value: T) -> !`
+    pub fn r#break(&self, depth: usize, r#type: crate::Type) -> IntrinsicFunction {
+        make_intrinsic(
+            if r#type == crate::Type::Unit {
+                crate::function_signature![() -> ! transparent]
+            } else {
+                crate::function_signature![(value: {r#type}) -> ! transparent]
+            },
+            Intrinsic::Break(depth),
+        )
+    }
+
+    /// Continue a loop `depth` levels up
+    /// Signature: `fn orco::intrinsics::continue() -> !`
+    pub fn r#continue(&self, depth: usize) -> IntrinsicFunction {
+        make_intrinsic(
+            crate::function_signature![() -> ! transparent],
+            Intrinsic::Continue(depth),
+        )
+    }
+
+    /// Assign a value to a variable
+    /// Signature: `fn orco::intrinsics::assign<T>(target: T, value: T) -> T`
+    pub fn assign(&self, r#type: crate::Type) -> IntrinsicFunction {
+        make_intrinsic(
+            crate::function_signature![(target: {r#type.clone()}, value: {r#type.clone()}) -> {r#type} transparent],
+            Intrinsic::Assign,
+        )
+    }
+
+    /// Construct a struct, takes values of all the fields in order
+    /// Signature: `fn orco::intrinsics::construct(field: F, ...) -> S`
+    pub fn construct(&self, r#struct: crate::types::StructRef) -> IntrinsicFunction {
+        let parameters = r#struct
+            .read()
+            .unwrap()
+            .fields
+            .iter()
+            .map(|(name, r#type)| (Some(name.clone()), r#type.clone()))
+            .collect();
+        make_intrinsic(
+            FunctionSignature::new(
+                parameters,
+                crate::Type::Struct(r#struct),
+                crate::types::CallingConvention::Transparent,
+            ),
+            Intrinsic::Construct,
+        )
+    }
+
+    /// Take the address of a place: a variable, a field or a loaded value
+    /// Signature: `fn orco::intrinsics::address_of<T>(place: T) -> *mut T`
+    pub fn address_of(&self, r#type: crate::Type) -> IntrinsicFunction {
+        let pointer = crate::Type::Pointer(Box::new(r#type.clone()), Mutability::Mut);
+        make_intrinsic(
+            crate::function_signature![(place: {r#type}) -> {pointer} transparent],
+            Intrinsic::AddressOf,
+        )
+    }
+
+    /// Read a value behind a pointer
+    /// Signature: `fn orco::intrinsics::load<T>(pointer: *const T) -> T`
+    pub fn load(&self, r#type: crate::Type) -> IntrinsicFunction {
+        let pointer = crate::Type::Pointer(Box::new(r#type.clone()), Mutability::Const);
+        make_intrinsic(
+            crate::function_signature![(pointer: {pointer}) -> {r#type} transparent],
+            Intrinsic::Load,
+        )
+    }
+
+    /// Write a value behind a pointer
+    /// Signature: `fn orco::intrinsics::store<T>(pointer: *mut T, value: T) -> ()`
+    pub fn store(&self, r#type: crate::Type) -> IntrinsicFunction {
+        let pointer = crate::Type::Pointer(Box::new(r#type.clone()), Mutability::Mut);
+        make_intrinsic(
+            crate::function_signature![(pointer: {pointer}, value: {r#type}) -> () transparent],
+            Intrinsic::Store,
+        )
+    }
+
+    /// Offset a pointer by `offset` elements, `offset` is a signed integer of any size
+    /// Signature: `fn orco::intrinsics::offset<T, I>(pointer: *const T, offset: I) -> *const T`
+    pub fn offset(
+        &self,
+        r#type: crate::Type,
+        mutability: Mutability,
+        offset: crate::Type,
+    ) -> IntrinsicFunction {
+        let pointer = crate::Type::Pointer(Box::new(r#type), mutability);
+        make_intrinsic(
+            crate::function_signature![(pointer: {pointer.clone()}, offset: {offset}) -> {pointer} transparent],
+            Intrinsic::Offset,
+        )
+    }
+
+    /// Construct an array of `len` elements, takes the values of all the elements in order
+    /// Signature: `fn orco::intrinsics::array<T>(element: T, ...) -> [T; len]`
+    pub fn array(&self, r#type: crate::Type, len: u64) -> IntrinsicFunction {
+        let parameters = (0..len).map(|_| (None, r#type.clone())).collect();
+        make_intrinsic(
+            FunctionSignature::new(
+                parameters,
+                crate::Type::Array(Box::new(r#type), len),
+                crate::types::CallingConvention::Transparent,
+            ),
+            Intrinsic::Array,
+        )
+    }
+
+    /// Make a slice from a pointer to the first element and a number of elements,
+    /// `len` is an integer of any size
+    /// Signature: `fn orco::intrinsics::slice<T, I>(pointer: *const T, len: I) -> *const [T]`
+    pub fn slice(
+        &self,
+        r#type: crate::Type,
+        mutability: Mutability,
+        len: crate::Type,
+    ) -> IntrinsicFunction {
+        let pointer = crate::Type::Pointer(Box::new(r#type.clone()), mutability);
+        let slice = crate::Type::Slice(Box::new(r#type), mutability);
+        make_intrinsic(
+            crate::function_signature![(pointer: {pointer}, len: {len}) -> {slice} transparent],
+            Intrinsic::Slice,
+        )
+    }
+
+    /// Number of elements in a slice, `len` is an integer of any size
+    /// Signature: `fn orco::intrinsics::length<T, I>(slice: *const [T]) -> I`
+    pub fn length(&self, r#type: crate::Type, len: crate::Type) -> IntrinsicFunction {
+        let slice = crate::Type::Slice(Box::new(r#type), Mutability::Const);
+        make_intrinsic(
+            crate::function_signature![(slice: {slice}) -> {len} transparent],
+            Intrinsic::Length,
+        )
+    }
+
+    /// Convert a value from one type to another, see [Cast]
+    /// Signature: `fn orco::intrinsics::cast<From, To>(value: From) -> To`
+    pub fn cast(&self, cast: Cast, from: crate::Type, to: crate::Type) -> IntrinsicFunction {
+        make_intrinsic(
+            crate::function_signature![(value: {from}) -> {to} transparent],
+            Intrinsic::Cast(cast),
+        )
+    }
+
+    /// Same as [Intrinsics::cast], but marks the conversion as inserted by the frontend
+    /// Signature: `fn orco::intrinsics::implicit_cast<From, To>(value: From) -> To`
+    pub fn implicit_cast(
+        &self,
+        cast: Cast,
+        from: crate::Type,
+        to: crate::Type,
+    ) -> IntrinsicFunction {
+        make_intrinsic(
+            crate::function_signature![(value: {from}) -> {to} transparent],
+            Intrinsic::ImplicitCast(cast),
+        )
+    }
+
+    /// Binary arithmetic, bitwise or comparison operator
+    /// Signature: `fn orco::intrinsics::<op><T>(lhs: T, rhs: T) -> T`,
+    /// comparisons return `bool` instead
+    pub fn binary(&self, intrinsic: Intrinsic, r#type: crate::Type) -> IntrinsicFunction {
+        let return_type = if intrinsic.is_comparison() {
+            crate::Type::Bool
+        } else {
+            r#type.clone()
+        };
+        make_intrinsic(
+            crate::function_signature![(lhs: {r#type.clone()}, rhs: {r#type}) -> {return_type} transparent],
+            intrinsic,
+        )
+    }
+
+    /// Unary operator, [Intrinsic::Neg] or [Intrinsic::Not]
+    /// Signature: `fn orco::intrinsics::<op><T>(value: T) -> T`
+    pub fn unary(&self, intrinsic: Intrinsic, r#type: crate::Type) -> IntrinsicFunction {
+        make_intrinsic(
+            crate::function_signature![(value: {r#type.clone()}) -> {r#type} transparent],
+            intrinsic,
+        )
+    }
 }
 
 impl Default for Intrinsics {
@@ -48,3 +627,67 @@ impl Default for Intrinsics {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Type;
+    use assert2::*;
+
+    #[test]
+    fn test_supports() {
+        check!(Intrinsic::Add.supports(&Type::Float(32)));
+        check!(!Intrinsic::Add.supports(&Type::Bool));
+        check!(!Intrinsic::Rem.supports(&Type::Float(64)));
+        check!(!Intrinsic::Neg.supports(&Type::Unsigned(8)));
+        check!(Intrinsic::Not.supports(&Type::Bool));
+        check!(Intrinsic::Eq.supports(&Type::Bool));
+        check!(!Intrinsic::Lt.supports(&Type::Unit));
+        let pointer = Type::Pointer(Box::new(Type::Bool), crate::types::Mutability::Const);
+        check!(Intrinsic::Lt.supports(&pointer));
+        check!(!Intrinsic::Add.supports(&pointer));
+        check!(Intrinsic::Assign.supports(&pointer));
+    }
+
+    #[test]
+    fn test_nested_break() {
+        use crate::expression::{Call, Literal};
+        let mut ctx = crate::TypeInferenceContext::new();
+        let r#type = ctx.new_type_variable(crate::type_inference::TypeVariableKind::General);
+        ctx.enter_loop(r#type.clone());
+        ctx.enter_loop(Type::Unit);
+        let outer = ctx.r#break(1).unwrap();
+        let inner = ctx.r#break(0).unwrap();
+        check!(ctx.r#break(2).is_none());
+        ctx.exit_loop();
+        ctx.exit_loop();
+        check!(ctx.r#continue(0).is_none());
+
+        check!(inner.read().unwrap().signature.parameters.is_empty());
+        let mut expression = crate::Expression::Call(Call::new(
+            outer,
+            vec![crate::Expression::Literal(crate::Spanned::new(
+                Literal::Integer(5, Type::Unsigned(8)),
+                None,
+            ))],
+        ));
+        check!(expression.infer_types(&mut ctx) == Type::Never);
+        check!(ctx.resolve(&r#type) == Type::Unsigned(8));
+    }
+
+    #[test]
+    fn test_cast_between() {
+        check!(Cast::between(&Type::Integer(8), &Type::Unsigned(32)) == Some(Cast::SignExtend));
+        check!(Cast::between(&Type::Unsigned(8), &Type::Integer(32)) == Some(Cast::ZeroExtend));
+        check!(Cast::between(&Type::Integer(64), &Type::Integer(16)) == Some(Cast::Truncate));
+        check!(Cast::between(&Type::Float(32), &Type::Integer(32)) == Some(Cast::FloatToInt));
+        check!(Cast::between(&Type::Integer(32), &Type::Bool) == Some(Cast::ToBool));
+        check!(Cast::between(&Type::Bool, &Type::Float(32)) == None);
+
+        check!(Cast::Truncate.supports(&Type::Integer(32), &Type::Unsigned(8)));
+        check!(!Cast::SignExtend.supports(&Type::Integer(32), &Type::Integer(8)));
+        check!(Cast::Bitcast.supports(&Type::Float(32), &Type::Unsigned(32)));
+        check!(!Cast::Bitcast.supports(&Type::Float(32), &Type::Unsigned(64)));
+        check!(Cast::FloatToIntSaturating.supports(&Type::Float(64), &Type::Unsigned(8)));
+    }
+}
@@ -12,6 +12,30 @@ pub struct TypeInferenceContext {
     pub intrinsics: intrinsics::Intrinsics,
     /// Scopes, see [Scope]
     pub scopes: Vec<Scope>,
+    /// Type variables and their solutions, see [TypeVariable]
+    pub type_variables: Vec<TypeVariableState>,
+    /// Return types of the functions that are currently being inferred
+    pub return_types: Vec<crate::Type>,
+    /// Types of the loops that are currently being built, innermost last.
+    /// See [TypeInferenceContext::enter_loop]
+    pub loops: Vec<crate::Type>,
+    /// Named types declared so far, f.e. structs
+    pub types: std::collections::HashMap<String, crate::Type>,
+    /// Symbols declared so far, by their paths. Frontends declare functions
+    /// here before building them, so that calls can be resolved, see [crate::Module]
+    pub module: crate::Module,
+    /// Traits declared so far
+    pub traits: std::collections::HashMap<String, crate::traits::TraitRef>,
+    /// Traits of the operators, see [crate::traits::Trait::operator]
+    pub operators: std::collections::HashMap<intrinsics::Intrinsic, crate::traits::TraitRef>,
+    /// Trait implementations, see [TypeInferenceContext::implement]
+    pub impls: Vec<crate::traits::Impl>,
+    /// Diagnostics reported by frontends and type inference
+    pub diagnostics: crate::diagnostics::Diagnostics,
+    /// Source files, see [crate::SourceMap]
+    pub source_map: crate::SourceMap,
+    /// File that is currently being lowered by the frontend
+    pub current_file: Option<crate::span::FileId>,
 }
 
 impl TypeInferenceContext {
@@ -20,9 +44,79 @@ impl TypeInferenceContext {
         Self {
             intrinsics: intrinsics::Intrinsics::new(),
             scopes: Vec::new(),
+            type_variables: Vec::new(),
+            return_types: Vec::new(),
+            loops: Vec::new(),
+            types: std::collections::HashMap::new(),
+            module: crate::Module::new(),
+            traits: std::collections::HashMap::new(),
+            operators: crate::traits::operators(),
+            impls: Vec::new(),
+            diagnostics: crate::diagnostics::Diagnostics::new(),
+            source_map: crate::SourceMap::new(),
+            current_file: None,
         }
     }
 
+    /// Infer types of the whole unit. Creates type variables for all
+    /// the wildcards, solves them and writes the solutions back into the IR
+    pub fn infer<'a>(&mut self, expressions: impl IntoIterator<Item = &'a mut crate::Expression>) {
+        let mut expressions = expressions.into_iter().collect::<Vec<_>>();
+        for expression in &mut expressions {
+            expression.infer_types(self);
+        }
+        self.apply_defaults();
+        for expression in expressions {
+            expression.finish_types(self);
+        }
+    }
+
+    /// [TypeInferenceContext::infer] the symbols of a unit. Functions are taken
+    /// out of their locks while they are inferred, until then the calls
+    /// to them only see their signatures
+    pub fn infer_symbols<'a>(&mut self, symbols: impl IntoIterator<Item = &'a mut crate::Symbol>) {
+        let mut functions = Vec::new();
+        let mut constants = Vec::new();
+        for symbol in symbols {
+            match symbol {
+                crate::Symbol::Function(lock) => {
+                    let mut function = lock.write().unwrap();
+                    for (_, parameter) in &mut function.signature.parameters {
+                        self.instantiate_wildcards(parameter);
+                    }
+                    self.instantiate_wildcards(&mut function.signature.return_type);
+                    let declaration = crate::expression::Function::new(
+                        function.signature.clone(),
+                        function.name.clone(),
+                        Vec::new(),
+                    );
+                    let function = std::mem::replace(&mut *function, declaration);
+                    functions.push((lock.clone(), function));
+                }
+                crate::Symbol::Constant(expression) => constants.push(expression),
+            }
+        }
+        for (_, function) in &mut functions {
+            function.infer_types(self);
+        }
+        for expression in &mut constants {
+            expression.infer_types(self);
+        }
+        self.apply_defaults();
+        for (lock, mut function) in functions {
+            function.finish_types(self);
+            *lock.write().unwrap() = function;
+        }
+        for expression in constants {
+            expression.finish_types(self);
+        }
+    }
+
+    /// Make a span from a byte range in the current file
+    pub fn span(&self, range: std::ops::Range<usize>) -> Option<crate::Span> {
+        self.current_file.map(|file| crate::Span::new(file, range))
+    }
+
     /// Call when starting to generate a new function
 
     /// Call once done with the current function
@@ -31,6 +125,29 @@ impl TypeInferenceContext {
         self.intrinsics.r#return.take();
     }
 
+    /// Call when starting to generate a loop body,
+    /// breaking out of the loop yields a value of this type
+    pub fn enter_loop(&mut self, r#type: crate::Type) {
+        self.loops.push(r#type);
+    }
+
+    /// Call once done with the current loop
+    pub fn exit_loop(&mut self) {
+        self.loops.pop();
+    }
+
+    /// Break out of a loop `depth` levels up. [None] if there is no such loop
+    pub fn r#break(&self, depth: usize) -> Option<crate::ArcLock<crate::expression::Function>> {
+        let r#type = self.loops.iter().rev().nth(depth)?;
+        Some(self.intrinsics.r#break(depth, r#type.clone()))
+    }
+
+    /// Continue a loop `depth` levels up. [None] if there is no such loop
+    pub fn r#continue(&self, depth: usize) -> Option<crate::ArcLock<crate::expression::Function>> {
+        self.loops.iter().rev().nth(depth)?;
+        Some(self.intrinsics.r#continue(depth))
+    }
+
     /// Resolve a variable, starting from current scope and going up
     pub fn resolve_variable(&self, name: &str) -> Option<crate::ArcLock<Variable>> {
         for scope in self.scopes.iter().rev() {
@@ -48,6 +165,14 @@ impl Default for TypeInferenceContext {
     }
 }
 
+/// Type variables and unification
+pub mod unification;
+pub use unification::{TypeVariable, TypeVariableKind, TypeVariableState};
+
+/// Type inference errors
+pub mod error;
+pub use error::TypeError;
+
 /// A scope contains variables (see [Variable]) and helps resolving them
 pub type Scope = std::collections::HashMap<String, crate::ArcLock<Variable>>;
 
@@ -58,11 +183,17 @@ pub struct Variable {
     pub name: Option<String>,
     /// Variable type
     pub r#type: crate::Type,
+    /// Where the variable was declared
+    pub span: Option<crate::Span>,
 }
 
 impl Variable {
     pub fn new(name: Option<String>, r#type: crate::Type) -> Self {
-        Self { name, r#type }
+        Self {
+            name,
+            r#type,
+            span: None,
+        }
                 .unwrap_or("unnamed variable"),
             self.r#type
         )
@@ -0,0 +1,323 @@
+use super::*;
+use crate::Type;
+
+/// Type variable, a placeholder for a type that is not known yet.
+/// Created for every [Type::Wildcard] during type inference
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TypeVariable(pub usize);
+
+impl std::fmt::Display for TypeVariable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "?{}", self.0)
+    }
+}
+
+/// What a type variable is allowed to be solved to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeVariableKind {
+    /// Any type
+    General,
+    /// Only integer types, defaults to `i32`
+    Integer,
+    /// Only floating point types, defaults to `f64`
+    Float,
+}
+
+/// Solution state of a single type variable
+#[derive(Clone, Debug)]
+pub struct TypeVariableState {
+    /// What this variable is allowed to be
+    pub kind: TypeVariableKind,
+    /// Type this variable was unified with, if any
+    pub binding: Option<Type>,
+}
+
+impl TypeInferenceContext {
+    /// Create a fresh type variable
+    pub fn new_type_variable(&mut self, kind: TypeVariableKind) -> Type {
+        self.type_variables.push(TypeVariableState {
+            kind,
+            binding: None,
+        });
+        Type::Variable(TypeVariable(self.type_variables.len() - 1))
+    }
+
+    /// Replace every [Type::Wildcard] in a type with a fresh type variable
+    pub fn instantiate_wildcards(&mut self, r#type: &mut Type) {
+        match r#type {
+            Type::Wildcard => *r#type = self.new_type_variable(TypeVariableKind::General),
+            Type::Fn(signature) => {
+                for (_, parameter) in &mut signature.parameters {
+                    self.instantiate_wildcards(parameter);
+                }
+                self.instantiate_wildcards(&mut signature.return_type);
+            }
+            Type::Pointer(r#type, _) | Type::Array(r#type, _) | Type::Slice(r#type, _) => {
+                self.instantiate_wildcards(r#type)
+            }
+            _ => (),
+        }
+    }
+
+    /// Follow type variable bindings until the type is either
+    /// concrete on the outside or an unbound variable
+    pub fn shallow_resolve(&self, r#type: &Type) -> Type {
+        let mut r#type = r#type.clone();
+        while let Type::Variable(variable) = r#type {
+            match &self.type_variables[variable.0].binding {
+                Some(binding) => r#type = binding.clone(),
+                None => break,
+            }
+        }
+        r#type
+    }
+
+    /// Substitute all solved type variables in a type
+    pub fn resolve(&self, r#type: &Type) -> Type {
+        match self.shallow_resolve(r#type) {
+            Type::Fn(mut signature) => {
+                for (_, parameter) in &mut signature.parameters {
+                    *parameter = self.resolve(parameter);
+                }
+                *signature.return_type = self.resolve(&signature.return_type);
+                Type::Fn(signature)
+            }
+            Type::Pointer(r#type, mutability) => {
+                Type::Pointer(Box::new(self.resolve(&r#type)), mutability)
+            }
+            Type::Array(r#type, len) => Type::Array(Box::new(self.resolve(&r#type)), len),
+            Type::Slice(r#type, mutability) => {
+                Type::Slice(Box::new(self.resolve(&r#type)), mutability)
+            }
+            r#type => r#type,
+        }
+    }
+
+    /// Make two types equal, binding type variables on the way.
+    /// [Type::Wildcard] and [Type::Never] are compatible with any type,
+    /// a mutable pointer is accepted where a const pointer is expected.
+    /// The error is boxed, it holds two whole types
+    pub fn unify(&mut self, expected: &Type, found: &Type) -> Result<(), Box<TypeError>> {
+        let expected = self.shallow_resolve(expected);
+        let found = self.shallow_resolve(found);
+        let mismatch = || {
+            Box::new(TypeError::Mismatch {
+                expected: expected.clone(),
+                found: found.clone(),
+            })
+        };
+
+        match (&expected, &found) {
+            (Type::Wildcard, _) | (_, Type::Wildcard) => Ok(()),
+            (Type::Never, _) | (_, Type::Never) => Ok(()),
+            (Type::Variable(a), Type::Variable(b)) if a == b => Ok(()),
+            (Type::Variable(variable), other) | (other, Type::Variable(variable)) => {
+                self.bind(*variable, other)
+            }
+            (Type::Fn(expected_signature), Type::Fn(found_signature)) => {
+                if expected_signature.parameters.len() != found_signature.parameters.len()
+                    || expected_signature.type_parameters != found_signature.type_parameters
+                    || expected_signature.calling_convention != found_signature.calling_convention
+                {
+                    return Err(mismatch());
+                }
+                for ((_, expected), (_, found)) in expected_signature
+                    .parameters
+                    .iter()
+                    .zip(found_signature.parameters.iter())
+                {
+                    self.unify(expected, found).map_err(|_| mismatch())?;
+                }
+                self.unify(
+                    &expected_signature.return_type,
+                    &found_signature.return_type,
+                )
+                .map_err(|_| mismatch())
+            }
+            (
+                Type::Pointer(expected_type, expected_mutability),
+                Type::Pointer(found_type, found_mutability),
+            )
+            | (
+                Type::Slice(expected_type, expected_mutability),
+                Type::Slice(found_type, found_mutability),
+            ) => {
+                if expected_mutability > found_mutability {
+                    return Err(mismatch());
+                }
+                self.unify(expected_type, found_type)
+                    .map_err(|_| mismatch())
+            }
+            (Type::Array(expected_type, expected_len), Type::Array(found_type, found_len)) => {
+                if expected_len != found_len {
+                    return Err(mismatch());
+                }
+                self.unify(expected_type, found_type)
+                    .map_err(|_| mismatch())
+            }
+            (expected, found) if expected == found => Ok(()),
+            _ => Err(mismatch()),
+        }
+    }
+
+    /// Unify two types, recording an error if they are incompatible
+    pub fn expect(&mut self, expected: &Type, found: &Type, span: Option<crate::Span>) {
+        if let Err(error) = self.unify(expected, found) {
+            let error = match *error {
+                TypeError::Mismatch { expected, found } => TypeError::Mismatch {
+                    expected: self.resolve(&expected),
+                    found: self.resolve(&found),
+                },
+                error => error,
+            };
+            self.diagnostics
+                .report(crate::Diagnostic::from(error).with_label(span, "here"));
+        }
+    }
+
+    fn bind(&mut self, variable: TypeVariable, r#type: &Type) -> Result<(), Box<TypeError>> {
+        if self.occurs(variable, r#type) {
+            return Err(Box::new(TypeError::InfiniteType(
+                variable,
+                self.resolve(r#type),
+            )));
+        }
+
+        let kind = self.type_variables[variable.0].kind;
+        let compatible = match (kind, r#type) {
+            (TypeVariableKind::General, _) => true,
+            (TypeVariableKind::Integer, Type::Integer(_) | Type::Unsigned(_)) => true,
+            (TypeVariableKind::Float, Type::Float(_)) => true,
+            (kind, Type::Variable(other)) => {
+                let other = &mut self.type_variables[other.0].kind;
+                if *other == TypeVariableKind::General {
+                    *other = kind;
+                }
+                *other == kind
+            }
+            _ => false,
+        };
+        if !compatible {
+            return Err(Box::new(TypeError::Mismatch {
+                expected: Type::Variable(variable),
+                found: r#type.clone(),
+            }));
+        }
+
+        self.type_variables[variable.0].binding = Some(r#type.clone());
+        Ok(())
+    }
+
+    fn occurs(&self, variable: TypeVariable, r#type: &Type) -> bool {
+        match self.shallow_resolve(r#type) {
+            Type::Variable(other) => other == variable,
+            Type::Fn(signature) => {
+                signature
+                    .parameters
+                    .iter()
+                    .any(|(_, parameter)| self.occurs(variable, parameter))
+                    || self.occurs(variable, &signature.return_type)
+            }
+            Type::Pointer(r#type, _) | Type::Array(r#type, _) | Type::Slice(r#type, _) => {
+                self.occurs(variable, &r#type)
+            }
+            _ => false,
+        }
+    }
+
+    /// Solve all unbound number type variables to their default types, `i32` and `f64`
+    pub fn apply_defaults(&mut self) {
+        self.apply_defaults_since(0);
+    }
+
+    /// Apply defaults only to the type variables created since `first`,
+    /// f.e. the ones of a single expression evaluated at compile time
+    pub fn apply_defaults_since(&mut self, first: usize) {
+        for state in &mut self.type_variables[first..] {
+            if state.binding.is_none() {
+                state.binding = match state.kind {
+                    TypeVariableKind::General => None,
+                    TypeVariableKind::Integer => Some(Type::Integer(32)),
+                    TypeVariableKind::Float => Some(Type::Float(64)),
+                };
+            }
+        }
+    }
+
+    /// Fully resolve a type after inference is done, reporting
+    /// an error if it still contains unsolved type variables
+    pub fn finish_type(&mut self, r#type: &mut Type, span: Option<crate::Span>) {
+        *r#type = self.resolve(r#type);
+        if let Some(variable) = unsolved(r#type) {
+            self.diagnostics.report(
+                crate::Diagnostic::from(TypeError::Unsolved(variable))
+                    .with_label(span, "type must be known at this point"),
+            );
+            // Bind to a wildcard, so that the error is only reported once
+            self.type_variables[variable.0].binding = Some(Type::Wildcard);
+            *r#type = self.resolve(r#type);
+        }
+    }
+}
+
+fn unsolved(r#type: &Type) -> Option<TypeVariable> {
+    match r#type {
+        Type::Variable(variable) => Some(*variable),
+        Type::Fn(signature) => signature
+            .parameters
+            .iter()
+            .find_map(|(_, parameter)| unsolved(parameter))
+            .or_else(|| unsolved(&signature.return_type)),
+        Type::Pointer(r#type, _) | Type::Array(r#type, _) | Type::Slice(r#type, _) => {
+            unsolved(r#type)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    #[test]
+    fn test_unify_variables() {
+        let mut ctx = TypeInferenceContext::new();
+        let a = ctx.new_type_variable(TypeVariableKind::General);
+        let b = ctx.new_type_variable(TypeVariableKind::General);
+        check!(ctx.unify(&a, &b).is_ok());
+        check!(ctx.unify(&b, &Type::Bool).is_ok());
+        check!(ctx.resolve(&a) == Type::Bool);
+        check!(ctx.unify(&a, &Type::Unit).is_err());
+    }
+
+    #[test]
+    fn test_integer_default() {
+        let mut ctx = TypeInferenceContext::new();
+        let a = ctx.new_type_variable(TypeVariableKind::Integer);
+        check!(ctx.unify(&a, &Type::Bool).is_err());
+        ctx.apply_defaults();
+        check!(ctx.resolve(&a) == Type::Integer(32));
+    }
+
+    #[test]
+    fn test_occurs_check() {
+        let mut ctx = TypeInferenceContext::new();
+        let a = ctx.new_type_variable(TypeVariableKind::General);
+        let function = Type::Fn(crate::function_signature![() -> {a.clone()}]);
+        let_assert!(Err(error) = ctx.unify(&a, &function));
+        check!(let TypeError::InfiniteType(..) = *error);
+    }
+
+    #[test]
+    fn test_pointer_mutability() {
+        use crate::types::Mutability;
+        let mut ctx = TypeInferenceContext::new();
+        let a = ctx.new_type_variable(TypeVariableKind::General);
+        let const_pointer = Type::Pointer(Box::new(Type::Integer(8)), Mutability::Const);
+        let mut_pointer = Type::Pointer(Box::new(a.clone()), Mutability::Mut);
+        check!(ctx.unify(&const_pointer, &mut_pointer).is_ok());
+        check!(ctx.resolve(&a) == Type::Integer(8));
+        check!(ctx.unify(&mut_pointer, &const_pointer).is_err());
+    }
+}
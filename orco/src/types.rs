@@ -1,7 +1,7 @@
 use super::*;
 
 /// Type. Can be a primitive or a whole struct
-#[derive(Clone, Default)]
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
 pub enum Type {
     /// Wildcard type
     #[default]
@@ -23,10 +23,22 @@ pub enum Type {
 
     /// Function if const, function pointer otherwise
     Fn(FunctionSignature),
+    /// Pointer to a value of a type
+    Pointer(Box<Type>, Mutability),
+    /// Fixed-size array, element type and length
+    Array(Box<Type>, u64),
+    /// Slice, a fat pointer to a number of elements
+    Slice(Box<Type>, Mutability),
+    /// Nominal struct type, see [Struct]
+    Struct(StructRef),
+
+    /// Reference to a type parameter of the enclosing generic function
+    Parameter(String),
+    /// Type variable, only exists during type inference
+    Variable(crate::type_inference::TypeVariable),
 
     /// Type that hasn't been resolved yet
     Unresolved(String),
-    // Struct(Arc<RwLock<dyn Struct>>),
     // Tuple(M::Ref<'a, dyn Tuple>),
     // Reference(SymbolRef<dyn TypeTrait>),
 }
@@ -46,6 +58,19 @@ impl std::fmt::Display for Type {
             Self::Float(size) => write!(f, "f{}", size),
 
             Self::Fn(signature) => write!(f, "fn {}", signature),
+            Self::Pointer(r#type, mutability) => write!(f, "*{} {}", mutability, r#type),
+            Self::Array(r#type, len) => write!(f, "[{}; {}]", r#type, len),
+            Self::Slice(r#type, mutability) => write!(f, "*{} [{}]", mutability, r#type),
+            Self::Struct(r#struct) => {
+                let r#struct = r#struct.read().unwrap();
+                match &r#struct.name {
+                    Some(name) => write!(f, "struct {}", name),
+                    None => write!(f, "{}", r#struct),
+                }
+            }
+
+            Self::Parameter(name) => write!(f, "{}", name),
+            Self::Variable(variable) => write!(f, "{}", variable),
 
             Self::Unresolved(name) => write!(f, "'{}'", name),
             // Self::Reference(r#type) => {
@@ -55,6 +80,125 @@ impl std::fmt::Display for Type {
     }
 }
 
+impl Type {
+    /// Replace type parameters with their arguments.
+    /// Parameters without an argument are left as they are
+    pub fn substitute(&self, arguments: &std::collections::HashMap<String, Type>) -> Type {
+        match self {
+            Self::Parameter(name) => arguments.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Self::Fn(signature) => Self::Fn(signature.substitute(arguments)),
+            Self::Pointer(r#type, mutability) => {
+                Self::Pointer(Box::new(r#type.substitute(arguments)), *mutability)
+            }
+            Self::Array(r#type, len) => Self::Array(Box::new(r#type.substitute(arguments)), *len),
+            Self::Slice(r#type, mutability) => {
+                Self::Slice(Box::new(r#type.substitute(arguments)), *mutability)
+            }
+            r#type => r#type.clone(),
+        }
+    }
+}
+
+/// Can the value behind a pointer be modified through it?
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Mutability {
+    /// Read-only
+    Const,
+    /// Read-write
+    Mut,
+}
+
+impl std::fmt::Display for Mutability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Const => write!(f, "const"),
+            Self::Mut => write!(f, "mut"),
+        }
+    }
+}
+
+/// Struct declaration. Fields are laid out in declaration order, like in C
+#[derive(Clone, Debug, Default)]
+pub struct Struct {
+    /// Struct name, anonymous structs don't have one
+    pub name: Option<String>,
+    /// Field names and types
+    pub fields: Vec<(String, Type)>,
+    /// Location of the declaration in the source code
+    pub span: Option<crate::Span>,
+}
+
+impl Struct {
+    /// Create a new struct without a location
+    pub fn new(name: Option<String>, fields: Vec<(String, Type)>) -> Self {
+        Self {
+            name,
+            fields,
+            span: None,
+        }
+    }
+
+    /// Find a field by name, returns it's index and type
+    pub fn field(&self, name: &str) -> Option<(usize, &Type)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .find(|(_, (field, _))| field == name)
+            .map(|(index, (_, r#type))| (index, r#type))
+    }
+}
+
+impl std::fmt::Display for Struct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "struct ")?;
+        if let Some(name) = &self.name {
+            write!(f, "{} ", name)?;
+        }
+        write!(f, "{{")?;
+        for (index, (name, r#type)) in self.fields.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, " {}: {}", name, r#type)?;
+        }
+        write!(f, " }}")
+    }
+}
+
+/// Shared reference to a [Struct]. Structs are nominal: two
+/// references are equal only if they point to the same declaration
+#[derive(Clone, Debug)]
+pub struct StructRef(pub crate::ArcLock<Struct>);
+
+impl StructRef {
+    /// Wrap a struct declaration
+    pub fn new(r#struct: Struct) -> Self {
+        Self(std::sync::Arc::new(std::sync::RwLock::new(r#struct)))
+    }
+}
+
+impl std::ops::Deref for StructRef {
+    type Target = crate::ArcLock<Struct>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl PartialEq for StructRef {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for StructRef {}
+
+impl std::hash::Hash for StructRef {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::sync::Arc::as_ptr(&self.0).hash(state);
+    }
+}
+
 #[macro_export]
 macro_rules! quote_type {
     (_) => {
@@ -161,8 +305,11 @@ impl std::str::FromStr for CallingConvention {
 }
 
 /// Function signature. Contains all the typing information about this function
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct FunctionSignature {
+    /// Names of the type parameters, the function is generic if there are any.
+    /// They are referred to with [Type::Parameter]
+    pub type_parameters: Vec<String>,
     /// Function parameters, optional names and types
     pub parameters: Vec<(Option<String>, Type)>,
     /// Return type of the function
@@ -178,15 +325,52 @@ impl FunctionSignature {
         calling_convention: CallingConvention,
     ) -> Self {
         Self {
+            type_parameters: Vec::new(),
             parameters,
             return_type: Box::new(return_type),
             calling_convention,
         }
     }
+
+    /// Replace type parameters with their arguments, see [Type::substitute]
+    pub fn substitute(&self, arguments: &std::collections::HashMap<String, Type>) -> Self {
+        Self {
+            type_parameters: self
+                .type_parameters
+                .iter()
+                .filter(|name| !arguments.contains_key(*name))
+                .cloned()
+                .collect(),
+            parameters: self
+                .parameters
+                .iter()
+                .map(|(name, r#type)| (name.clone(), r#type.substitute(arguments)))
+                .collect(),
+            return_type: Box::new(self.return_type.substitute(arguments)),
+            calling_convention: self.calling_convention,
+        }
+    }
+
+    /// Map type parameters to type arguments, in order
+    pub fn type_arguments(&self, arguments: &[Type]) -> std::collections::HashMap<String, Type> {
+        self.type_parameters
+            .iter()
+            .cloned()
+            .zip(arguments.iter().cloned())
+            .collect()
+    }
+
+    /// Signature of an instance of a generic function
+    pub fn instantiate(&self, arguments: &[Type]) -> Self {
+        self.substitute(&self.type_arguments(arguments))
+    }
 }
 
 impl std::fmt::Display for FunctionSignature {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.type_parameters.is_empty() {
+            write!(f, "<{}>", self.type_parameters.join(", "))?;
+        }
         write!(f, "(")?;
         for (index, (name, r#type)) in self.parameters.iter().enumerate() {
             if index > 0 {
@@ -0,0 +1,521 @@
+use crate::expression::{function::FunctionBody, Call, Comptime, FieldAccess, Function, Index};
+use crate::layout::Target;
+use crate::type_inference::intrinsics::Intrinsic;
+use crate::types::CallingConvention;
+use crate::{Span, Type, Visitor, Walk};
+
+/// Check that a unit is well-formed, before it is passed to a backend.
+/// Every problem is reported as a diagnostic, returns true if there were none
+pub fn verify(
+    ctx: &mut crate::TypeInferenceContext,
+    symbols: &crate::Symbols,
+    target: &Target,
+) -> bool {
+    let mut verifier = Verifier {
+        ctx,
+        target,
+        errors: 0,
+        type_parameters: Vec::new(),
+        in_function: false,
+        loops: 0,
+        variables: std::collections::HashSet::new(),
+    };
+    for symbol in symbols.values() {
+        if let crate::Symbol::Constant(expression) = symbol {
+            verifier.report(
+                crate::Diagnostic::error("E0126", "top-level constants are not supported")
+                    .with_label(expression.span(), "only functions can be built"),
+            );
+        }
+        symbol.walk(&mut verifier);
+    }
+    verifier.errors == 0
+}
+
+/// Runs [verify] in a pipeline, see [crate::passes::PassManager]
+#[derive(Default)]
+pub struct Verify {
+    /// Target to check the types against
+    pub target: Target,
+}
+
+impl crate::passes::Pass for Verify {
+    fn name(&self) -> &'static str {
+        "verify"
+    }
+
+    fn run(&mut self, ctx: &mut crate::TypeInferenceContext, symbols: &mut crate::Symbols) -> bool {
+        verify(ctx, symbols, &self.target);
+        false
+    }
+}
+
+struct Verifier<'a> {
+    ctx: &'a mut crate::TypeInferenceContext,
+    target: &'a Target,
+    errors: usize,
+    /// Type parameters of the generic functions around
+    type_parameters: Vec<String>,
+    /// Is there a function to return from?
+    in_function: bool,
+    /// Number of loops to break out of, inside of the current function
+    loops: usize,
+    /// Variables, that were checked already
+    variables: std::collections::HashSet<*const std::sync::RwLock<crate::Variable>>,
+}
+
+impl Verifier<'_> {
+    fn report(&mut self, diagnostic: crate::Diagnostic) {
+        self.errors += 1;
+        self.ctx.diagnostics.report(diagnostic);
+    }
+
+    fn check_type(&mut self, r#type: &Type, span: Option<Span>, label: &str) {
+        if let Some(diagnostic) = self.type_error(r#type, &mut Vec::new()) {
+            self.report(diagnostic.with_label(span, label));
+        }
+    }
+
+    /// First problem with a type, structs are checked only once
+    fn type_error(
+        &self,
+        r#type: &Type,
+        structs: &mut Vec<*const std::sync::RwLock<crate::types::Struct>>,
+    ) -> Option<crate::Diagnostic> {
+        match r#type {
+            Type::Wildcard | Type::Variable(_) => Some(crate::Diagnostic::error(
+                "E0116",
+                format!("type `{}` is not fully inferred", r#type),
+            )),
+            Type::Parameter(name) if !self.type_parameters.contains(name) => {
+                Some(crate::Diagnostic::error(
+                    "E0116",
+                    format!("type parameter `{}` is not in scope", name),
+                ))
+            }
+            Type::Unresolved(name) => Some(crate::Diagnostic::error(
+                "E0117",
+                format!("cannot find type `{}`", name),
+            )),
+            Type::Integer(bits) | Type::Unsigned(bits)
+                if !self.target.integer_sizes.contains(bits) =>
+            {
+                Some(crate::Diagnostic::error(
+                    "E0118",
+                    format!("{}-bit integers are not supported by the target", bits),
+                ))
+            }
+            Type::Float(bits) if !self.target.float_sizes.contains(bits) => {
+                Some(crate::Diagnostic::error(
+                    "E0118",
+                    format!("{}-bit floats are not supported by the target", bits),
+                ))
+            }
+            Type::Pointer(r#type, _) | Type::Array(r#type, _) | Type::Slice(r#type, _) => {
+                self.type_error(r#type, structs)
+            }
+            Type::Fn(signature) => signature
+                .parameters
+                .iter()
+                .map(|(_, r#type)| r#type)
+                .chain(std::iter::once(signature.return_type.as_ref()))
+                .find_map(|r#type| self.type_error(r#type, structs)),
+            Type::Struct(r#struct) => {
+                if structs.contains(&std::sync::Arc::as_ptr(r#struct)) {
+                    return None;
+                }
+                structs.push(std::sync::Arc::as_ptr(r#struct));
+                let r#struct = r#struct.read().unwrap();
+                r#struct
+                    .fields
+                    .iter()
+                    .find_map(|(_, r#type)| self.type_error(r#type, structs))
+            }
+            _ => None,
+        }
+    }
+
+    /// Expressions, that type inference should have replaced
+    fn unresolved(&mut self, what: &str, span: Option<Span>) {
+        self.report(
+            crate::Diagnostic::error("E0122", format!("{} was not resolved", what))
+                .with_label(span, "should have been resolved by type inference"),
+        );
+    }
+}
+
+impl Visitor for Verifier<'_> {
+    fn visit_variable(&mut self, variable: &crate::Spanned<crate::ArcLock<crate::Variable>>) {
+        if self.variables.insert(std::sync::Arc::as_ptr(variable)) {
+            let r#type = variable.read().unwrap().r#type.clone();
+            self.check_type(&r#type, variable.span, "type of this variable");
+        }
+    }
+
+    fn visit_literal(&mut self, literal: &crate::Spanned<crate::expression::Literal>) {
+        self.check_type(literal.r#type(), literal.span, "type of this literal");
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        let type_parameters = self.type_parameters.len();
+        self.type_parameters
+            .extend(function.signature.type_parameters.iter().cloned());
+        for (_, r#type) in &function.signature.parameters {
+            self.check_type(r#type, function.span, "parameter of this function");
+        }
+        let return_type = function.signature.return_type.as_ref();
+        self.check_type(return_type, function.span, "return type of this function");
+        // Parameter variables have the same types as in the signature
+        for parameter in &function.parameters {
+            self.variables.insert(std::sync::Arc::as_ptr(parameter));
+        }
+
+        if function.signature.calling_convention == CallingConvention::Transparent {
+            function.walk(self);
+        } else {
+            let in_function = std::mem::replace(&mut self.in_function, true);
+            let loops = std::mem::replace(&mut self.loops, 0);
+            function.walk(self);
+            self.in_function = in_function;
+            self.loops = loops;
+        }
+        self.type_parameters.truncate(type_parameters);
+    }
+
+    fn visit_call(&mut self, call: &Call) {
+        let function = call.function.read().unwrap();
+        let generic = &function.signature.type_parameters;
+        if call.type_arguments.len() != generic.len() {
+            self.report(
+                crate::Diagnostic::error(
+                    "E0114",
+                    format!(
+                        "function takes {} type arguments, but {} were given",
+                        generic.len(),
+                        call.type_arguments.len()
+                    ),
+                )
+                .with_label(call.span, "in this call"),
+            );
+            return;
+        }
+        for r#type in &call.type_arguments {
+            self.check_type(r#type, call.span, "type argument of this call");
+        }
+        let signature = function.signature.instantiate(&call.type_arguments);
+        if call.args.len() != signature.parameters.len() {
+            self.report(
+                crate::Diagnostic::error(
+                    "E0114",
+                    format!(
+                        "function takes {} arguments, but {} were given",
+                        signature.parameters.len(),
+                        call.args.len()
+                    ),
+                )
+                .with_label(call.span, "in this call"),
+            );
+        }
+        for (arg, (_, expected)) in call.args.iter().zip(&signature.parameters) {
+            let found = arg.r#type();
+            if !same_type(expected, &found) {
+                self.report(
+                    crate::Diagnostic::error(
+                        "E0115",
+                        format!(
+                            "mismatched types: expected `{}`, found `{}`",
+                            expected, found
+                        ),
+                    )
+                    .with_label(arg.span().or(call.span), "argument of this type"),
+                );
+            }
+        }
+
+        let mut loops = 0;
+        match function.body {
+            FunctionBody::Intrinsic(Intrinsic::Return) if !self.in_function => self.report(
+                crate::Diagnostic::error("E0119", "return outside of a function")
+                    .with_label(call.span, "there is no function to return from"),
+            ),
+            FunctionBody::Intrinsic(Intrinsic::Break(depth) | Intrinsic::Continue(depth))
+                if depth >= self.loops =>
+            {
+                self.report(
+                    crate::Diagnostic::error(
+                        "E0120",
+                        format!("break or continue {} loops up, outside of a loop", depth),
+                    )
+                    .with_label(call.span, "there is no loop here"),
+                )
+            }
+            FunctionBody::Intrinsic(Intrinsic::Loop) => loops = 1,
+            _ => (),
+        }
+        // Backends build the bodies of branches and loops in place
+        let bodies = match function.body {
+            FunctionBody::Intrinsic(Intrinsic::Branch) => 1..call.args.len(),
+            FunctionBody::Intrinsic(Intrinsic::Loop) => 0..call.args.len().min(2),
+            _ => 0..0,
+        };
+        self.loops += loops;
+        for (index, arg) in call.args.iter().enumerate() {
+            match arg {
+                crate::Expression::Function(body) if bodies.contains(&index) => {
+                    self.visit_function(body)
+                }
+                arg => {
+                    if bodies.contains(&index) {
+                        self.report(
+                            crate::Diagnostic::error("E0124", "body has to be a function literal")
+                                .with_label(arg.span().or(call.span), "not a function literal"),
+                        );
+                    }
+                    self.visit_expression(arg);
+                }
+            }
+        }
+        self.loops -= loops;
+
+        // Transparent functions are a part of the caller
+        if let FunctionBody::Block(_) = function.body {
+            if function.signature.calling_convention == CallingConvention::Transparent {
+                self.visit_function(&function);
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &crate::Expression) {
+        if let crate::Expression::Function(function) = expression {
+            self.report(
+                crate::Diagnostic::error("E0125", "function used as a value").with_label(
+                    function.span,
+                    "only branch and loop bodies can be functions",
+                ),
+            );
+        }
+        expression.walk(self);
+    }
+
+    fn visit_trait_call(&mut self, call: &crate::expression::TraitCall) {
+        self.unresolved(&format!("trait call `{}`", call.method), call.span);
+        call.walk(self);
+    }
+
+    fn visit_field(&mut self, field: &FieldAccess) {
+        if field.field.is_none() {
+            self.unresolved(&format!("field `{}`", field.name), field.span);
+        }
+        field.walk(self);
+    }
+
+    fn visit_index(&mut self, index: &Index) {
+        if index.container.is_none() || index.index_type.is_none() {
+            self.unresolved("index", index.span);
+        }
+        index.walk(self);
+    }
+
+    fn visit_comptime(&mut self, comptime: &Comptime) {
+        self.unresolved("comptime block", comptime.span);
+        comptime.walk(self);
+    }
+
+    fn visit_error(&mut self, _: &crate::diagnostics::DiagnosticId) {
+        self.report(crate::Diagnostic::error(
+            "E0121",
+            "invalid expression reached codegen",
+        ));
+    }
+}
+
+/// Do the types match? Follows the rules of [crate::TypeInferenceContext::unify]:
+/// never matches anything and a mutable pointer can be passed where a const one is expected.
+/// Unlike unification, this doesn't touch the inference state, and wildcards and type variables
+/// don't match anything. Parameter names of function types are ignored
+fn same_type(expected: &Type, found: &Type) -> bool {
+    match (expected, found) {
+        (Type::Wildcard | Type::Variable(_), _) | (_, Type::Wildcard | Type::Variable(_)) => false,
+        (Type::Never, _) | (_, Type::Never) => true,
+        (Type::Fn(expected), Type::Fn(found)) => {
+            expected.type_parameters == found.type_parameters
+                && expected.calling_convention == found.calling_convention
+                && expected.parameters.len() == found.parameters.len()
+                && expected
+                    .parameters
+                    .iter()
+                    .zip(&found.parameters)
+                    .all(|((_, expected), (_, found))| same_type(expected, found))
+                && same_type(&expected.return_type, &found.return_type)
+        }
+        (Type::Pointer(expected, expected_mut), Type::Pointer(found, found_mut))
+        | (Type::Slice(expected, expected_mut), Type::Slice(found, found_mut)) => {
+            expected_mut <= found_mut && same_type(expected, found)
+        }
+        (Type::Array(expected, expected_len), Type::Array(found, found_len)) => {
+            expected_len == found_len && same_type(expected, found)
+        }
+        (expected, found) => expected == found,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    fn codes(source: &str) -> Vec<&'static str> {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let symbols = crate::parser::parse_unit(&mut ctx, source).unwrap();
+        let valid = verify(&mut ctx, &symbols, &Target::default());
+        let codes = ctx
+            .diagnostics
+            .iter()
+            .filter_map(|diagnostic| diagnostic.code)
+            .collect::<Vec<_>>();
+        check!(valid == codes.is_empty());
+        codes
+    }
+
+    #[test]
+    fn test_valid() {
+        check!(codes(
+            "\
+const main = fn main(x: i32) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (lhs: i32, rhs: i32) -> i32 transparent = add)(x: i32, 1i32));
+};
+"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_calls() {
+        check!(
+            codes(
+                "\
+const main = fn main(x: i32) -> i32 fastest {
+    (fn (lhs: i32, rhs: i32) -> i32 transparent = add)(x: i32);
+    (fn (lhs: i32, rhs: i32) -> i32 transparent = add)(x: i32, true);
+    (fn (value: i32) -> ! transparent = return)(x: i32);
+};
+"
+            ) == ["E0114", "E0115"]
+        );
+    }
+
+    #[test]
+    fn test_types() {
+        check!(
+            codes(
+                "\
+const main = fn main(x: i7, y: 'size_t') -> () fastest {
+    z: _;
+    (fn () -> ! transparent = break)();
+};
+"
+            ) == ["E0118", "E0117", "E0116", "E0120"]
+        );
+    }
+
+    #[test]
+    fn test_bodies() {
+        check!(
+            codes(
+                "\
+const main = fn main(x: bool, body: fn () -> () transparent) -> () fastest {
+    (fn (cond: bool, then: fn () -> () transparent, else: fn () -> () transparent) -> () transparent = branch)(x: bool, body: fn () -> () transparent, fn () -> () transparent {
+    });
+};
+"
+            ) == ["E0124"]
+        );
+    }
+
+    #[test]
+    fn test_return_outside_of_function() {
+        check!(
+            codes(
+                "\
+const value = (fn (value: i32) -> ! transparent = return)(1i32);
+"
+            ) == ["E0126", "E0119"]
+        );
+    }
+
+    #[test]
+    fn test_same_type() {
+        check!(same_type(&Type::Integer(32), &Type::Integer(32)));
+        check!(!same_type(&Type::Integer(32), &Type::Wildcard));
+        check!(same_type(&Type::Never, &Type::Integer(32)));
+        check!(same_type(&Type::Integer(32), &Type::Never));
+        check!(!same_type(&Type::Never, &Type::Wildcard));
+        let mut ctx = crate::TypeInferenceContext::new();
+        let mut parse = |r#type| crate::parser::parse_type(&mut ctx, r#type).unwrap();
+        let lhs = parse("fn (a: *mut [i32; 2]) -> () fastest");
+        let rhs = parse("fn (b: *mut [i32; 2]) -> () fastest");
+        check!(same_type(&lhs, &rhs));
+        let rhs = parse("fn (b: *const [i32; 2]) -> () fastest");
+        check!(!same_type(&lhs, &rhs));
+        check!(same_type(&rhs, &lhs));
+    }
+
+    #[test]
+    fn test_pointers() {
+        // `*p` with `int *p` and `const int *q = NULL;`
+        check!(codes(
+            "\
+const get = fn get(p: *mut i32) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (pointer: *const i32) -> i32 transparent = load)(p: *mut i32));
+};
+const null = fn null() -> () fastest {
+    (fn (target: *const i32, value: *const i32) -> *const i32 transparent = assign)(q: *const i32, null as *mut i32);
+};
+"
+        )
+        .is_empty());
+        check!(
+            codes(
+                "\
+const set = fn set(p: *const i32) -> () fastest {
+    (fn (pointer: *mut i32, value: i32) -> () transparent = store)(p: *const i32, 1i32);
+};
+"
+            ) == ["E0115"]
+        );
+    }
+
+    #[test]
+    fn test_never() {
+        // Both branches return, so the branch itself never finishes
+        check!(codes(
+            "\
+const pick = fn pick(x: bool) -> i32 fastest {
+    (fn (value: i32) -> ! transparent = return)((fn (cond: bool, then: fn () -> ! transparent, else: fn () -> ! transparent) -> ! transparent = branch)(x: bool, fn () -> ! transparent {
+        (fn (value: i32) -> ! transparent = return)(1i32);
+    }, fn () -> ! transparent {
+        (fn (value: i32) -> ! transparent = return)(2i32);
+    }));
+};
+"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_values() {
+        check!(
+            codes(
+                "\
+const answer = 42i32;
+const apply = fn apply(f: fn () -> () transparent) -> () fastest {
+};
+const main = fn main() -> () fastest {
+    apply(fn () -> () transparent {
+    });
+};
+"
+            ) == ["E0126", "E0125"]
+        );
+    }
+}
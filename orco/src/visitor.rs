@@ -0,0 +1,171 @@
+use crate::expression::function::FunctionBody;
+use crate::expression::*;
+pub use crate::expression::{Visitor, VisitorMut};
+
+/// Walk into the children of an IR node, calling the visitor on each of them.
+/// Visitor methods call this by default, override a method
+/// and call this from it to keep walking
+pub trait Walk {
+    /// Visit the children of this node
+    fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V);
+    /// Visit the children of this node mutably
+    fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V);
+}
+
+/// Leaves of the IR, that have no children to walk into
+macro_rules! leaf {
+    ($($type:ty),*) => {
+        $(impl Walk for $type {
+            fn walk<V: Visitor + ?Sized>(&self, _visitor: &mut V) {}
+            fn walk_mut<V: VisitorMut + ?Sized>(&mut self, _visitor: &mut V) {}
+        })*
+    };
+}
+
+leaf!(
+    crate::Spanned<Literal>,
+    crate::Spanned<crate::ArcLock<crate::Variable>>,
+    crate::diagnostics::DiagnosticId
+);
+
+/// Walks into the body of the function.
+/// Called functions aren't walked into from the calls, they are separate symbols
+impl Walk for Function {
+    fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        if let FunctionBody::Block(body) = &self.body {
+            body.iter()
+                .for_each(|expression| visitor.visit_expression(expression));
+        }
+    }
+
+    fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        if let FunctionBody::Block(body) = &mut self.body {
+            body.iter_mut()
+                .for_each(|expression| visitor.visit_expression(expression));
+        }
+    }
+}
+
+impl Walk for Call {
+    fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        self.args
+            .iter()
+            .for_each(|arg| visitor.visit_expression(arg));
+    }
+
+    fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        self.args
+            .iter_mut()
+            .for_each(|arg| visitor.visit_expression(arg));
+    }
+}
+
+impl Walk for TraitCall {
+    fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        self.args
+            .iter()
+            .for_each(|arg| visitor.visit_expression(arg));
+    }
+
+    fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        self.args
+            .iter_mut()
+            .for_each(|arg| visitor.visit_expression(arg));
+    }
+}
+
+impl Walk for FieldAccess {
+    fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_expression(&self.value);
+    }
+
+    fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_expression(&mut self.value);
+    }
+}
+
+impl Walk for Index {
+    fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_expression(&self.value);
+        visitor.visit_expression(&self.index);
+    }
+
+    fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_expression(&mut self.value);
+        visitor.visit_expression(&mut self.index);
+    }
+}
+
+impl Walk for Comptime {
+    fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        self.body
+            .iter()
+            .for_each(|expression| visitor.visit_expression(expression));
+    }
+
+    fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        self.body
+            .iter_mut()
+            .for_each(|expression| visitor.visit_expression(expression));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::*;
+
+    /// Counts the calls and the nested functions
+    #[derive(Default)]
+    struct Counter {
+        calls: usize,
+        functions: usize,
+    }
+
+    impl Visitor for Counter {
+        fn visit_call(&mut self, call: &Call) {
+            self.calls += 1;
+            call.walk(self);
+        }
+
+        fn visit_function(&mut self, function: &Function) {
+            self.functions += 1;
+            function.walk(self);
+        }
+    }
+
+    /// Replaces every literal with zero
+    struct Zero;
+
+    impl VisitorMut for Zero {
+        fn visit_literal(&mut self, literal: &mut crate::Spanned<Literal>) {
+            literal.inner = Literal::Integer(0, crate::Type::Integer(32));
+        }
+    }
+
+    #[test]
+    fn test_visitor() {
+        let mut ctx = crate::TypeInferenceContext::new();
+        let mut symbols = crate::parser::parse_unit(
+            &mut ctx,
+            "const branch = fn branch(x: i32) -> i32 fastest {
+    (fn (cond: bool, then: fn () -> i32 transparent, else: fn () -> i32 transparent) -> i32 transparent = branch)((fn (lhs: i32, rhs: i32) -> bool transparent = lt)(x: i32, 1i32), fn () -> i32 transparent {
+        1i32;
+    }, fn () -> i32 transparent {
+        2i32;
+    });
+};
+",
+        )
+        .unwrap();
+
+        let mut counter = Counter::default();
+        symbols["branch"].walk(&mut counter);
+        check!(counter.calls == 2);
+        check!(counter.functions == 3);
+
+        let branch = symbols.get_mut("branch").unwrap();
+        branch.walk_mut(&mut Zero);
+        check!(branch.to_string().matches("0i32").count() == 3);
+    }
+}